@@ -0,0 +1,206 @@
+//! Lists and deletes the snapshots, recordings and captures the app has
+//! saved. They share the downloads folder with everything else, so only
+//! files named the way the app names them count.
+
+use std::{
+    cmp::Reverse,
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    Snapshot,
+    Recording,
+    Capture,
+}
+
+impl ArtifactKind {
+    const ALL: [ArtifactKind; 3] = [
+        ArtifactKind::Snapshot,
+        ArtifactKind::Recording,
+        ArtifactKind::Capture,
+    ];
+
+    /// The start of the file name, before the timestamp.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            ArtifactKind::Snapshot => "traffic-snapshot",
+            ArtifactKind::Recording => "traffic-timelapse",
+            ArtifactKind::Capture => "traffic-capture",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArtifactKind::Snapshot | ArtifactKind::Recording => "json",
+            ArtifactKind::Capture => "png",
+        }
+    }
+}
+
+/// A saved file, as returned by `list_artifacts`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Artifact {
+    pub path: String,
+    pub kind: ArtifactKind,
+    pub size: u64,
+    /// Milliseconds since the Unix epoch.
+    pub created_ms: u64,
+    /// Snapshots only.
+    pub seed: Option<u64>,
+    pub tick: Option<u64>,
+}
+
+/// The fields of a snapshot worth showing in a list.
+#[derive(Deserialize)]
+struct SnapshotSummary {
+    seed: u64,
+    tick: u64,
+}
+
+/// What kind of artifact a file named `name` is, if it's one at all: the
+/// kind's prefix, a dash, a timestamp and the kind's extension.
+fn kind_of(name: &str) -> Option<ArtifactKind> {
+    ArtifactKind::ALL.into_iter().find(|kind| {
+        name.strip_prefix(kind.prefix())
+            .and_then(|rest| rest.strip_prefix('-'))
+            .and_then(|rest| rest.strip_suffix(kind.extension()))
+            .and_then(|rest| rest.strip_suffix('.'))
+            .is_some_and(|secs| !secs.is_empty() && secs.bytes().all(|b| b.is_ascii_digit()))
+    })
+}
+
+/// Where artifacts are saved; see `snapshot::download_path`.
+fn dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    app.path()
+        .download_dir()
+        .map_err(|e| AppError::Platform(e.to_string()))
+}
+
+fn describe(path: &Path, kind: ArtifactKind) -> Option<Artifact> {
+    let metadata = fs::metadata(path).ok().filter(|m| m.is_file())?;
+    // Not every filesystem records creation times.
+    let created = metadata.created().or_else(|_| metadata.modified()).ok();
+    let created_ms = created
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64);
+    let summary = (kind == ArtifactKind::Snapshot)
+        .then(|| fs::read(path).ok())
+        .flatten()
+        .and_then(|contents| serde_json::from_slice::<SnapshotSummary>(&contents).ok());
+    Some(Artifact {
+        path: path.display().to_string(),
+        kind,
+        size: metadata.len(),
+        created_ms,
+        seed: summary.as_ref().map(|s| s.seed),
+        tick: summary.as_ref().map(|s| s.tick),
+    })
+}
+
+/// Every artifact in `dir`, newest first. A missing folder has none.
+fn scan(dir: &Path) -> Vec<Artifact> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::debug!("no artifacts in {}: {e}", dir.display());
+            return Vec::new();
+        }
+    };
+    let mut artifacts: Vec<Artifact> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let kind = kind_of(entry.file_name().to_str()?)?;
+            describe(&entry.path(), kind)
+        })
+        .collect();
+    artifacts.sort_by_key(|a| Reverse(a.created_ms));
+    artifacts
+}
+
+/// Lists the saved snapshots, recordings and captures, newest first.
+#[tauri::command]
+pub async fn list_artifacts(app: AppHandle) -> Vec<Artifact> {
+    let dir = match dir(&app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::warn!("can't find the downloads folder: {e}");
+            return Vec::new();
+        }
+    };
+    // Reads every snapshot, so keep it off the main thread.
+    tauri::async_runtime::spawn_blocking(move || scan(&dir))
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("failed to list artifacts: {e}");
+            Vec::new()
+        })
+}
+
+/// Deletes a file `list_artifacts` returned. Anything else, including paths
+/// that lead out of the folder through `..` or links, is refused.
+#[tauri::command]
+pub fn delete_artifact(app: AppHandle, path: String) -> Result<(), AppError> {
+    let refuse = || AppError::InvalidParam(format!("{path} is not a saved artifact"));
+    let dir = dir(&app)?.canonicalize().map_err(|_| refuse())?;
+    let file = Path::new(&path).canonicalize().map_err(|_| refuse())?;
+    let name = file
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(refuse)?;
+    if file.parent() != Some(dir.as_path()) || kind_of(name).is_none() || !file.is_file() {
+        return Err(refuse());
+    }
+    fs::remove_file(&file)
+        .map_err(|e| AppError::Io(format!("failed to delete {}: {e}", file.display())))?;
+    log::info!("deleted {}", file.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_the_apps_own_file_names() {
+        assert_eq!(
+            kind_of("traffic-snapshot-1700000000.json"),
+            Some(ArtifactKind::Snapshot)
+        );
+        assert_eq!(
+            kind_of("traffic-timelapse-1700000000.json"),
+            Some(ArtifactKind::Recording)
+        );
+        assert_eq!(
+            kind_of("traffic-capture-1700000000.png"),
+            Some(ArtifactKind::Capture)
+        );
+    }
+
+    #[test]
+    fn ignores_other_files() {
+        for name in [
+            "traffic-snapshot.json",
+            "traffic-snapshot-.json",
+            "traffic-snapshot-1700000000.png",
+            "traffic-capture-1700000000 (1).png",
+            "traffic-snapshot-latest.json",
+            "report.json",
+        ] {
+            assert_eq!(kind_of(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn a_missing_folder_has_no_artifacts() {
+        assert!(scan(Path::new("/nonexistent/traffic-artifacts")).is_empty());
+    }
+}
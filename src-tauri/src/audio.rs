@@ -0,0 +1,282 @@
+//! Makes traffic react to how loud the room is: the microphone's level
+//! scales the spawn rate and vehicle speed while `audio_reactive` is on.
+
+use std::{
+    ops::RangeInclusive,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, SampleFormat, SizedSample, Stream, StreamConfig,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    error::AppError,
+    settings::{self, Settings, SettingsState, DEFAULT_AUDIO_SENSITIVITY},
+    sim::{SimParams, SPAWN_RATE_RANGE, SPEED_MULTIPLIER_RANGE},
+};
+
+pub const SENSITIVITY_RANGE: RangeInclusive<f64> = 0.1..=10.0;
+/// Weight of the newest buffer in the rolling level.
+const SMOOTHING: f32 = 0.2;
+/// RMS level that counts as full volume at sensitivity 1; about someone
+/// talking near the microphone.
+const FULL_LEVEL: f64 = 0.1;
+/// What silence and full volume multiply the spawn rate and speed by.
+const MIN_SCALE: f64 = 0.25;
+const MAX_SCALE: f64 = 3.0;
+/// macOS hands a refused app silence instead of an error, so a stream that
+/// hasn't produced a single non-zero sample by then was refused.
+const SILENCE_CHECK: Duration = Duration::from_secs(2);
+
+/// The capture thread, which owns the input stream.
+struct Capture {
+    /// Sending, or dropping, ends the thread and releases the microphone.
+    stop: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+pub struct AudioState {
+    /// The rolling RMS level as `f32` bits, written by the stream.
+    level: Arc<AtomicU32>,
+    /// Whether a stream is running and `level` is live.
+    active: Arc<AtomicBool>,
+    sensitivity: Mutex<f64>,
+    capture: Mutex<Option<Capture>>,
+}
+
+impl Default for AudioState {
+    fn default() -> Self {
+        Self {
+            level: Arc::default(),
+            active: Arc::default(),
+            sensitivity: Mutex::new(DEFAULT_AUDIO_SENSITIVITY),
+            capture: Mutex::default(),
+        }
+    }
+}
+
+enum OpenError {
+    NoDevice,
+    Refused(String),
+}
+
+fn rms(samples: impl ExactSizeIterator<Item = f32>) -> f32 {
+    let n = samples.len();
+    if n == 0 {
+        return 0.0;
+    }
+    (samples.map(|s| s * s).sum::<f32>() / n as f32).sqrt()
+}
+
+/// What `level` multiplies the spawn rate and speed by.
+fn scale(level: f32, sensitivity: f64) -> f64 {
+    let loudness = (level as f64 * sensitivity / FULL_LEVEL).min(1.0);
+    MIN_SCALE + (MAX_SCALE - MIN_SCALE) * loudness
+}
+
+fn build<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    level: Arc<AtomicU32>,
+    heard: Arc<AtomicBool>,
+) -> Result<Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            let samples = data.iter().map(|s| s.to_sample::<f32>());
+            if !heard.load(Ordering::Relaxed) && samples.clone().any(|s| s != 0.0) {
+                heard.store(true, Ordering::Relaxed);
+            }
+            let prev = f32::from_bits(level.load(Ordering::Relaxed));
+            let next = prev + SMOOTHING * (rms(samples) - prev);
+            level.store(next.to_bits(), Ordering::Relaxed);
+        },
+        |e| log::warn!("microphone stream error: {e}"),
+        None,
+    )
+}
+
+/// Starts listening to the default input device. This is what asks for
+/// microphone permission where the OS wants it.
+fn open(level: Arc<AtomicU32>, heard: Arc<AtomicBool>) -> Result<Stream, OpenError> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or(OpenError::NoDevice)?;
+    let refused = |e: &dyn std::fmt::Display| OpenError::Refused(e.to_string());
+    let supported = device.default_input_config().map_err(|e| refused(&e))?;
+    let config = supported.config();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build::<f32>(&device, &config, level, heard),
+        SampleFormat::I16 => build::<i16>(&device, &config, level, heard),
+        SampleFormat::U16 => build::<u16>(&device, &config, level, heard),
+        other => {
+            return Err(OpenError::Refused(format!(
+                "unsupported sample format {other}"
+            )))
+        }
+    }
+    .map_err(|e| refused(&e))?;
+    stream.play().map_err(|e| refused(&e))?;
+    Ok(stream)
+}
+
+/// Tells the UI the microphone is off limits and turns the mode off, so it
+/// isn't asked again at every launch.
+fn refused(app: &AppHandle, reason: &str) {
+    log::warn!("microphone unavailable: {reason}");
+    if let Err(e) = app.emit("audio-permission-denied", reason) {
+        log::error!("failed to emit audio-permission-denied: {e}");
+    }
+    if let Err(e) = settings::update(app, |s| s.audio_reactive = false) {
+        log::error!("failed to turn off audio-reactive traffic: {e}");
+    }
+}
+
+/// Runs on the capture thread until `stop` fires, then drops the stream.
+fn listen(app: AppHandle, stop: mpsc::Receiver<()>) {
+    let state = app.state::<AudioState>();
+    let heard = Arc::new(AtomicBool::new(false));
+    let stream = match open(state.level.clone(), heard.clone()) {
+        Ok(stream) => stream,
+        Err(OpenError::NoDevice) => {
+            log::warn!("no microphone found, traffic won't follow the sound");
+            return;
+        }
+        Err(OpenError::Refused(reason)) => return refused(&app, &reason),
+    };
+    state.active.store(true, Ordering::Relaxed);
+    log::info!("listening to the microphone");
+    if cfg!(target_os = "macos") {
+        match stop.recv_timeout(SILENCE_CHECK) {
+            Err(RecvTimeoutError::Timeout) if !heard.load(Ordering::Relaxed) => {
+                drop(stream);
+                state.active.store(false, Ordering::Relaxed);
+                return refused(&app, "microphone access was refused");
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => {}
+        }
+    }
+    // Either way, the sender going away means stop.
+    let _ = stop.recv();
+    drop(stream);
+    state.active.store(false, Ordering::Relaxed);
+    state.level.store(0, Ordering::Relaxed);
+    log::info!("stopped listening to the microphone");
+}
+
+fn start(app: &AppHandle) {
+    let state = app.state::<AudioState>();
+    let mut capture = state.capture.lock().unwrap();
+    if capture.as_ref().is_some_and(|c| !c.thread.is_finished()) {
+        return;
+    }
+    let (stop, rx) = mpsc::channel();
+    let handle = app.clone();
+    let thread = thread::spawn(move || listen(handle, rx));
+    *capture = Some(Capture { stop, thread });
+}
+
+/// Stops listening, waiting until the stream is dropped so the OS's
+/// microphone indicator goes off.
+fn stop(app: &AppHandle) {
+    let Some(capture) = app.state::<AudioState>().capture.lock().unwrap().take() else {
+        return;
+    };
+    // The thread may already have ended on its own.
+    let _ = capture.stop.send(());
+    if capture.thread.join().is_err() {
+        log::error!("the microphone thread panicked");
+    }
+}
+
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    *app.state::<AudioState>().sensitivity.lock().unwrap() =
+        if settings.audio_sensitivity.is_finite() {
+            settings
+                .audio_sensitivity
+                .clamp(*SENSITIVITY_RANGE.start(), *SENSITIVITY_RANGE.end())
+        } else {
+            DEFAULT_AUDIO_SENSITIVITY
+        };
+    if settings.audio_reactive {
+        start(app);
+    } else {
+        stop(app);
+    }
+}
+
+/// Scales the spawn rate and speed by the microphone's level while it's
+/// being listened to.
+pub fn adjust(app: &AppHandle, params: SimParams) -> SimParams {
+    let state = app.state::<AudioState>();
+    if !state.active.load(Ordering::Relaxed) {
+        return params;
+    }
+    let level = f32::from_bits(state.level.load(Ordering::Relaxed));
+    let scale = scale(level, *state.sensitivity.lock().unwrap());
+    SimParams {
+        spawn_rate: (params.spawn_rate * scale)
+            .clamp(*SPAWN_RATE_RANGE.start(), *SPAWN_RATE_RANGE.end()),
+        speed_multiplier: (params.speed_multiplier * scale).clamp(
+            *SPEED_MULTIPLIER_RANGE.start(),
+            *SPEED_MULTIPLIER_RANGE.end(),
+        ),
+        ..params
+    }
+}
+
+/// Turns microphone-driven traffic on or off. `sensitivity` multiplies the
+/// level, so higher values need less noise for busy roads. Emits
+/// `audio-permission-denied` if the microphone can't be used.
+#[tauri::command]
+pub fn set_audio_reactive(app: AppHandle, enabled: bool, sensitivity: f64) -> Result<(), AppError> {
+    if !SENSITIVITY_RANGE.contains(&sensitivity) {
+        return Err(AppError::InvalidParam(format!(
+            "sensitivity must be between {} and {}, got {sensitivity}",
+            SENSITIVITY_RANGE.start(),
+            SENSITIVITY_RANGE.end()
+        )));
+    }
+    settings::update(&app, |s| {
+        s.audio_reactive = enabled;
+        s.audio_sensitivity = sensitivity;
+    })
+    .map_err(AppError::Io)?;
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    load_settings(&app, &settings);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rms_of_a_square_wave_is_its_amplitude() {
+        let samples = [0.5, -0.5, 0.5, -0.5];
+        assert!((rms(samples.into_iter()) - 0.5).abs() < 1e-6);
+        assert_eq!(rms(std::iter::empty()), 0.0);
+    }
+
+    #[test]
+    fn scale_runs_from_silence_to_full_volume() {
+        assert_eq!(scale(0.0, 1.0), MIN_SCALE);
+        assert_eq!(scale(FULL_LEVEL as f32 * 2.0, 1.0), MAX_SCALE);
+        // Twice as sensitive, half as loud.
+        assert!((scale(0.025, 2.0) - scale(0.05, 1.0)).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,71 @@
+//! Tells a login launch from a manual one, so the overlay can start quietly
+//! in the tray at login.
+
+#[cfg(desktop)]
+use tauri::AppHandle;
+use tauri::State;
+#[cfg(desktop)]
+use tauri_plugin_autostart::ManagerExt;
+
+use crate::cli::CliArgs;
+
+/// Passed by the login entry the autostart plugin registers.
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+/// Whether this session was started by the OS login launcher.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LaunchedByAutostart(pub bool);
+
+/// Environment markers set by the login launchers, for entries registered
+/// before [`AUTOSTART_ARG`] was added.
+fn env_marker() -> Option<&'static str> {
+    let var = |name| std::env::var(name).ok().filter(|v| !v.is_empty());
+    // gnome-session and other XDG autostart runners.
+    if cfg!(target_os = "linux") && var("DESKTOP_AUTOSTART_ID").is_some() {
+        return Some("DESKTOP_AUTOSTART_ID");
+    }
+    // launchd names the job after the LaunchAgent's label; apps opened from
+    // Finder or the Dock get an "application." one instead.
+    if cfg!(target_os = "macos")
+        && var("XPC_SERVICE_NAME")
+            .is_some_and(|name| name != "0" && !name.starts_with("application."))
+    {
+        return Some("XPC_SERVICE_NAME");
+    }
+    None
+}
+
+/// Works out how the app was launched and logs which way it was detected.
+pub fn detect(args: &CliArgs) -> LaunchedByAutostart {
+    if args.autostart {
+        log::info!("launched by autostart ({AUTOSTART_ARG})");
+        return LaunchedByAutostart(true);
+    }
+    if let Some(marker) = env_marker() {
+        log::info!("launched by autostart ({marker} is set)");
+        return LaunchedByAutostart(true);
+    }
+    log::info!("launched manually");
+    LaunchedByAutostart(false)
+}
+
+/// Rewrites an existing login entry so it passes [`AUTOSTART_ARG`]. Windows'
+/// Run key leaves no other trace of how the app was started.
+#[cfg(desktop)]
+pub fn refresh(app: &AppHandle) {
+    let autolaunch = app.autolaunch();
+    match autolaunch.is_enabled() {
+        Ok(true) => {
+            if let Err(e) = autolaunch.enable() {
+                log::warn!("failed to refresh the autostart entry: {e}");
+            }
+        }
+        Ok(false) => {}
+        Err(e) => log::warn!("failed to check autostart: {e}"),
+    }
+}
+
+#[tauri::command]
+pub fn launched_by_autostart(state: State<LaunchedByAutostart>) -> bool {
+    state.0
+}
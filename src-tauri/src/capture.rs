@@ -0,0 +1,117 @@
+//! Saves a PNG of what an overlay window is currently showing.
+
+use std::{path::PathBuf, thread, time::Duration};
+
+use tauri::{Manager, WebviewWindow};
+use xcap::{
+    image::{imageops, RgbaImage},
+    Monitor,
+};
+
+use crate::{artifacts::ArtifactKind, error::AppError, overlay, snapshot};
+
+/// Gives the compositor time to redraw after the window is raised.
+const SETTLE_DELAY: Duration = Duration::from_millis(150);
+
+/// Fails early with an actionable message instead of capturing a blank desktop.
+#[cfg(target_os = "macos")]
+fn check_permission() -> Result<(), AppError> {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    if unsafe { CGPreflightScreenCaptureAccess() } {
+        return Ok(());
+    }
+    // Shows the system prompt the first time; afterwards it's a no-op.
+    unsafe { CGRequestScreenCaptureAccess() };
+    Err(AppError::Platform(
+        "screen recording permission denied; allow it under System Settings > \
+         Privacy & Security > Screen Recording"
+            .into(),
+    ))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn check_permission() -> Result<(), AppError> {
+    Ok(())
+}
+
+/// Captures the monitor under the window and crops it to the window's bounds.
+fn grab(window: &WebviewWindow) -> Result<RgbaImage, AppError> {
+    thread::sleep(SETTLE_DELAY);
+    let position = window.outer_position()?;
+    let size = window.outer_size()?;
+    let monitor = window
+        .current_monitor()?
+        .ok_or_else(|| AppError::Platform("window is not on any monitor".into()))?;
+
+    // xcap finds monitors by points on macOS and by pixels elsewhere.
+    let center_x = position.x + size.width as i32 / 2;
+    let center_y = position.y + size.height as i32 / 2;
+    let (x, y) = if cfg!(target_os = "macos") {
+        let scale = monitor.scale_factor();
+        (
+            (center_x as f64 / scale) as i32,
+            (center_y as f64 / scale) as i32,
+        )
+    } else {
+        (center_x, center_y)
+    };
+    let screen = Monitor::from_point(x, y).map_err(|e| AppError::Platform(e.to_string()))?;
+    let image = screen
+        .capture_image()
+        .map_err(|e| AppError::Platform(format!("screen capture failed: {e}")))?;
+
+    // Map window pixels onto the image, whatever resolution it came back in.
+    let ratio = image.width() as f64 / monitor.size().width as f64;
+    let origin = monitor.position();
+    let scaled = |v: i64| (v.max(0) as f64 * ratio) as u32;
+    let left = scaled((position.x - origin.x).into()).min(image.width());
+    let top = scaled((position.y - origin.y).into()).min(image.height());
+    let width = scaled(size.width.into()).min(image.width() - left);
+    let height = scaled(size.height.into()).min(image.height() - top);
+    if width == 0 || height == 0 {
+        return Err(AppError::Platform("window is off screen".into()));
+    }
+    Ok(imageops::crop_imm(&image, left, top, width, height).to_image())
+}
+
+#[tauri::command]
+pub async fn capture_overlay(
+    window: WebviewWindow,
+    path: Option<String>,
+) -> Result<String, AppError> {
+    check_permission()?;
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => snapshot::download_path(window.app_handle(), ArtifactKind::Capture)
+            .map_err(AppError::Io)?,
+    };
+
+    let click_through = overlay::click_through_of(&window);
+    // Raised and taking input, nothing else is drawn over the window.
+    overlay::set_ignore_cursor_events(&window, false)?;
+    window.set_always_on_top(true)?;
+
+    let target = window.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || grab(&target))
+        .await
+        .map_err(AppError::from)
+        .and_then(|r| r);
+
+    if let Err(e) = overlay::set_ignore_cursor_events(&window, click_through) {
+        log::error!("failed to restore click-through: {e}");
+    }
+    if let Err(e) = overlay::restore_level(&window) {
+        log::error!("failed to restore always-on-top: {e}");
+    }
+
+    result?
+        .save(&path)
+        .map_err(|e| AppError::Io(format!("failed to write {}: {e}", path.display())))?;
+    log::info!("saved capture to {}", path.display());
+    Ok(path.display().to_string())
+}
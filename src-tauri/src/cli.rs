@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+
+use crate::deeplink;
+
+pub const USAGE: &str = "\
+Usage: shibuya-traffic [OPTIONS]
+
+Options:
+  --hidden           Start minimized to the tray
+  --click-through    Start with click-through enabled
+  --autostart        Set by the login entry; see start_hidden_on_autostart
+  --config <PATH>    Load settings from PATH instead of the default location
+  -v, --verbose      Log debug output (overrides RUST_LOG)
+  -h, --help         Print this help";
+
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    pub hidden: bool,
+    pub click_through: bool,
+    pub autostart: bool,
+    pub config: Option<PathBuf>,
+    pub verbose: bool,
+}
+
+pub enum Parsed {
+    Run(CliArgs),
+    Help,
+}
+
+/// Parses command line arguments, excluding the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Parsed, String> {
+    let mut parsed = CliArgs::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--hidden" => parsed.hidden = true,
+            "--click-through" => parsed.click_through = true,
+            "--autostart" => parsed.autostart = true,
+            "--config" => {
+                let path = args.next().ok_or("--config requires a path")?;
+                parsed.config = Some(PathBuf::from(path));
+            }
+            "-v" | "--verbose" => parsed.verbose = true,
+            "-h" | "--help" => return Ok(Parsed::Help),
+            // Older macOS versions pass a process serial number when launched from Finder.
+            arg if arg.starts_with("-psn_") => {}
+            // Windows and Linux open links by launching the app with the URL;
+            // the deep-link plugin reads it from there.
+            arg if is_link(arg) => {}
+            arg => return Err(format!("unknown argument: {arg}")),
+        }
+    }
+    Ok(Parsed::Run(parsed))
+}
+
+fn is_link(arg: &str) -> bool {
+    arg.strip_prefix(deeplink::SCHEME)
+        .is_some_and(|rest| rest.starts_with("://"))
+}
+
+/// Parses `std::env::args`, printing usage and exiting on `--help` or bad input.
+pub fn parse_env() -> CliArgs {
+    let args = match parse(std::env::args().skip(1)) {
+        Ok(Parsed::Run(args)) => args,
+        Ok(Parsed::Help) => {
+            println!("{USAGE}");
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    if let Some(path) = &args.config {
+        if !path.is_file() {
+            eprintln!("error: config file not found: {}", path.display());
+            std::process::exit(1);
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Result<CliArgs, String> {
+        match parse(args.iter().map(|a| a.to_string()))? {
+            Parsed::Run(args) => Ok(args),
+            Parsed::Help => Err("help".into()),
+        }
+    }
+
+    #[test]
+    fn reads_flags_and_config() {
+        let args = run(&["--hidden", "-v", "--config", "/tmp/s.json"]).unwrap();
+        assert!(args.hidden && args.verbose && !args.click_through);
+        assert_eq!(args.config, Some(PathBuf::from("/tmp/s.json")));
+    }
+
+    #[test]
+    fn config_needs_a_path() {
+        assert!(run(&["--config"]).is_err());
+    }
+
+    #[test]
+    fn help_wins() {
+        assert!(matches!(
+            parse(["--hidden".to_string(), "-h".to_string()]),
+            Ok(Parsed::Help)
+        ));
+    }
+
+    #[test]
+    fn launch_links_are_skipped() {
+        let args = run(&["traffic://preset?speed=2&seed=1", "--click-through"]).unwrap();
+        assert!(args.click_through);
+    }
+
+    #[test]
+    fn finder_serial_numbers_are_skipped() {
+        assert!(run(&["-psn_0_12345"]).is_ok());
+    }
+
+    #[test]
+    fn unknown_arguments_are_rejected() {
+        let err = run(&["--bogus"]).unwrap_err();
+        assert!(err.contains("--bogus"), "{err}");
+        // Only the registered scheme counts as a link.
+        assert!(run(&["https://example.com"]).is_err());
+        assert!(run(&["trafficx://preset"]).is_err());
+    }
+}
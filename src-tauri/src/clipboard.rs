@@ -0,0 +1,85 @@
+//! Shares settings and presets as JSON through the clipboard.
+
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::{
+    error::AppError,
+    presets::{self, Preset},
+    settings::{self, Settings, SettingsState},
+};
+
+/// What a pasted document turned out to be.
+enum Pasted {
+    Settings(Box<Settings>),
+    Preset(Preset),
+}
+
+/// Settings documents carry a `version`; presets don't but always have a
+/// `name`. Anything else is rejected rather than guessed at.
+fn parse(contents: &str) -> Result<Pasted, String> {
+    let doc: Value = serde_json::from_str(contents)
+        .map_err(|e| format!("the clipboard doesn't hold valid JSON: {e}"))?;
+    let object = doc
+        .as_object()
+        .ok_or("the clipboard JSON isn't an object")?;
+    if object.contains_key("version") {
+        let settings = Settings::parse(contents)
+            .map_err(|e| format!("the clipboard doesn't hold valid settings: {e}"))?;
+        return Ok(Pasted::Settings(Box::new(settings)));
+    }
+    if object.contains_key("name") {
+        let preset: Preset = serde_json::from_value(doc)
+            .map_err(|e| format!("the clipboard doesn't hold a valid preset: {e}"))?;
+        return Ok(Pasted::Preset(preset));
+    }
+    Err("the clipboard JSON is neither settings (no \"version\") nor a preset (no \"name\")".into())
+}
+
+/// Applies settings or a preset copied to the clipboard. Pasted settings keep
+/// this machine's window size and position.
+pub fn import(app: &AppHandle) -> Result<(), AppError> {
+    let contents = app
+        .clipboard()
+        .read_text()
+        .map_err(|e| AppError::Io(format!("the clipboard doesn't hold any text: {e}")))?;
+    match parse(&contents).map_err(AppError::InvalidParam)? {
+        Pasted::Settings(mut new) => {
+            {
+                let current = app.state::<SettingsState>();
+                let current = current.0.lock().unwrap();
+                new.window_position = current.window_position;
+                new.window_size = current.window_size;
+            }
+            new.save(app).map_err(AppError::Io)?;
+            settings::replace(app, *new);
+            log::info!("imported settings from the clipboard");
+        }
+        Pasted::Preset(preset) => {
+            presets::apply(app, &preset)?;
+            log::info!("applied preset {:?} from the clipboard", preset.name);
+        }
+    }
+    Ok(())
+}
+
+/// Copies the current settings to the clipboard as JSON.
+pub fn export(app: &AppHandle) -> Result<(), AppError> {
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    let contents =
+        serde_json::to_string_pretty(&settings).map_err(|e| AppError::Io(e.to_string()))?;
+    app.clipboard()
+        .write_text(contents)
+        .map_err(|e| AppError::Io(format!("failed to write to the clipboard: {e}")))
+}
+
+#[tauri::command]
+pub fn import_from_clipboard(app: AppHandle) -> Result<(), AppError> {
+    import(&app)
+}
+
+#[tauri::command]
+pub fn export_to_clipboard(app: AppHandle) -> Result<(), AppError> {
+    export(&app)
+}
@@ -0,0 +1,198 @@
+//! Finds vehicles whose bounding boxes overlap and reports the crashes.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    motion, notify,
+    settings::SettingsState,
+    sim::{Direction, Vehicle},
+};
+
+/// Size of a vehicle's bounding box along and across its direction of travel.
+const VEHICLE_LENGTH: f64 = 16.0;
+const VEHICLE_WIDTH: f64 = 10.0;
+/// Grid cells are as big as the largest box, so boxes can only overlap if
+/// their centers are in the same or neighbouring cells.
+const CELL_SIZE: f64 = VEHICLE_LENGTH;
+/// How long a wreck stays on the road when wrecks are despawned.
+pub const WRECK_CLEAR_SECS: f64 = 3.0;
+
+#[cfg(target_os = "macos")]
+const COLLISION_SOUND: &str = "Basso";
+#[cfg(target_os = "windows")]
+const COLLISION_SOUND: &str = "Default";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const COLLISION_SOUND: &str = "dialog-warning";
+
+/// What happens to vehicles after they crash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionBehavior {
+    /// Wrecks stay where they crashed.
+    Stop,
+    /// Wrecks stop and are cleared after [`WRECK_CLEAR_SECS`].
+    #[default]
+    Despawn,
+    /// Crashes are reported but vehicles drive on.
+    PassThrough,
+}
+
+/// Payload of `collision`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Collision {
+    pub vehicles: [u64; 2],
+    /// `[x, y]` of each vehicle, in the same order.
+    pub positions: [[f64; 2]; 2],
+}
+
+impl Collision {
+    pub fn between(a: &Vehicle, b: &Vehicle) -> Self {
+        Self {
+            vehicles: [a.id, b.id],
+            positions: [[a.x, a.y], [b.x, b.y]],
+        }
+    }
+}
+
+/// Half the box's width and height.
+fn half_extents(vehicle: &Vehicle) -> (f64, f64) {
+    match vehicle.direction {
+        Direction::E | Direction::W => (VEHICLE_LENGTH / 2.0, VEHICLE_WIDTH / 2.0),
+        Direction::N | Direction::S => (VEHICLE_WIDTH / 2.0, VEHICLE_LENGTH / 2.0),
+    }
+}
+
+fn overlaps(a: &Vehicle, b: &Vehicle) -> bool {
+    let (aw, ah) = half_extents(a);
+    let (bw, bh) = half_extents(b);
+    (a.x - b.x).abs() < aw + bw && (a.y - b.y).abs() < ah + bh
+}
+
+fn cell(vehicle: &Vehicle) -> (i64, i64) {
+    (
+        (vehicle.x / CELL_SIZE).floor() as i64,
+        (vehicle.y / CELL_SIZE).floor() as i64,
+    )
+}
+
+/// Index pairs `(i, j)`, `i < j`, of overlapping vehicles. Bucketing by grid
+/// cell keeps this close to linear in the number of vehicles.
+pub fn detect(vehicles: &[Vehicle]) -> Vec<(usize, usize)> {
+    let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, vehicle) in vehicles.iter().enumerate() {
+        grid.entry(cell(vehicle)).or_default().push(i);
+    }
+    let mut pairs = Vec::new();
+    for (i, a) in vehicles.iter().enumerate() {
+        let (cx, cy) = cell(a);
+        for x in cx - 1..=cx + 1 {
+            for y in cy - 1..=cy + 1 {
+                for &j in grid.get(&(x, y)).into_iter().flatten() {
+                    if j > i && overlaps(a, &vehicles[j]) {
+                        pairs.push((i, j));
+                    }
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// Emits `collision` for each crash and, if enabled, plays a sound. The sound
+/// comes with a notification, so it needs notifications on and is
+/// rate-limited like them. Nothing is reported while motion is reduced, since
+/// the canvas animates each crash.
+pub fn report(app: &AppHandle, collisions: &[Collision]) {
+    let Some(first) = collisions.first() else {
+        return;
+    };
+    if motion::reduced(app) {
+        return;
+    }
+    for collision in collisions {
+        if let Err(e) = app.emit("collision", collision) {
+            log::error!("failed to emit collision: {e}");
+        }
+    }
+    if !app
+        .state::<SettingsState>()
+        .0
+        .lock()
+        .unwrap()
+        .collision_sounds
+    {
+        return;
+    }
+    let [x, y] = first.positions[0];
+    let body = format!("Two vehicles collided at ({x:.0}, {y:.0})");
+    if let Err(e) = notify::send_with_sound(app, "collision", "Crash!", &body, COLLISION_SOUND) {
+        log::error!("failed to play collision sound: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vehicle(id: u64, x: f64, y: f64, direction: Direction) -> Vehicle {
+        Vehicle {
+            id,
+            x,
+            y,
+            vx: 0.0,
+            vy: 0.0,
+            direction,
+            lane: 0,
+            edge: 0,
+            progress: 0.0,
+            speed: 50.0,
+            crashed: None,
+        }
+    }
+
+    #[test]
+    fn overlapping_vehicles_collide() {
+        let vehicles = [
+            vehicle(1, 100.0, 100.0, Direction::E),
+            vehicle(2, 112.0, 100.0, Direction::E),
+        ];
+        assert_eq!(detect(&vehicles), [(0, 1)]);
+    }
+
+    #[test]
+    fn neighbouring_lanes_dont_collide() {
+        let vehicles = [
+            vehicle(1, 100.0, 100.0, Direction::E),
+            vehicle(2, 100.0, 120.0, Direction::W),
+        ];
+        assert!(detect(&vehicles).is_empty());
+    }
+
+    #[test]
+    fn boxes_turn_with_the_vehicle() {
+        // 12 apart across: clear of an eastbound box, inside a northbound one.
+        let eastbound = [
+            vehicle(1, 100.0, 100.0, Direction::E),
+            vehicle(2, 100.0, 112.0, Direction::E),
+        ];
+        assert!(detect(&eastbound).is_empty());
+        let northbound = [
+            vehicle(1, 100.0, 100.0, Direction::N),
+            vehicle(2, 100.0, 112.0, Direction::N),
+        ];
+        assert_eq!(detect(&northbound), [(0, 1)]);
+    }
+
+    #[test]
+    fn collisions_across_cell_boundaries_are_found() {
+        let vehicles = [
+            vehicle(1, CELL_SIZE - 1.0, 5.0, Direction::E),
+            vehicle(2, CELL_SIZE + 1.0, 5.0, Direction::E),
+            vehicle(3, 500.0, 5.0, Direction::E),
+        ];
+        assert_eq!(detect(&vehicles), [(0, 1)]);
+    }
+}
@@ -0,0 +1,55 @@
+use std::{backtrace::Backtrace, panic, thread};
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{
+    settings::SettingsState,
+    telemetry::{self, Event},
+};
+
+/// Name of the tick loop's thread. Its panics are handled by the restart
+/// logic in `sim::start` rather than reported straight away.
+pub const SIM_THREAD: &str = "sim";
+
+/// Logs every panic with a backtrace, and tells the user about crashes
+/// outside the tick loop.
+pub fn install_hook(app: AppHandle) {
+    panic::set_hook(Box::new(move |info| {
+        // A panic inside the hook aborts the process, so everything below
+        // avoids unwrapping: locks are only tried and errors are dropped.
+        let thread = thread::current();
+        let name = thread.name().unwrap_or("<unnamed>");
+        let backtrace = Backtrace::force_capture();
+        log::error!("thread '{name}' {info}\n{backtrace}");
+        telemetry::record(&app, Event::Crash);
+        if name != SIM_THREAD {
+            notify_crash(&app);
+        }
+    }));
+}
+
+/// Shows a notification pointing at the log. Safe to call from the panic
+/// hook: it never blocks on or unwraps a lock.
+pub fn notify_crash(app: &AppHandle) {
+    let enabled = app
+        .try_state::<SettingsState>()
+        .and_then(|s| s.0.try_lock().ok().map(|s| s.notifications_enabled))
+        .unwrap_or(true);
+    if !enabled {
+        return;
+    }
+    let body = match app.path().app_log_dir() {
+        Ok(dir) => format!("Details were written to the log in {}", dir.display()),
+        Err(_) => "Details were written to the log".to_string(),
+    };
+    let result = app
+        .notification()
+        .builder()
+        .title("Shibuya Traffic crashed")
+        .body(body)
+        .show();
+    if let Err(e) = result {
+        log::error!("failed to show crash notification: {e}");
+    }
+}
@@ -0,0 +1,204 @@
+//! Tints the scene for the time of day, either set by hand or following the
+//! system clock.
+
+use std::{sync::Mutex, thread, time::Duration};
+
+use chrono::Timelike;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+    error::AppError,
+    settings::{self, Settings, SettingsState},
+};
+
+/// How often the auto mode looks at the clock.
+const AUTO_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Tints at points through the day, as RGB multipliers the scene is painted
+/// with. Hours in between blend the neighbouring keyframes; the last wraps
+/// around to the first.
+const KEYFRAMES: [(f64, [u8; 3]); 8] = [
+    (0.0, NIGHT),
+    (4.5, NIGHT),
+    (6.5, DAWN),
+    (9.0, NOON),
+    (16.0, NOON),
+    (18.5, DUSK),
+    (20.5, NIGHT),
+    (24.0, NIGHT),
+];
+const NIGHT: [u8; 3] = [0x4a, 0x55, 0x8c];
+const DAWN: [u8; 3] = [0xff, 0xc2, 0x9b];
+const NOON: [u8; 3] = [0xff, 0xff, 0xff];
+const DUSK: [u8; 3] = [0xff, 0x9e, 0x7a];
+
+/// Payload of `time-of-day-changed`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimeOfDay {
+    pub hour: f64,
+    /// The tint for `hour`, as a CSS hex color.
+    pub tint: String,
+}
+
+impl TimeOfDay {
+    pub fn at(hour: f64) -> Self {
+        let hour = wrap(hour);
+        Self {
+            hour,
+            tint: tint(hour),
+        }
+    }
+}
+
+/// The time of day the scene is currently tinted for.
+pub struct Daylight(Mutex<TimeOfDay>);
+
+impl Default for Daylight {
+    fn default() -> Self {
+        Self(Mutex::new(TimeOfDay::at(settings::DEFAULT_TIME_OF_DAY)))
+    }
+}
+
+/// `hour` in `0.0..24.0`.
+fn wrap(hour: f64) -> f64 {
+    let hour = hour.rem_euclid(24.0);
+    // rem_euclid can round up to exactly 24 for tiny negative hours.
+    if hour >= 24.0 {
+        0.0
+    } else {
+        hour
+    }
+}
+
+/// Blends the keyframes either side of `hour`.
+fn tint(hour: f64) -> String {
+    let i = KEYFRAMES
+        .windows(2)
+        .position(|pair| hour < pair[1].0)
+        .unwrap_or(KEYFRAMES.len() - 2);
+    let (start, from) = KEYFRAMES[i];
+    let (end, to) = KEYFRAMES[i + 1];
+    let t = ((hour - start) / (end - start)).clamp(0.0, 1.0);
+    let channel = |c: usize| (from[c] as f64 + (to[c] as f64 - from[c] as f64) * t).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(0), channel(1), channel(2))
+}
+
+/// The local time as a fractional hour.
+fn clock_hour() -> f64 {
+    let now = chrono::Local::now();
+    now.hour() as f64 + now.minute() as f64 / 60.0
+}
+
+/// Tints the scene for `hour`, telling the UI if that changed anything.
+fn update(app: &AppHandle, hour: f64) {
+    let time = TimeOfDay::at(hour);
+    let state = app.state::<Daylight>();
+    let mut current = state.0.lock().unwrap();
+    if *current == time {
+        return;
+    }
+    *current = time.clone();
+    drop(current);
+    if let Err(e) = app.emit("time-of-day-changed", time) {
+        log::error!("failed to emit time-of-day-changed: {e}");
+    }
+}
+
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    let hour = if settings.auto_day_night {
+        clock_hour()
+    } else {
+        settings.time_of_day
+    };
+    update(app, hour);
+}
+
+/// The time of day the scene is tinted for.
+pub fn current(app: &AppHandle) -> TimeOfDay {
+    app.state::<Daylight>().0.lock().unwrap().clone()
+}
+
+/// Spawns the thread that follows the clock while `auto_day_night` is on.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(AUTO_INTERVAL);
+        let auto = app
+            .state::<SettingsState>()
+            .0
+            .lock()
+            .unwrap()
+            .auto_day_night;
+        if auto {
+            update(&app, clock_hour());
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_time_of_day(state: State<Daylight>) -> TimeOfDay {
+    state.0.lock().unwrap().clone()
+}
+
+/// Tints the scene for `hour`, wrapped into a day. Turns off
+/// `auto_day_night`, which would otherwise move it back within a minute.
+#[tauri::command]
+pub fn set_time_of_day(app: AppHandle, hour: f64) -> Result<(), AppError> {
+    if !hour.is_finite() {
+        return Err(AppError::InvalidParam(format!(
+            "hour must be a number, got {hour}"
+        )));
+    }
+    let hour = wrap(hour);
+    settings::update(&app, |s| {
+        s.time_of_day = hour;
+        s.auto_day_night = false;
+    })
+    .map_err(AppError::Io)?;
+    update(&app, hour);
+    Ok(())
+}
+
+/// Switches between following the system clock and the saved hour.
+#[tauri::command]
+pub fn set_auto_day_night(app: AppHandle, enabled: bool) -> Result<(), AppError> {
+    settings::update(&app, |s| s.auto_day_night = enabled).map_err(AppError::Io)?;
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    load_settings(&app, &settings);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hours_wrap_into_a_day() {
+        assert_eq!(wrap(25.5), 1.5);
+        assert_eq!(wrap(-1.0), 23.0);
+        assert_eq!(wrap(24.0), 0.0);
+    }
+
+    #[test]
+    fn keyframes_are_hit_exactly() {
+        assert_eq!(tint(12.0), "#ffffff");
+        assert_eq!(tint(6.5), "#ffc29b");
+        assert_eq!(tint(2.0), "#4a558c");
+    }
+
+    #[test]
+    fn tints_blend_between_keyframes() {
+        // Halfway from night at 4:30 to dawn at 6:30.
+        let mid = [0x4a, 0x55, 0x8c]
+            .iter()
+            .zip([0xff, 0xc2, 0x9b])
+            .map(|(&a, b)| format!("{:02x}", ((a as f64 + b as f64) / 2.0).round() as u8))
+            .collect::<String>();
+        assert_eq!(tint(5.5), format!("#{mid}"));
+    }
+
+    #[test]
+    fn midnight_joins_up() {
+        assert_eq!(tint(23.99), tint(0.0));
+    }
+}
@@ -0,0 +1,183 @@
+//! Applies shared setups from `traffic://preset?speed=2&spawn=40&seed=123`
+//! links.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::{
+    error::AppError,
+    sim::{self, SimParams, SimState, SPAWN_RATE_RANGE, SPEED_MULTIPLIER_RANGE},
+};
+
+pub const SCHEME: &str = "traffic";
+
+/// What a link asks for. Missing values keep the current ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Link {
+    pub speed_multiplier: Option<f64>,
+    pub spawn_rate: Option<f64>,
+    pub seed: Option<u64>,
+}
+
+/// Applies links that open the app from now on, and the one it was launched
+/// with. A second launch's link arrives here through single-instance.
+pub fn init(app: &AppHandle) {
+    // Installed builds register the scheme at install time; this covers dev
+    // builds and AppImages.
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        log::warn!("failed to register the {SCHEME}:// scheme: {e}");
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle_url(&handle, &url);
+        }
+    });
+    match app.deep_link().get_current() {
+        Ok(urls) => {
+            for url in urls.unwrap_or_default() {
+                handle_url(app, &url);
+            }
+        }
+        Err(e) => log::warn!("failed to read the launch link: {e}"),
+    }
+}
+
+fn handle_url(app: &AppHandle, url: &Url) {
+    let link = match parse(url) {
+        Ok(link) => link,
+        Err(e) => {
+            log::warn!("ignoring link {url}: {e}");
+            return;
+        }
+    };
+    log::info!("applying link {url}");
+    if let Err(e) = apply(app, link) {
+        log::error!("failed to apply link {url}: {e}");
+        return;
+    }
+    if let Err(e) = app.emit("deep-link-applied", link) {
+        log::error!("failed to emit deep-link-applied: {e}");
+    }
+}
+
+/// Reads a `traffic://preset` link, clamping values into their valid ranges.
+fn parse(url: &Url) -> Result<Link, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("unsupported scheme {:?}", url.scheme()));
+    }
+    if url.host_str() != Some("preset") {
+        return Err(format!("unknown link kind {:?}", url.host_str()));
+    }
+    let number = |key: &str, value: &str| {
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| format!("{key} must be a number, got {value:?}"))
+    };
+
+    let mut link = Link::default();
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "speed" => {
+                let speed = number("speed", &value)?;
+                link.speed_multiplier = Some(speed.clamp(
+                    *SPEED_MULTIPLIER_RANGE.start(),
+                    *SPEED_MULTIPLIER_RANGE.end(),
+                ));
+            }
+            "spawn" => {
+                let spawn = number("spawn", &value)?;
+                link.spawn_rate =
+                    Some(spawn.clamp(*SPAWN_RATE_RANGE.start(), *SPAWN_RATE_RANGE.end()));
+            }
+            "seed" => {
+                let seed = value
+                    .parse()
+                    .map_err(|_| format!("seed must be a whole number, got {value:?}"))?;
+                link.seed = Some(seed);
+            }
+            _ => log::warn!("ignoring unknown link parameter {key:?}"),
+        }
+    }
+    if link == Link::default() {
+        return Err("link sets nothing".into());
+    }
+    Ok(link)
+}
+
+fn apply(app: &AppHandle, link: Link) -> Result<(), AppError> {
+    if link.speed_multiplier.is_some() || link.spawn_rate.is_some() {
+        let current = *app.state::<SimState>().params.lock().unwrap();
+        sim::set_params(
+            app,
+            SimParams {
+                speed_multiplier: link.speed_multiplier.unwrap_or(current.speed_multiplier),
+                spawn_rate: link.spawn_rate.unwrap_or(current.spawn_rate),
+                ..current
+            },
+        )?;
+    }
+    if let Some(seed) = link.seed {
+        sim::reseed(app, seed)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(url: &str) -> Result<Link, String> {
+        parse(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn reads_every_value() {
+        let link = parse_str("traffic://preset?speed=2&spawn=4&seed=123").unwrap();
+        assert_eq!(
+            link,
+            Link {
+                speed_multiplier: Some(2.0),
+                spawn_rate: Some(4.0),
+                seed: Some(123),
+            }
+        );
+    }
+
+    #[test]
+    fn other_schemes_are_rejected() {
+        let err = parse_str("https://preset?speed=2").unwrap_err();
+        assert!(err.contains("scheme"), "{err}");
+    }
+
+    #[test]
+    fn other_hosts_are_rejected() {
+        let err = parse_str("traffic://reset?speed=2").unwrap_err();
+        assert!(err.contains("link kind"), "{err}");
+    }
+
+    #[test]
+    fn values_are_clamped() {
+        let link = parse_str("traffic://preset?speed=1000&spawn=-5").unwrap();
+        assert_eq!(link.speed_multiplier, Some(*SPEED_MULTIPLIER_RANGE.end()));
+        assert_eq!(link.spawn_rate, Some(*SPAWN_RATE_RANGE.start()));
+    }
+
+    #[test]
+    fn bad_seeds_are_rejected() {
+        for seed in ["-1", "1.5", "abc"] {
+            let err = parse_str(&format!("traffic://preset?seed={seed}")).unwrap_err();
+            assert!(err.contains("seed"), "{seed}: {err}");
+        }
+    }
+
+    #[test]
+    fn empty_links_are_rejected() {
+        assert!(parse_str("traffic://preset").is_err());
+    }
+}
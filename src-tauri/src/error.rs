@@ -0,0 +1,76 @@
+//! The error every command rejects with, and the `app-error` event for
+//! failures that have no command to reject.
+
+use std::fmt;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// Serialized as `{ "kind": "window_missing", "message": "..." }`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum AppError {
+    /// A window the operation needs has been closed.
+    WindowMissing(String),
+    /// Reading or writing a file, the clipboard or the network failed.
+    Io(String),
+    /// An argument, or a document from outside the app, was rejected.
+    InvalidParam(String),
+    /// The OS or a plugin refused.
+    Platform(String),
+}
+
+impl AppError {
+    pub fn window_missing(label: &str) -> Self {
+        Self::WindowMissing(format!("window {label:?} not found"))
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WindowMissing(message)
+            | Self::Io(message)
+            | Self::InvalidParam(message)
+            | Self::Platform(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        match e {
+            tauri::Error::WindowNotFound | tauri::Error::WebviewNotFound => {
+                Self::WindowMissing(e.to_string())
+            }
+            tauri::Error::Io(e) => Self::Io(e.to_string()),
+            e => Self::Platform(e.to_string()),
+        }
+    }
+}
+
+/// Payload of `app-error`.
+#[derive(Clone, Serialize)]
+struct Reported<'a> {
+    /// What was being done, e.g. "failed to hide window".
+    context: &'a str,
+    #[serde(flatten)]
+    error: &'a AppError,
+}
+
+/// Logs a failure and emits it as `app-error` so the UI can show it.
+pub fn report(app: &AppHandle, context: &str, error: impl Into<AppError>) {
+    let error = error.into();
+    log::error!("{context}: {error}");
+    if let Err(e) = app.emit(
+        "app-error",
+        Reported {
+            context,
+            error: &error,
+        },
+    ) {
+        log::error!("failed to emit app-error: {e}");
+    }
+}
@@ -0,0 +1,48 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::overlay;
+
+/// Alt-tabbing flips focus several times in quick succession; only the
+/// state after it settles is reported.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Whether any overlay window last had focus, and whether a check is queued.
+#[derive(Default)]
+pub struct FocusState {
+    focused: AtomicBool,
+    pending: AtomicBool,
+}
+
+/// Queues a focus check once the burst of focus events settles.
+pub fn schedule_check(app: &AppHandle) {
+    if app
+        .state::<FocusState>()
+        .pending
+        .swap(true, Ordering::AcqRel)
+    {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(DEBOUNCE);
+        let state = app.state::<FocusState>();
+        state.pending.store(false, Ordering::Release);
+
+        let focused = overlay::labels(&app).iter().any(|label| {
+            app.get_webview_window(label)
+                .and_then(|w| w.is_focused().ok())
+                .unwrap_or(false)
+        });
+        if state.focused.swap(focused, Ordering::Relaxed) != focused {
+            if let Err(e) = app.emit_to("main", "window-focus", focused) {
+                log::error!("failed to emit window-focus: {e}");
+            }
+        }
+    });
+}
@@ -0,0 +1,326 @@
+//! Shows the overlay only while an allowed app is in front. The foreground
+//! window's process is polled; an empty allowlist leaves the overlay alone.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    error::{self, AppError},
+    overlay,
+    settings::{self, Settings, SettingsState},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a change of foreground app must last before the overlay follows,
+/// so alt-tabbing past apps doesn't make it flicker.
+const DEBOUNCE: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+pub struct ForegroundState {
+    /// Normalized with [`normalize`].
+    allowlist: Mutex<Vec<String>>,
+    /// Set by hiding the window by hand; the allowlist won't show it again
+    /// until it's shown by hand.
+    manually_hidden: AtomicBool,
+    /// Whether the window is hidden because no allowed app is in front.
+    hidden_by_allowlist: AtomicBool,
+}
+
+/// Lowercase, without a Windows `.exe` suffix.
+fn normalize(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    name.strip_suffix(".exe").map(str::to_owned).unwrap_or(name)
+}
+
+fn allowed(allowlist: &[String], process: &str) -> bool {
+    allowlist.contains(&normalize(process))
+}
+
+#[cfg(target_os = "windows")]
+struct ForegroundMonitor;
+
+#[cfg(target_os = "windows")]
+impl ForegroundMonitor {
+    fn new() -> Option<Self> {
+        Some(Self)
+    }
+
+    fn pid(&mut self) -> Option<u32> {
+        use windows::Win32::UI::WindowsAndMessaging::{
+            GetForegroundWindow, GetWindowThreadProcessId,
+        };
+
+        let mut pid = 0;
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_invalid() {
+                return None;
+            }
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        }
+        (pid != 0).then_some(pid)
+    }
+}
+
+#[cfg(target_os = "macos")]
+struct ForegroundMonitor;
+
+#[cfg(target_os = "macos")]
+impl ForegroundMonitor {
+    fn new() -> Option<Self> {
+        Some(Self)
+    }
+
+    fn pid(&mut self) -> Option<u32> {
+        use objc2::{class, msg_send, runtime::AnyObject};
+
+        // SAFETY: NSWorkspace is thread-safe and both calls return objects
+        // owned by AppKit.
+        unsafe {
+            let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let app: *mut AnyObject = msg_send![workspace, frontmostApplication];
+            if app.is_null() {
+                return None;
+            }
+            let pid: i32 = msg_send![app, processIdentifier];
+            u32::try_from(pid).ok()
+        }
+    }
+}
+
+/// Reads `_NET_ACTIVE_WINDOW` and its `_NET_WM_PID`. libX11 is loaded at
+/// runtime, so Wayland-only systems without it never filter.
+#[cfg(target_os = "linux")]
+struct ForegroundMonitor {
+    xlib: x11_dl::xlib::Xlib,
+    display: *mut x11_dl::xlib::Display,
+    active_window: x11_dl::xlib::Atom,
+    wm_pid: x11_dl::xlib::Atom,
+}
+
+#[cfg(target_os = "linux")]
+impl ForegroundMonitor {
+    fn new() -> Option<Self> {
+        let xlib = x11_dl::xlib::Xlib::open().ok()?;
+        let display = unsafe { (xlib.XOpenDisplay)(std::ptr::null()) };
+        if display.is_null() {
+            return None;
+        }
+        let atom = |name: &std::ffi::CStr| unsafe {
+            (xlib.XInternAtom)(display, name.as_ptr(), x11_dl::xlib::False)
+        };
+        let active_window = atom(c"_NET_ACTIVE_WINDOW");
+        let wm_pid = atom(c"_NET_WM_PID");
+        Some(Self {
+            xlib,
+            display,
+            active_window,
+            wm_pid,
+        })
+    }
+
+    /// The first 32-bit item of `property` on `window`.
+    fn property(
+        &self,
+        window: x11_dl::xlib::Window,
+        property: x11_dl::xlib::Atom,
+    ) -> Option<std::ffi::c_ulong> {
+        use x11_dl::xlib::{AnyPropertyType, False, Success};
+
+        let mut actual_type = 0;
+        let mut format = 0;
+        let mut items = 0;
+        let mut bytes_after = 0;
+        let mut data = std::ptr::null_mut();
+        unsafe {
+            let status = (self.xlib.XGetWindowProperty)(
+                self.display,
+                window,
+                property,
+                0,
+                1,
+                False,
+                AnyPropertyType as x11_dl::xlib::Atom,
+                &mut actual_type,
+                &mut format,
+                &mut items,
+                &mut bytes_after,
+                &mut data,
+            );
+            if status != Success as i32 || data.is_null() {
+                return None;
+            }
+            // Xlib hands 32-bit items back as longs.
+            let value = (items > 0 && format == 32).then(|| *(data as *const std::ffi::c_ulong));
+            (self.xlib.XFree)(data.cast());
+            value
+        }
+    }
+
+    fn pid(&mut self) -> Option<u32> {
+        let root = unsafe { (self.xlib.XDefaultRootWindow)(self.display) };
+        let window = self.property(root, self.active_window)?;
+        if window == 0 {
+            return None;
+        }
+        u32::try_from(self.property(window, self.wm_pid)?).ok()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for ForegroundMonitor {
+    fn drop(&mut self) {
+        unsafe { (self.xlib.XCloseDisplay)(self.display) };
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+struct ForegroundMonitor;
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+impl ForegroundMonitor {
+    fn new() -> Option<Self> {
+        None
+    }
+
+    fn pid(&mut self) -> Option<u32> {
+        None
+    }
+}
+
+fn process_name(system: &mut System, pid: u32) -> Option<String> {
+    let pid = Pid::from_u32(pid);
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+    system
+        .process(pid)
+        .map(|p| p.name().to_string_lossy().into_owned())
+}
+
+/// Records a show or hide done by hand, which overrides the allowlist.
+pub fn set_manually_hidden(app: &AppHandle, hidden: bool) {
+    let state = app.state::<ForegroundState>();
+    state.manually_hidden.store(hidden, Ordering::Relaxed);
+    state.hidden_by_allowlist.store(false, Ordering::Relaxed);
+}
+
+/// Hides or shows the main window for the app in front, unless it was hidden
+/// by hand.
+fn follow(app: &AppHandle, show: bool) {
+    let state = app.state::<ForegroundState>();
+    if state.manually_hidden.load(Ordering::Relaxed) {
+        return;
+    }
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let result = if show {
+        if !state.hidden_by_allowlist.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        overlay::show(&window)
+    } else {
+        if !window.is_visible().unwrap_or(false) {
+            return;
+        }
+        state.hidden_by_allowlist.store(true, Ordering::Relaxed);
+        window.hide()
+    };
+    if let Err(e) = result {
+        error::report(app, "failed to follow the foreground app", e);
+    }
+}
+
+/// Polls the foreground app at 2 Hz on its own thread.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let Some(mut monitor) = ForegroundMonitor::new() else {
+            log::warn!("foreground app detection is unavailable, app_allowlist is ignored");
+            return;
+        };
+        let own_pid = std::process::id();
+        let mut system = System::new();
+        let mut pending: Option<(bool, Instant)> = None;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let allowlist = app
+                .state::<ForegroundState>()
+                .allowlist
+                .lock()
+                .unwrap()
+                .clone();
+            let show = if allowlist.is_empty() {
+                true
+            } else {
+                match monitor.pid() {
+                    // Clicking the overlay itself changes nothing.
+                    Some(pid) if pid == own_pid => continue,
+                    Some(pid) => process_name(&mut system, pid)
+                        .is_some_and(|name| allowed(&allowlist, &name)),
+                    None => continue,
+                }
+            };
+            let showing = !app
+                .state::<ForegroundState>()
+                .hidden_by_allowlist
+                .load(Ordering::Relaxed);
+            if show == showing {
+                pending = None;
+                continue;
+            }
+            match pending {
+                Some((want, since)) if want == show && since.elapsed() >= DEBOUNCE => {
+                    pending = None;
+                    follow(&app, show);
+                }
+                Some((want, _)) if want == show => {}
+                _ => pending = Some((show, Instant::now())),
+            }
+        }
+    });
+}
+
+/// Copies the allowlist into the live state.
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    *app.state::<ForegroundState>().allowlist.lock().unwrap() = settings
+        .app_allowlist
+        .iter()
+        .map(|app| normalize(app))
+        .collect();
+}
+
+/// Shows the overlay only while one of `apps` is in front; empty shows it
+/// always.
+#[tauri::command]
+pub fn set_app_allowlist(app: AppHandle, apps: Vec<String>) -> Result<(), AppError> {
+    settings::update(&app, |s| s.app_allowlist = apps).map_err(AppError::Io)?;
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    load_settings(&app, &settings);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_match_ignoring_case_and_exe() {
+        let allowlist = vec![normalize("Firefox"), normalize("chrome.EXE")];
+        assert!(allowed(&allowlist, "firefox"));
+        assert!(allowed(&allowlist, "Chrome.exe"));
+        assert!(allowed(&allowlist, "chrome"));
+        assert!(!allowed(&allowlist, "code"));
+    }
+}
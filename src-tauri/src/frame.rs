@@ -0,0 +1,173 @@
+//! Packs simulation frames for the webview and holds them back when it
+//! isn't keeping up.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+
+use crate::{
+    heartbeat, overlay,
+    sim::{Direction, Sim, Vehicle},
+};
+
+/// Bytes per vehicle in a packed frame.
+pub const VEHICLE_BYTES: usize = 22;
+/// An unacknowledged frame is assumed lost after this long, so a webview
+/// that never acks still gets the occasional frame.
+const ACK_TIMEOUT: Duration = Duration::from_secs(1);
+/// How often to log emit timings.
+const STATS_INTERVAL: Duration = Duration::from_secs(10);
+/// Slack for sleep jitter when enforcing the tick rate.
+const RATE_SLACK: Duration = Duration::from_millis(1);
+
+/// Payload of `sim-tick`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Frame {
+    pub id: u64,
+    pub tick: u64,
+    /// Base64 of the vehicles packed by [`pack`].
+    pub vehicles: String,
+}
+
+/// The newest frame id each webview has acknowledged, by window label.
+#[derive(Default)]
+pub struct FrameAcks(Mutex<HashMap<String, u64>>);
+
+/// Drops a destroyed window's acks.
+pub fn forget(app: &AppHandle, label: &str) {
+    if let Some(acks) = app.try_state::<FrameAcks>() {
+        acks.0.lock().unwrap().remove(label);
+    }
+}
+
+/// Packs each vehicle as little-endian `id: u32`, `x, y, vx, vy: f32`,
+/// `direction: u8` (0-3 for N, S, E, W) and `lane: u8`.
+pub fn pack(vehicles: &[Vehicle]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(vehicles.len() * VEHICLE_BYTES);
+    for v in vehicles {
+        // Ids wrap after four billion vehicles, which the frontend only uses
+        // to tell sprites apart.
+        bytes.extend_from_slice(&(v.id as u32).to_le_bytes());
+        for value in [v.x, v.y, v.vx, v.vy] {
+            bytes.extend_from_slice(&(value as f32).to_le_bytes());
+        }
+        bytes.push(match v.direction {
+            Direction::N => 0,
+            Direction::S => 1,
+            Direction::E => 2,
+            Direction::W => 3,
+        });
+        bytes.push(v.lane);
+    }
+    bytes
+}
+
+/// Emits `sim-tick` at most once per tick, skipping frames while the
+/// previous one is unacknowledged by every target or the webview isn't
+/// answering pings.
+pub struct FrameEmitter {
+    next_id: u64,
+    last_emit: Option<Instant>,
+    stats_since: Instant,
+    emitted: u32,
+    dropped: u32,
+    busy: Duration,
+}
+
+impl FrameEmitter {
+    pub fn new() -> Self {
+        Self {
+            next_id: 1,
+            last_emit: None,
+            stats_since: Instant::now(),
+            emitted: 0,
+            dropped: 0,
+            busy: Duration::ZERO,
+        }
+    }
+
+    fn should_emit(&self, app: &AppHandle, targets: &[String], hz: u32) -> bool {
+        if !heartbeat::responsive(app) {
+            return false;
+        }
+        let Some(last) = self.last_emit else {
+            return true;
+        };
+        let elapsed = last.elapsed();
+        if elapsed + RATE_SLACK < Duration::from_secs_f64(1.0 / hz.max(1) as f64) {
+            return false;
+        }
+        let acked = {
+            let acks = app.state::<FrameAcks>();
+            let acks = acks.0.lock().unwrap();
+            targets
+                .iter()
+                .filter_map(|label| acks.get(label))
+                .max()
+                .copied()
+                .unwrap_or(0)
+        };
+        acked + 1 >= self.next_id || elapsed >= ACK_TIMEOUT
+    }
+
+    /// Returns how long serializing and emitting took, if a frame was sent.
+    pub fn emit(
+        &mut self,
+        app: &AppHandle,
+        targets: &[String],
+        hz: u32,
+        sim: &Sim,
+    ) -> Option<Duration> {
+        let mut took = None;
+        if self.should_emit(app, targets, hz) {
+            let started = Instant::now();
+            let frame = Frame {
+                id: self.next_id,
+                tick: sim.tick(),
+                vehicles: STANDARD.encode(pack(sim.vehicles())),
+            };
+            let result = app.emit_filter("sim-tick", frame, |target| {
+                overlay::targets_any(targets, target)
+            });
+            if let Err(e) = result {
+                log::error!("failed to emit sim-tick: {e}");
+            }
+            let elapsed = started.elapsed();
+            self.busy += elapsed;
+            took = Some(elapsed);
+            self.next_id += 1;
+            self.last_emit = Some(started);
+            self.emitted += 1;
+        } else {
+            self.dropped += 1;
+        }
+
+        if self.stats_since.elapsed() >= STATS_INTERVAL {
+            let average = self.busy.checked_div(self.emitted).unwrap_or_default();
+            log::debug!(
+                "sim-tick: {} emitted, {} dropped, {average:?} average serialize+emit",
+                self.emitted,
+                self.dropped
+            );
+            self.stats_since = Instant::now();
+            self.emitted = 0;
+            self.dropped = 0;
+            self.busy = Duration::ZERO;
+        }
+        took
+    }
+}
+
+/// Tells the backend the webview has drawn `frame_id`.
+#[tauri::command]
+pub fn ack_frame(window: WebviewWindow, acks: State<FrameAcks>, frame_id: u64) {
+    let mut acks = acks.0.lock().unwrap();
+    let acked = acks.entry(window.label().to_string()).or_default();
+    *acked = (*acked).max(frame_id);
+}
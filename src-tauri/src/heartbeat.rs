@@ -0,0 +1,108 @@
+//! Pings the main webview and notices when it stops answering.
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{error, settings::SettingsState};
+
+pub const DEFAULT_INTERVAL_SECS: u64 = 5;
+pub const DEFAULT_MISSED_THRESHOLD: u32 = 3;
+
+#[derive(Default)]
+pub struct Heartbeat {
+    /// Timestamp of the last ping sent, in milliseconds since the epoch.
+    last_ping: AtomicU64,
+    /// Timestamp of the last ping the webview answered.
+    last_pong: AtomicU64,
+    missed: AtomicU32,
+    stopped: AtomicBool,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Sends `ping` to "main" every interval until [`stop`] is called.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let heartbeat = app.state::<Heartbeat>();
+        loop {
+            let (interval, threshold, recover) = {
+                let settings = app.state::<SettingsState>();
+                let settings = settings.0.lock().unwrap();
+                (
+                    settings.heartbeat_interval_secs.max(1),
+                    settings.heartbeat_missed_threshold.max(1),
+                    settings.auto_recover_webview,
+                )
+            };
+            thread::sleep(Duration::from_secs(interval));
+            if heartbeat.stopped.load(Ordering::Relaxed) {
+                return;
+            }
+            let Some(window) = app.get_webview_window("main") else {
+                continue;
+            };
+            // Hidden webviews may be suspended, which isn't a hang.
+            if !window.is_visible().unwrap_or(false) {
+                heartbeat.missed.store(0, Ordering::Relaxed);
+                heartbeat.last_ping.store(0, Ordering::Relaxed);
+                continue;
+            }
+
+            let last_ping = heartbeat.last_ping.load(Ordering::Relaxed);
+            if last_ping != 0 && heartbeat.last_pong.load(Ordering::Relaxed) < last_ping {
+                let missed = heartbeat.missed.fetch_add(1, Ordering::Relaxed) + 1;
+                if missed >= threshold {
+                    heartbeat.missed.store(0, Ordering::Relaxed);
+                    log::warn!("webview missed {missed} pings");
+                    if let Err(e) = app.emit("webview-unresponsive", missed) {
+                        log::error!("failed to emit webview-unresponsive: {e}");
+                    }
+                    if recover {
+                        log::info!("reloading unresponsive webview");
+                        if let Err(e) = window.eval("location.reload()") {
+                            error::report(&app, "failed to reload webview", e);
+                        }
+                    }
+                }
+            } else {
+                heartbeat.missed.store(0, Ordering::Relaxed);
+            }
+
+            let timestamp = now_millis();
+            heartbeat.last_ping.store(timestamp, Ordering::Relaxed);
+            if let Err(e) = window.emit_to("main", "ping", timestamp) {
+                log::error!("failed to emit ping: {e}");
+            }
+        }
+    });
+}
+
+/// False while pings are going unanswered.
+pub fn responsive(app: &AppHandle) -> bool {
+    app.state::<Heartbeat>().missed.load(Ordering::Relaxed) == 0
+}
+
+/// Ends the ping thread at its next wake-up.
+pub fn stop(app: &AppHandle) {
+    if let Some(heartbeat) = app.try_state::<Heartbeat>() {
+        heartbeat.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Answers a `ping` with the timestamp it carried.
+#[tauri::command]
+pub fn pong(app: AppHandle, timestamp: u64) {
+    app.state::<Heartbeat>()
+        .last_pong
+        .fetch_max(timestamp, Ordering::Relaxed);
+}
@@ -0,0 +1,259 @@
+//! Tray menu labels in the user's language.
+
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager};
+
+#[cfg(desktop)]
+use crate::tray;
+use crate::{
+    error::AppError,
+    settings::{self, Settings, SettingsState},
+};
+
+/// Used for unknown languages and for keys a translation is missing.
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+const EN: &[(&str, &str)] = &[
+    ("show", "Show"),
+    ("hide", "Hide"),
+    ("click_through", "Click-Through"),
+    ("always_on_top", "Always on Top"),
+    ("reset_pos", "Reset Position"),
+    ("anchors", "Move to…"),
+    ("monitors", "Move to Monitor"),
+    ("opacity", "Opacity"),
+    ("speed", "Speed"),
+    ("roads", "Road Layout"),
+    ("waypoints", "Waypoints"),
+    ("themes", "Theme"),
+    ("presets", "Presets"),
+    ("next_preset", "Next Preset"),
+    ("paused", "Pause Simulation"),
+    ("export", "Export Snapshot"),
+    ("copy_settings", "Copy Settings"),
+    ("paste_settings", "Paste Settings or Preset"),
+    ("notifications", "Notifications"),
+    ("network_driven", "Network-Driven Traffic"),
+    ("telemetry", "Share Usage Statistics"),
+    ("check_updates", "Check for Updates…"),
+    ("autostart", "Start at Login"),
+    ("close_to_tray", "Close to Tray"),
+    ("settings", "Settings…"),
+    ("open_config", "Open Config Folder"),
+    ("reset_settings", "Reset to Defaults…"),
+    ("quit", "Quit"),
+    ("slow", "Slow"),
+    ("normal", "Normal"),
+    ("fast", "Fast"),
+    ("display", "Display"),
+    ("top_left", "Top Left"),
+    ("top", "Top"),
+    ("top_right", "Top Right"),
+    ("left", "Left"),
+    ("center", "Center"),
+    ("right", "Right"),
+    ("bottom_left", "Bottom Left"),
+    ("bottom", "Bottom"),
+    ("bottom_right", "Bottom Right"),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("show", "Anzeigen"),
+    ("hide", "Ausblenden"),
+    ("click_through", "Durchklicken"),
+    ("always_on_top", "Immer im Vordergrund"),
+    ("reset_pos", "Position zurücksetzen"),
+    ("anchors", "Verschieben nach…"),
+    ("monitors", "Auf Bildschirm verschieben"),
+    ("opacity", "Deckkraft"),
+    ("speed", "Geschwindigkeit"),
+    ("roads", "Straßennetz"),
+    ("waypoints", "Wegpunkte"),
+    ("themes", "Design"),
+    ("presets", "Voreinstellungen"),
+    ("next_preset", "Nächste Voreinstellung"),
+    ("paused", "Simulation anhalten"),
+    ("export", "Snapshot exportieren"),
+    ("copy_settings", "Einstellungen kopieren"),
+    (
+        "paste_settings",
+        "Einstellungen oder Voreinstellung einfügen",
+    ),
+    ("notifications", "Benachrichtigungen"),
+    ("network_driven", "Verkehr nach Netzwerklast"),
+    ("telemetry", "Nutzungsstatistiken teilen"),
+    ("check_updates", "Nach Updates suchen…"),
+    ("autostart", "Bei Anmeldung starten"),
+    ("close_to_tray", "Beim Schließen in den Tray"),
+    ("settings", "Einstellungen…"),
+    ("open_config", "Konfigurationsordner öffnen"),
+    ("reset_settings", "Auf Standard zurücksetzen…"),
+    ("quit", "Beenden"),
+    ("slow", "Langsam"),
+    ("normal", "Normal"),
+    ("fast", "Schnell"),
+    ("display", "Bildschirm"),
+    ("top_left", "Oben links"),
+    ("top", "Oben"),
+    ("top_right", "Oben rechts"),
+    ("left", "Links"),
+    ("center", "Mitte"),
+    ("right", "Rechts"),
+    ("bottom_left", "Unten links"),
+    ("bottom", "Unten"),
+    ("bottom_right", "Unten rechts"),
+];
+
+const JA: &[(&str, &str)] = &[
+    ("show", "表示"),
+    ("hide", "隠す"),
+    ("click_through", "クリックスルー"),
+    ("always_on_top", "常に手前に表示"),
+    ("reset_pos", "位置をリセット"),
+    ("anchors", "移動先…"),
+    ("monitors", "モニターへ移動"),
+    ("opacity", "不透明度"),
+    ("speed", "速度"),
+    ("roads", "道路レイアウト"),
+    ("waypoints", "ウェイポイント"),
+    ("themes", "テーマ"),
+    ("presets", "プリセット"),
+    ("next_preset", "次のプリセット"),
+    ("paused", "シミュレーションを一時停止"),
+    ("export", "スナップショットを書き出す"),
+    ("copy_settings", "設定をコピー"),
+    ("paste_settings", "設定またはプリセットを貼り付け"),
+    ("notifications", "通知"),
+    ("network_driven", "ネットワーク連動の交通量"),
+    ("telemetry", "使用状況の統計を送信"),
+    ("check_updates", "アップデートを確認…"),
+    ("autostart", "ログイン時に起動"),
+    ("close_to_tray", "閉じるとトレイに格納"),
+    ("settings", "設定…"),
+    ("open_config", "設定フォルダを開く"),
+    ("reset_settings", "初期設定に戻す…"),
+    ("quit", "終了"),
+    ("slow", "遅い"),
+    ("normal", "普通"),
+    ("fast", "速い"),
+    ("display", "ディスプレイ"),
+    ("top_left", "左上"),
+    ("top", "上"),
+    ("top_right", "右上"),
+    ("left", "左"),
+    ("center", "中央"),
+    ("right", "右"),
+    ("bottom_left", "左下"),
+    ("bottom", "下"),
+    ("bottom_right", "右下"),
+];
+
+const TABLES: &[(&str, &[(&str, &str)])] = &[("en", EN), ("de", DE), ("ja", JA)];
+
+/// The language the tray is currently labelled in.
+pub struct LocaleState(Mutex<&'static str>);
+
+impl Default for LocaleState {
+    fn default() -> Self {
+        Self(Mutex::new(FALLBACK_LANGUAGE))
+    }
+}
+
+/// The translated language for a locale like `de-AT` or `ja_JP`, or English.
+pub fn language(locale: &str) -> &'static str {
+    let code = locale.split(['-', '_', '.']).next().unwrap_or_default();
+    TABLES
+        .iter()
+        .map(|(language, _)| *language)
+        .find(|language| language.eq_ignore_ascii_case(code))
+        .unwrap_or(FALLBACK_LANGUAGE)
+}
+
+fn lookup(table: &[(&str, &'static str)], key: &str) -> Option<&'static str> {
+    table.iter().find(|(k, _)| *k == key).map(|(_, text)| *text)
+}
+
+/// `key` in `language`, falling back to English and then to the key itself.
+pub fn text(language: &str, key: &'static str) -> &'static str {
+    TABLES
+        .iter()
+        .find(|(l, _)| *l == language)
+        .and_then(|(_, table)| lookup(table, key))
+        .or_else(|| lookup(EN, key))
+        .unwrap_or(key)
+}
+
+/// The language the tray should use: the saved choice, else the system's.
+fn resolve(settings: &Settings) -> &'static str {
+    match &settings.locale {
+        Some(locale) => language(locale),
+        None => sys_locale::get_locale().map_or(FALLBACK_LANGUAGE, |l| language(&l)),
+    }
+}
+
+/// The current tray language.
+pub fn current(app: &AppHandle) -> &'static str {
+    *app.state::<LocaleState>().0.lock().unwrap()
+}
+
+/// Switches to the language `settings` asks for, relabelling the tray if it
+/// changed.
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    let language = resolve(settings);
+    let state = app.state::<LocaleState>();
+    let previous = std::mem::replace(&mut *state.0.lock().unwrap(), language);
+    if previous != language {
+        log::info!("tray language is now {language}");
+        #[cfg(desktop)]
+        tray::rebuild(app);
+    }
+}
+
+/// Saves `locale` and relabels the tray. Unknown locales show English.
+#[tauri::command]
+pub fn set_locale(app: AppHandle, locale: String) -> Result<(), AppError> {
+    settings::update(&app, |s| s.locale = Some(locale)).map_err(AppError::Io)?;
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    load_settings(&app, &settings);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_ignores_region_and_case() {
+        assert_eq!(language("de-AT"), "de");
+        assert_eq!(language("ja_JP.UTF-8"), "ja");
+        assert_eq!(language("EN-us"), "en");
+    }
+
+    #[test]
+    fn unknown_language_falls_back_to_english() {
+        assert_eq!(language("fr-FR"), FALLBACK_LANGUAGE);
+        assert_eq!(language(""), FALLBACK_LANGUAGE);
+        assert_eq!(text("fr", "quit"), "Quit");
+    }
+
+    #[test]
+    fn translations_are_used() {
+        assert_eq!(text("de", "quit"), "Beenden");
+        assert_eq!(text("ja", "quit"), "終了");
+    }
+
+    #[test]
+    fn unknown_key_is_shown_as_is() {
+        assert_eq!(text("de", "no_such_key"), "no_such_key");
+    }
+
+    #[test]
+    fn every_translation_covers_every_english_key() {
+        for (language, table) in TABLES {
+            for (key, _) in EN {
+                assert!(lookup(table, key).is_some(), "{language} is missing {key}");
+            }
+        }
+    }
+}
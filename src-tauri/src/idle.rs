@@ -0,0 +1,120 @@
+//! How long since the user last touched the keyboard or mouse, system-wide.
+//! This works regardless of whether the overlay is click-through.
+
+use std::time::Duration;
+
+pub const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 120;
+
+#[cfg(target_os = "windows")]
+pub struct IdleMonitor;
+
+#[cfg(target_os = "windows")]
+impl IdleMonitor {
+    pub fn new() -> Option<Self> {
+        Some(Self)
+    }
+
+    pub fn idle_time(&mut self) -> Option<Duration> {
+        use windows::Win32::{
+            System::SystemInformation::GetTickCount,
+            UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO},
+        };
+
+        let mut info = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        unsafe {
+            if !GetLastInputInfo(&mut info).as_bool() {
+                return None;
+            }
+            // Both are 32-bit millisecond tick counts that wrap every ~49 days.
+            Some(Duration::from_millis(
+                GetTickCount().wrapping_sub(info.dwTime) as u64,
+            ))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub struct IdleMonitor;
+
+#[cfg(target_os = "macos")]
+impl IdleMonitor {
+    pub fn new() -> Option<Self> {
+        Some(Self)
+    }
+
+    pub fn idle_time(&mut self) -> Option<Duration> {
+        #[link(name = "CoreGraphics", kind = "framework")]
+        extern "C" {
+            fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+        }
+        const COMBINED_SESSION_STATE: i32 = 0;
+        const ANY_INPUT_EVENT: u32 = !0;
+
+        let secs = unsafe {
+            CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT)
+        };
+        Duration::try_from_secs_f64(secs).ok()
+    }
+}
+
+/// Queries the X screensaver extension. libX11 and libXss are loaded at
+/// runtime, so Wayland-only systems without them simply never go idle.
+#[cfg(target_os = "linux")]
+pub struct IdleMonitor {
+    xlib: x11_dl::xlib::Xlib,
+    xss: x11_dl::xss::Xss,
+    display: *mut x11_dl::xlib::Display,
+}
+
+#[cfg(target_os = "linux")]
+impl IdleMonitor {
+    pub fn new() -> Option<Self> {
+        let xlib = x11_dl::xlib::Xlib::open().ok()?;
+        let xss = x11_dl::xss::Xss::open().ok()?;
+        let display = unsafe { (xlib.XOpenDisplay)(std::ptr::null()) };
+        if display.is_null() {
+            return None;
+        }
+        Some(Self { xlib, xss, display })
+    }
+
+    pub fn idle_time(&mut self) -> Option<Duration> {
+        unsafe {
+            let info = (self.xss.XScreenSaverAllocInfo)();
+            if info.is_null() {
+                return None;
+            }
+            let root = (self.xlib.XDefaultRootWindow)(self.display);
+            let status = (self.xss.XScreenSaverQueryInfo)(self.display, root, info);
+            // `c_ulong` is only 32 bits on some targets.
+            #[allow(clippy::unnecessary_cast)]
+            let idle = (*info).idle as u64;
+            (self.xlib.XFree)(info.cast());
+            (status != 0).then(|| Duration::from_millis(idle))
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for IdleMonitor {
+    fn drop(&mut self) {
+        unsafe { (self.xlib.XCloseDisplay)(self.display) };
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+pub struct IdleMonitor;
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+impl IdleMonitor {
+    pub fn new() -> Option<Self> {
+        None
+    }
+
+    pub fn idle_time(&mut self) -> Option<Duration> {
+        None
+    }
+}
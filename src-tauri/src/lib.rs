@@ -1,66 +1,433 @@
-use tauri::{
-    menu::{Menu, MenuItem},
-    tray::TrayIconBuilder,
-    Emitter, Manager,
-};
+// Much of the window management is only reachable from the tray and global
+// shortcuts, neither of which exists on mobile.
+#![cfg_attr(mobile, allow(dead_code))]
+
+#[cfg(desktop)]
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+use serde::Serialize;
+#[cfg(desktop)]
+use tauri::Emitter;
+use tauri::{DragDropEvent, Manager, RunEvent, WindowEvent};
+
+mod artifacts;
+#[cfg(desktop)]
+mod audio;
+mod autostart;
+#[cfg(desktop)]
+mod capture;
+mod cli;
+mod clipboard;
+mod collision;
+mod crash;
+mod daylight;
+mod deeplink;
+mod error;
+mod focus;
+#[cfg(desktop)]
+mod foreground;
+mod frame;
+mod heartbeat;
+mod i18n;
+mod idle;
+#[cfg(target_os = "linux")]
+mod linux_clickthrough;
+mod logging;
+mod metrics;
+mod monitors;
+mod motion;
+mod netstat;
+mod notify;
+mod opacity;
+mod overlay;
+mod platform;
+mod position;
+mod power;
+mod presets;
+mod ready;
+mod recording;
+mod region;
+mod road;
+mod settings;
+#[cfg(desktop)]
+mod settings_window;
+#[cfg(desktop)]
+mod shortcut;
+mod sim;
+mod snapshot;
+mod stats;
+mod telemetry;
+mod theme;
+mod transparency;
+#[cfg(desktop)]
+mod tray;
+#[cfg(desktop)]
+mod tray_load;
+#[cfg(desktop)]
+mod updater;
+mod viewport;
+mod watch;
+
+#[cfg(desktop)]
+use audio::AudioState;
+use daylight::Daylight;
+use focus::FocusState;
+#[cfg(desktop)]
+use foreground::ForegroundState;
+use frame::FrameAcks;
+use heartbeat::Heartbeat;
+use i18n::LocaleState;
+use metrics::MetricsState;
+use monitors::MonitorWatch;
+use motion::MotionState;
+use netstat::NetworkRate;
+use notify::NotifyState;
+use overlay::{AppState, Overlays};
+use power::PowerState;
+use ready::ReadyState;
+use recording::RecordingState;
+use region::InteractiveRegions;
+use road::RoadState;
+use settings::{ConfigOverride, Settings, SettingsState};
+#[cfg(desktop)]
+use shortcut::{NudgeShortcuts, PresetShortcuts, ToggleShortcut};
+use sim::SimState;
+use telemetry::{Event, TelemetryState};
+use theme::ThemeState;
+use transparency::Transparency;
+#[cfg(desktop)]
+use tray_load::TrayLoad;
+#[cfg(desktop)]
+use updater::PendingUpdate;
+use watch::LastSave;
+
+/// Command line forwarded from a launch that found the app already running.
+#[derive(Clone, Serialize)]
+struct SecondInstance {
+    args: Vec<String>,
+    cwd: String,
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_autostart::init(
-            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
-            None,
-        ))
-        .setup(|app| {
-            let show = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
-            let hide = MenuItem::with_id(app, "hide", "Hide", true, None::<&str>)?;
-            let click_through =
-                MenuItem::with_id(app, "click_through", "Toggle Click-Through", true, None::<&str>)?;
-            let reset_pos =
-                MenuItem::with_id(app, "reset_pos", "Reset Position", true, None::<&str>)?;
-            let autostart =
-                MenuItem::with_id(app, "autostart", "Start at Login", true, None::<&str>)?;
-            let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let args = cli::parse_env();
 
-            let menu = Menu::with_items(app, &[&show, &hide, &click_through, &reset_pos, &autostart, &quit])?;
+    #[allow(unused_mut)]
+    let mut builder = tauri::Builder::default();
+    // Must be registered first so a second launch exits before doing any setup.
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+            log::info!("second instance launched with {args:?}");
+            let Some(window) = app.get_webview_window("main") else {
+                log::warn!("main window not found");
+                return;
+            };
+            if let Err(e) = overlay::show(&window) {
+                log::error!("failed to show window: {e}");
+            }
+            if let Err(e) = window.set_focus() {
+                log::warn!("failed to focus window: {e}");
+            }
+            if let Err(e) = window.emit("second-instance", SecondInstance { args, cwd }) {
+                log::error!("failed to emit second-instance: {e}");
+            }
+        }));
+    }
+    builder = builder
+        .plugin(logging::plugin(args.verbose))
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_opener::init());
+    #[cfg(desktop)]
+    {
+        builder = builder
+            .plugin(tauri_plugin_autostart::init(
+                tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+                Some(vec![autostart::AUTOSTART_ARG]),
+            ))
+            .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+            .plugin(tauri_plugin_updater::Builder::new().build())
+            .manage(ForegroundState::default())
+            .manage(AudioState::default())
+            .manage(ToggleShortcut::default())
+            .manage(PresetShortcuts::default())
+            .manage(NudgeShortcuts::default())
+            .manage(TrayLoad::default())
+            .manage(PendingUpdate::default());
+    }
 
-            TrayIconBuilder::new()
-                .menu(&menu)
-                .on_menu_event(|app, event| match event.id.as_ref() {
-                    "show" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                        }
-                    }
-                    "hide" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.hide();
-                        }
-                    }
-                    "click_through" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("toggle-click-through", ());
-                        }
-                    }
-                    "reset_pos" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("reset-position", ());
-                        }
-                    }
-                    "autostart" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("toggle-autostart", ());
-                        }
+    builder
+        .manage(AppState::default())
+        .manage(SimState::default())
+        .manage(NotifyState::default())
+        .manage(Overlays::default())
+        .manage(MonitorWatch::default())
+        .manage(FocusState::default())
+        .manage(NetworkRate::default())
+        .manage(LastSave::default())
+        .manage(PowerState::default())
+        .manage(MotionState::default())
+        .manage(RecordingState::default())
+        .manage(ReadyState::default())
+        .manage(TelemetryState::default())
+        .manage(InteractiveRegions::default())
+        .manage(Heartbeat::default())
+        .manage(FrameAcks::default())
+        .manage(ThemeState::default())
+        .manage(Daylight::default())
+        .manage(MetricsState::default())
+        .manage(LocaleState::default())
+        .manage(RoadState::default())
+        .manage(Transparency::default())
+        .invoke_handler(tauri::generate_handler![
+            artifacts::delete_artifact,
+            artifacts::list_artifacts,
+            #[cfg(desktop)]
+            audio::set_audio_reactive,
+            autostart::launched_by_autostart,
+            #[cfg(desktop)]
+            capture::capture_overlay,
+            clipboard::export_to_clipboard,
+            clipboard::import_from_clipboard,
+            daylight::get_time_of_day,
+            daylight::set_auto_day_night,
+            daylight::set_time_of_day,
+            #[cfg(desktop)]
+            foreground::set_app_allowlist,
+            frame::ack_frame,
+            heartbeat::pong,
+            i18n::set_locale,
+            metrics::get_metrics,
+            motion::get_reduce_motion,
+            monitors::list_monitors,
+            notify::notify,
+            opacity::set_window_opacity,
+            overlay::close_overlay,
+            overlay::is_click_through,
+            overlay::set_over_fullscreen,
+            #[cfg(desktop)]
+            overlay::spawn_overlay,
+            platform::capabilities,
+            platform::platform_info,
+            position::anchor_window,
+            position::get_window_geometry,
+            position::nudge_window,
+            position::resize_window,
+            position::save_window_position,
+            position::saved_window_position,
+            position::snap_if_near,
+            presets::apply_preset,
+            presets::cycle_preset,
+            presets::fetch_preset,
+            ready::frontend_ready,
+            recording::start_recording,
+            recording::stop_recording,
+            region::set_interactive_region,
+            road::get_road_network,
+            road::set_road_network,
+            settings::get_settings,
+            settings::reset_settings,
+            settings::set_settings,
+            #[cfg(desktop)]
+            shortcut::set_nudge_modifier,
+            #[cfg(desktop)]
+            shortcut::set_preset_shortcuts,
+            #[cfg(desktop)]
+            shortcut::set_toggle_shortcut,
+            sim::get_seed,
+            sim::run_for,
+            sim::set_boundary_behavior,
+            sim::set_global_force,
+            sim::set_max_vehicles,
+            sim::set_overlay_mode,
+            sim::set_overlay_params,
+            sim::set_paused,
+            sim::set_seed,
+            sim::set_sim_params,
+            sim::set_tick_rate,
+            sim::set_timestep,
+            sim::set_trails,
+            sim::spawn_burst,
+            sim::step,
+            snapshot::export_state,
+            snapshot::import_state,
+            stats::get_stats,
+            stats::reset_stats,
+            telemetry::set_telemetry,
+            theme::get_theme,
+            theme::list_themes,
+            theme::set_theme,
+            transparency::is_transparency_unavailable,
+            #[cfg(desktop)]
+            updater::install_update,
+            viewport::get_viewport,
+            viewport::get_waypoints,
+            viewport::go_to_waypoint,
+            viewport::remove_waypoint,
+            viewport::save_waypoint,
+            viewport::set_viewport,
+        ])
+        .setup(move |app| {
+            crash::install_hook(app.handle().clone());
+            // After the logger is up, so the detected path is logged.
+            let launched_by_autostart = autostart::detect(&args);
+            app.manage(launched_by_autostart);
+            if let Some(config) = args.config {
+                app.manage(ConfigOverride(config));
+            }
+            let settings = Settings::load(app.handle());
+            overlay::load_settings(app.handle(), &settings);
+            sim::load_settings(app.handle(), &settings);
+            motion::load_settings(app.handle(), &settings);
+            platform::set_dock_hidden(app.handle(), settings.hide_from_dock);
+            telemetry::load_settings(app.handle(), &settings);
+            theme::load_settings(app.handle(), &settings);
+            daylight::load_settings(app.handle(), &settings);
+            i18n::load_settings(app.handle(), &settings);
+            road::load_settings(app.handle(), &settings);
+            telemetry::record(app.handle(), Event::AppStart);
+
+            #[cfg(desktop)]
+            {
+                tray_load::load_settings(app.handle(), &settings);
+                audio::load_settings(app.handle(), &settings);
+                if args.click_through {
+                    app.state::<AppState>()
+                        .click_through
+                        .store(true, Ordering::Relaxed);
+                }
+                if let Some(window) = app.get_webview_window("main") {
+                    position::restore_saved(&window, &settings);
+                    transparency::check(app.handle(), &window, &settings);
+                    let quiet_login = settings.start_hidden_on_autostart && launched_by_autostart.0;
+                    if args.hidden || quiet_login {
+                        overlay::apply_window_state(&window, &overlay::window_state(&window))?;
+                    } else {
+                        overlay::show(&window)?;
                     }
-                    "quit" => {
-                        app.exit(0);
+                }
+                overlay::restore_open(app.handle(), &settings);
+                autostart::refresh(app.handle());
+                if let Err(e) = shortcut::register_toggle(app.handle(), &settings.toggle_shortcut) {
+                    log::warn!("failed to register toggle shortcut: {e}");
+                    if let Err(e) =
+                        shortcut::register_toggle(app.handle(), settings::DEFAULT_TOGGLE_SHORTCUT)
+                    {
+                        error::report(
+                            app.handle(),
+                            "failed to register the default toggle shortcut",
+                            e,
+                        );
                     }
-                    _ => {}
-                })
-                .build(app)?;
+                }
+                // Often taken by the desktop for switching workspaces.
+                if let Err(e) = shortcut::register_preset_cycle(
+                    app.handle(),
+                    &settings.next_preset_shortcut,
+                    &settings.previous_preset_shortcut,
+                ) {
+                    log::warn!("failed to register preset shortcuts: {e}");
+                }
+                if let Err(e) = shortcut::register_nudge(app.handle(), &settings.nudge_modifier) {
+                    log::warn!("failed to register nudge shortcuts: {e}");
+                }
+            }
+            // No tray, click-through or free positioning on mobile; the
+            // window always fills the screen.
+            #[cfg(mobile)]
+            if let Some(window) = app.get_webview_window("main") {
+                overlay::show(&window)?;
+            }
+
+            let prompt_telemetry = !settings.telemetry_prompted;
+            app.manage(SettingsState(Mutex::new(settings)));
+            stats::init(app.handle());
+            transparency::announce(app.handle());
+            monitors::init(app.handle());
+            deeplink::init(app.handle());
+
+            #[cfg(desktop)]
+            {
+                tray::create(app)?;
+                foreground::start(app.handle().clone());
+                region::start(app.handle().clone());
+                updater::start(app.handle().clone());
+            }
+            if prompt_telemetry {
+                telemetry::prompt(app.handle());
+            }
+            if let Err(e) = watch::start(app.handle()) {
+                log::warn!("not watching settings for changes: {e}");
+            }
+            sim::start(app.handle().clone());
+            netstat::start(app.handle().clone());
+            power::start(app.handle().clone());
+            motion::start(app.handle().clone());
+            daylight::start(app.handle().clone());
+            heartbeat::start(app.handle().clone());
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .on_window_event(|window, event| match event {
+            // There's no display-change event; the OS moves, resizes or
+            // rescales windows when a monitor comes or goes.
+            WindowEvent::Moved(_) | WindowEvent::ScaleFactorChanged { .. } => {
+                monitors::schedule_check(window.app_handle());
+            }
+            WindowEvent::Resized(_) => {
+                monitors::schedule_check(window.app_handle());
+                if let Some(webview) = window.app_handle().get_webview_window(window.label()) {
+                    region::on_resized(&webview);
+                }
+            }
+            WindowEvent::Focused(_) => focus::schedule_check(window.app_handle()),
+            #[cfg(desktop)]
+            WindowEvent::CloseRequested { api, .. } => {
+                tray::on_close_requested(window.app_handle(), window.label(), api);
+            }
+            WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) => {
+                let snapshot = paths
+                    .iter()
+                    .find(|p| p.extension().is_some_and(|ext| ext == "json"));
+                if let Some(path) = snapshot {
+                    if let Err(e) = snapshot::import(window.app_handle(), path) {
+                        let context = format!("failed to import {}", path.display());
+                        error::report(window.app_handle(), &context, e);
+                    }
+                }
+            }
+            WindowEvent::Destroyed => {
+                overlay::forget(window.app_handle(), window.label());
+                frame::forget(window.app_handle(), window.label());
+                window
+                    .app_handle()
+                    .state::<SimState>()
+                    .forget_overlay(window.label());
+                region::forget(window.app_handle(), window.label());
+            }
+            _ => {}
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Covers the tray's Quit, Cmd+Q and logout.
+            if let RunEvent::ExitRequested { code, api, .. } = event {
+                // Closing the last window leaves the app in the tray, where
+                // "Show" brings the window back.
+                if code.is_none() {
+                    api.prevent_exit();
+                    return;
+                }
+                heartbeat::stop(app);
+                settings::flush(app);
+                stats::flush(app);
+                overlay::close_all(app);
+                #[cfg(desktop)]
+                settings_window::close(app);
+            }
+        });
 }
@@ -0,0 +1,83 @@
+//! Click-through on Linux, where what works depends on the display server.
+//! Tauri's own call leaves a 1px input shape on the toplevel, which some
+//! Wayland compositors ignore.
+
+use gtk::{
+    cairo::{RectangleInt, Region},
+    prelude::*,
+};
+use tauri::{Emitter, WebviewWindow};
+
+use crate::{
+    platform::{self, DisplayServer},
+    region::Rect,
+};
+
+/// Makes the window click-through except for `interactive`, which keeps
+/// taking clicks.
+pub fn set_ignore_cursor_events(
+    window: &WebviewWindow,
+    ignore: bool,
+    interactive: Option<Rect>,
+) -> tauri::Result<()> {
+    let Some(server) = platform::display_server() else {
+        log::info!("click-through: unknown display server, using the default handling");
+        return window.set_ignore_cursor_events(ignore);
+    };
+    // GTK objects may only be touched on the main thread.
+    let target = window.clone();
+    window.run_on_main_thread(move || {
+        if let Err(reason) = apply(&target, server, ignore, interactive) {
+            log::warn!("click-through unsupported: {reason}");
+            if let Err(e) = target.emit("clickthrough-unsupported", &reason) {
+                log::error!("failed to emit clickthrough-unsupported: {e}");
+            }
+            if let Err(e) = target.set_ignore_cursor_events(ignore) {
+                log::error!("failed to set click-through: {e}");
+            }
+        }
+    })
+}
+
+fn apply(
+    window: &WebviewWindow,
+    server: DisplayServer,
+    ignore: bool,
+    interactive: Option<Rect>,
+) -> Result<(), String> {
+    let gtk_window = window.gtk_window().map_err(|e| e.to_string())?;
+    if !ignore {
+        // Clearing the shape is the same on both backends.
+        gtk_window.input_shape_combine_region(None);
+        return Ok(());
+    }
+    // GTK sizes are in logical pixels, like the rect.
+    let width = gtk_window.allocated_width() as f64;
+    let height = gtk_window.allocated_height() as f64;
+    let shape = match interactive.and_then(|r| r.clip(width, height)) {
+        Some(r) => Region::create_rectangle(&RectangleInt::new(
+            r.x.round() as i32,
+            r.y.round() as i32,
+            r.width.round() as i32,
+            r.height.round() as i32,
+        )),
+        None => Region::create(),
+    };
+    match server {
+        DisplayServer::Wayland => {
+            log::info!("click-through: Wayland input region {interactive:?}");
+            // GTK keeps this across re-realizes and passes it to the
+            // compositor as the surface's input region.
+            gtk_window.input_shape_combine_region(Some(&shape));
+        }
+        DisplayServer::X11 => {
+            let gdk_window = gtk_window.window().ok_or("window is not realized yet")?;
+            if !gdk_window.display().supports_input_shapes() {
+                return Err("the X server has no SHAPE input extension".into());
+            }
+            log::info!("click-through: X11 input shape {interactive:?}");
+            gdk_window.input_shape_combine_region(&shape, 0, 0);
+        }
+    }
+    Ok(())
+}
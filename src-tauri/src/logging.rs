@@ -0,0 +1,35 @@
+use log::LevelFilter;
+use tauri::{plugin::TauriPlugin, Wry};
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+/// Environment variable holding the log level, e.g. `RUST_LOG=debug`.
+const LEVEL_ENV: &str = "RUST_LOG";
+/// Size at which the log file is rotated.
+const MAX_FILE_SIZE: u128 = 5 * 1024 * 1024;
+/// Rotated log files kept alongside the current one.
+const KEEP_FILES: usize = 2;
+
+/// Picks the log level: `--verbose` wins, then `RUST_LOG`, then `info`.
+fn level(verbose: bool) -> LevelFilter {
+    if verbose {
+        return LevelFilter::Debug;
+    }
+    match std::env::var(LEVEL_ENV) {
+        Ok(value) => value.trim().parse().unwrap_or(LevelFilter::Info),
+        Err(_) => LevelFilter::Info,
+    }
+}
+
+/// Logs to stderr and to a size-capped file in the app log dir.
+pub fn plugin(verbose: bool) -> TauriPlugin<Wry> {
+    tauri_plugin_log::Builder::new()
+        .clear_targets()
+        .targets([
+            Target::new(TargetKind::Stderr),
+            Target::new(TargetKind::LogDir { file_name: None }),
+        ])
+        .level(level(verbose))
+        .max_file_size(MAX_FILE_SIZE)
+        .rotation_strategy(RotationStrategy::KeepSome(KEEP_FILES))
+        .build()
+}
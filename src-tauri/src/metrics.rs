@@ -0,0 +1,160 @@
+//! How well the tick loop is keeping up, kept as rolling averages so the
+//! loop never stores a history.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+#[cfg(desktop)]
+use crate::tray;
+
+/// Weight of the newest sample in each rolling average.
+const SMOOTHING: f64 = 0.1;
+/// How often process usage is sampled, `metrics` emitted and the tray
+/// tooltip updated.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Metrics {
+    /// Ticks actually run per second.
+    pub tick_rate: f64,
+    /// Time spent stepping and emitting each tick, in milliseconds.
+    pub tick_duration_ms: f64,
+    /// Time spent serializing and emitting each frame, in milliseconds.
+    pub emit_latency_ms: f64,
+    pub vehicles: usize,
+    /// Percent of one core used by the whole app.
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+}
+
+/// The latest metrics, and a flag asking the tick loop to start its
+/// averages over.
+#[derive(Default)]
+pub struct MetricsState {
+    metrics: Mutex<Metrics>,
+    reset: AtomicBool,
+}
+
+/// An exponential moving average that starts from its first sample.
+#[derive(Default)]
+struct Average(Option<f64>);
+
+impl Average {
+    fn add(&mut self, sample: f64) -> f64 {
+        let average = match self.0 {
+            Some(prev) => prev + SMOOTHING * (sample - prev),
+            None => sample,
+        };
+        self.0 = Some(average);
+        average
+    }
+}
+
+/// Owned by the tick loop, which feeds it one sample per tick.
+pub struct Recorder {
+    system: System,
+    pid: Option<Pid>,
+    last_tick: Option<Instant>,
+    reported: Instant,
+    interval: Average,
+    duration: Average,
+    emit: Average,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        let pid = sysinfo::get_current_pid()
+            .map_err(|e| log::warn!("process usage is unavailable: {e}"))
+            .ok();
+        Self {
+            system: System::new(),
+            pid,
+            last_tick: None,
+            reported: Instant::now(),
+            interval: Average::default(),
+            duration: Average::default(),
+            emit: Average::default(),
+        }
+    }
+
+    /// Records a tick that started at `started` and spent `emit` sending its
+    /// frame, if one was sent.
+    pub fn tick(
+        &mut self,
+        app: &AppHandle,
+        started: Instant,
+        emit: Option<Duration>,
+        vehicles: usize,
+    ) {
+        let state = app.state::<MetricsState>();
+        if state.reset.swap(false, Ordering::Relaxed) {
+            self.last_tick = None;
+            self.interval = Average::default();
+            self.duration = Average::default();
+            self.emit = Average::default();
+        }
+
+        let mut metrics = *state.metrics.lock().unwrap();
+        if let Some(last) = self.last_tick.replace(started) {
+            let interval = self.interval.add((started - last).as_secs_f64());
+            metrics.tick_rate = if interval > 0.0 { 1.0 / interval } else { 0.0 };
+        }
+        metrics.tick_duration_ms = self.duration.add(millis(started.elapsed()));
+        if let Some(emit) = emit {
+            metrics.emit_latency_ms = self.emit.add(millis(emit));
+        }
+        metrics.vehicles = vehicles;
+
+        if self.reported.elapsed() >= REPORT_INTERVAL {
+            self.reported = Instant::now();
+            self.sample_process(&mut metrics);
+            if let Err(e) = app.emit("metrics", metrics) {
+                log::error!("failed to emit metrics: {e}");
+            }
+            #[cfg(desktop)]
+            tray::set_status(app, metrics.vehicles, metrics.tick_rate);
+        }
+        *state.metrics.lock().unwrap() = metrics;
+    }
+
+    fn sample_process(&mut self, metrics: &mut Metrics) {
+        let Some(pid) = self.pid else {
+            return;
+        };
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            true,
+            ProcessRefreshKind::nothing().with_cpu().with_memory(),
+        );
+        if let Some(process) = self.system.process(pid) {
+            metrics.cpu_percent = process.cpu_usage();
+            metrics.memory_bytes = process.memory();
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Starts the rolling averages over on the next tick, so figures from before
+/// a pause or resume don't blend into the new ones.
+pub fn reset(app: &AppHandle) {
+    app.state::<MetricsState>()
+        .reset
+        .store(true, Ordering::Relaxed);
+}
+
+#[tauri::command]
+pub fn get_metrics(state: State<MetricsState>) -> Metrics {
+    *state.metrics.lock().unwrap()
+}
@@ -0,0 +1,130 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+#[cfg(desktop)]
+use crate::tray;
+use crate::{
+    error::{self, AppError},
+    overlay, position,
+    settings::WindowPosition,
+};
+
+/// How long to wait for a burst of display events to settle. Docking
+/// stations fire several in a row.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub position: PhysicalPosition<i32>,
+    pub size: PhysicalSize<u32>,
+    pub scale_factor: f64,
+    pub primary: bool,
+}
+
+/// Describes `monitors` in order, so each index matches what
+/// `spawn_overlay` and the tray's monitor menu use.
+fn describe(monitors: &[Monitor], primary: Option<&Monitor>) -> Vec<MonitorInfo> {
+    monitors
+        .iter()
+        .map(|m| MonitorInfo {
+            name: m.name().cloned(),
+            position: *m.position(),
+            size: *m.size(),
+            scale_factor: m.scale_factor(),
+            primary: primary.is_some_and(|p| p.position() == m.position() && p.name() == m.name()),
+        })
+        .collect()
+}
+
+/// The last seen monitor layout, and whether a re-check is already queued.
+#[derive(Default)]
+pub struct MonitorWatch {
+    last: Mutex<Vec<MonitorInfo>>,
+    pending: AtomicBool,
+}
+
+fn current(app: &AppHandle) -> tauri::Result<Vec<MonitorInfo>> {
+    Ok(describe(
+        &app.available_monitors()?,
+        app.primary_monitor()?.as_ref(),
+    ))
+}
+
+/// Records the starting layout so the first change is detected against it.
+pub fn init(app: &AppHandle) {
+    match current(app) {
+        Ok(monitors) => *app.state::<MonitorWatch>().last.lock().unwrap() = monitors,
+        Err(e) => log::warn!("failed to list monitors: {e}"),
+    }
+}
+
+/// Queues a re-check of the monitor layout once events stop arriving.
+pub fn schedule_check(app: &AppHandle) {
+    let watch = app.state::<MonitorWatch>();
+    if watch.pending.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(DEBOUNCE);
+        app.state::<MonitorWatch>()
+            .pending
+            .store(false, Ordering::Release);
+        check(&app);
+    });
+}
+
+/// Emits `monitors-changed` and pulls stranded windows back on screen if the
+/// connected displays differ from the last check.
+fn check(app: &AppHandle) {
+    let monitors = match current(app) {
+        Ok(monitors) => monitors,
+        Err(e) => {
+            log::warn!("failed to list monitors: {e}");
+            return;
+        }
+    };
+    let watch = app.state::<MonitorWatch>();
+    {
+        let mut last = watch.last.lock().unwrap();
+        if *last == monitors {
+            return;
+        }
+        *last = monitors.clone();
+    }
+    log::info!("monitor layout changed: {} connected", monitors.len());
+
+    #[cfg(desktop)]
+    tray::refresh_monitors(app);
+    for label in overlay::labels(app) {
+        let Some(window) = app.get_webview_window(&label) else {
+            continue;
+        };
+        let result = window.outer_position().and_then(|pos| {
+            position::restore_position(&window, WindowPosition { x: pos.x, y: pos.y })
+        });
+        if let Err(e) = result {
+            error::report(app, &format!("failed to keep {label} on screen"), e);
+        }
+    }
+    if let Err(e) = app.emit("monitors-changed", &monitors) {
+        log::error!("failed to emit monitors-changed: {e}");
+    }
+}
+
+#[tauri::command]
+pub fn list_monitors(window: WebviewWindow) -> Result<Vec<MonitorInfo>, AppError> {
+    let monitors = window.available_monitors()?;
+    let primary = window.primary_monitor()?;
+    Ok(describe(&monitors, primary.as_ref()))
+}
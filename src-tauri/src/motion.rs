@@ -0,0 +1,134 @@
+//! Follows the OS "reduce motion" accessibility setting. It's polled rather
+//! than subscribed to; the setting rarely changes and each check is cheap.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    settings::{Settings, SettingsState},
+    sim::{SimParams, SimState},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// The tick rate is capped at this while motion is reduced.
+pub const REDUCED_TICK_RATE: u32 = 15;
+
+#[derive(Default)]
+pub struct MotionState {
+    /// What the OS asks for.
+    requested: AtomicBool,
+    /// Whether the app is honoring it, i.e. also `respect_reduce_motion`.
+    reduced: AtomicBool,
+}
+
+#[cfg(target_os = "windows")]
+fn os_reduces_motion() -> bool {
+    use windows::{
+        core::BOOL,
+        Win32::UI::WindowsAndMessaging::{
+            SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+        },
+    };
+
+    let mut animations = BOOL(1);
+    let result = unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            Some(&mut animations as *mut BOOL as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+    result.is_ok() && !animations.as_bool()
+}
+
+#[cfg(target_os = "macos")]
+fn os_reduces_motion() -> bool {
+    use objc2::{class, msg_send, runtime::AnyObject};
+
+    // SAFETY: NSWorkspace is thread-safe and the property is a plain BOOL.
+    unsafe {
+        let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+        msg_send![workspace, accessibilityDisplayShouldReduceMotion]
+    }
+}
+
+/// GNOME and desktops that share its settings; elsewhere motion is never
+/// reduced.
+#[cfg(target_os = "linux")]
+fn os_reduces_motion() -> bool {
+    std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "enable-animations"])
+        .output()
+        .is_ok_and(|out| out.status.success() && out.stdout.trim_ascii() == b"false")
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn os_reduces_motion() -> bool {
+    false
+}
+
+/// Whether the simulation should currently calm down.
+pub fn reduced(app: &AppHandle) -> bool {
+    app.state::<MotionState>().reduced.load(Ordering::Relaxed)
+}
+
+/// `params` as the canvas should use them: no trails while motion is reduced.
+pub fn adjust(app: &AppHandle, params: SimParams) -> SimParams {
+    if !reduced(app) {
+        return params;
+    }
+    SimParams {
+        trail_enabled: false,
+        ..params
+    }
+}
+
+/// Recomputes whether motion is reduced and tells the UI if that changed.
+fn update(app: &AppHandle, respect: bool) {
+    let state = app.state::<MotionState>();
+    let reduced = respect && state.requested.load(Ordering::Relaxed);
+    if state.reduced.swap(reduced, Ordering::Relaxed) == reduced {
+        return;
+    }
+    log::info!("motion is {}", if reduced { "reduced" } else { "normal" });
+    if let Err(e) = app.emit("reduce-motion", reduced) {
+        log::error!("failed to emit reduce-motion: {e}");
+    }
+    let params = *app.state::<SimState>().params.lock().unwrap();
+    if let Err(e) = app.emit("sim-params", adjust(app, params)) {
+        log::error!("failed to emit sim-params: {e}");
+    }
+}
+
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    update(app, settings.respect_reduce_motion);
+}
+
+/// Spawns the thread that polls the OS setting.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || loop {
+        let requested = os_reduces_motion();
+        app.state::<MotionState>()
+            .requested
+            .store(requested, Ordering::Relaxed);
+        let respect = app
+            .state::<SettingsState>()
+            .0
+            .lock()
+            .unwrap()
+            .respect_reduce_motion;
+        update(&app, respect);
+        thread::sleep(POLL_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_reduce_motion(app: AppHandle) -> bool {
+    reduced(&app)
+}
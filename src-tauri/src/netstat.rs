@@ -0,0 +1,125 @@
+use std::{
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use sysinfo::{NetworkData, Networks};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{settings::SettingsState, sim::SimParams};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Weight of the newest sample in the rolling rate.
+const SMOOTHING: f64 = 0.3;
+/// Throughput that maps to one spawned vehicle per second.
+const BYTES_PER_VEHICLE: f64 = 100_000.0;
+/// Spawn rates the network can drive, in vehicles per second.
+const MIN_SPAWN_RATE: f64 = 0.1;
+const MAX_SPAWN_RATE: f64 = 5.0;
+
+/// Rolling throughput of the busiest interface, in bytes per second.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct NetworkStats {
+    pub up: f64,
+    pub down: f64,
+}
+
+impl NetworkStats {
+    fn spawn_rate(&self) -> f64 {
+        ((self.up + self.down) / BYTES_PER_VEHICLE).clamp(MIN_SPAWN_RATE, MAX_SPAWN_RATE)
+    }
+}
+
+/// The spawn rate derived from network traffic, when network-driven traffic
+/// is on and an interface could be read.
+#[derive(Default)]
+pub struct NetworkRate(pub Mutex<Option<f64>>);
+
+fn is_loopback(name: &str, data: &NetworkData) -> bool {
+    name == "lo"
+        || name.starts_with("lo0")
+        || (!data.ip_networks().is_empty()
+            && data.ip_networks().iter().all(|n| n.addr.is_loopback()))
+}
+
+/// Picks the interface that has moved the most data as the default one.
+fn busiest(networks: &Networks) -> Option<&NetworkData> {
+    networks
+        .list()
+        .iter()
+        .filter(|(name, data)| !is_loopback(name, data))
+        .max_by_key(|(_, data)| data.total_received() + data.total_transmitted())
+        .map(|(_, data)| data)
+}
+
+/// Spawns the thread that samples network throughput once a second while
+/// `network_driven` is on, feeding it into the spawn rate.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let mut networks = Networks::new_with_refreshed_list();
+        let mut last_sample = Instant::now();
+        let mut stats: Option<NetworkStats> = None;
+        let mut warned = false;
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            networks.refresh(true);
+            let elapsed = last_sample.elapsed().as_secs_f64();
+            last_sample = Instant::now();
+
+            let enabled = app
+                .state::<SettingsState>()
+                .0
+                .lock()
+                .unwrap()
+                .network_driven;
+            let rate = app.state::<NetworkRate>();
+            if !enabled {
+                stats = None;
+                *rate.0.lock().unwrap() = None;
+                continue;
+            }
+
+            let Some(data) = busiest(&networks) else {
+                if !warned {
+                    log::warn!("no network interface found, falling back to synthetic traffic");
+                    warned = true;
+                }
+                *rate.0.lock().unwrap() = None;
+                continue;
+            };
+            warned = false;
+
+            let sample = NetworkStats {
+                up: data.transmitted() as f64 / elapsed,
+                down: data.received() as f64 / elapsed,
+            };
+            let smoothed = match stats {
+                Some(prev) => NetworkStats {
+                    up: prev.up + SMOOTHING * (sample.up - prev.up),
+                    down: prev.down + SMOOTHING * (sample.down - prev.down),
+                },
+                None => sample,
+            };
+            stats = Some(smoothed);
+            *rate.0.lock().unwrap() = Some(smoothed.spawn_rate());
+
+            if let Err(e) = app.emit("network-stats", smoothed) {
+                log::error!("failed to emit network-stats: {e}");
+            }
+        }
+    });
+}
+
+/// Swaps in the network-driven spawn rate when one is available.
+pub fn adjust(app: &AppHandle, params: SimParams) -> SimParams {
+    match *app.state::<NetworkRate>().0.lock().unwrap() {
+        Some(spawn_rate) => SimParams {
+            spawn_rate,
+            ..params
+        },
+        None => params,
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{error::AppError, settings::SettingsState};
+
+/// Minimum time between two notifications of the same kind.
+const RATE_LIMIT: Duration = Duration::from_secs(30);
+
+/// When each kind of notification was last shown.
+#[derive(Default)]
+pub struct NotifyState(Mutex<HashMap<String, Instant>>);
+
+/// Shows a native notification unless notifications are disabled or one of
+/// the same `kind` was shown within the last 30 seconds.
+pub fn send(app: &AppHandle, kind: &str, title: &str, body: &str) -> Result<(), String> {
+    show(app, kind, title, body, None)
+}
+
+/// Like [`send`], playing the named system sound with the notification.
+pub fn send_with_sound(
+    app: &AppHandle,
+    kind: &str,
+    title: &str,
+    body: &str,
+    sound: &str,
+) -> Result<(), String> {
+    show(app, kind, title, body, Some(sound))
+}
+
+fn show(
+    app: &AppHandle,
+    kind: &str,
+    title: &str,
+    body: &str,
+    sound: Option<&str>,
+) -> Result<(), String> {
+    if !app
+        .state::<SettingsState>()
+        .0
+        .lock()
+        .unwrap()
+        .notifications_enabled
+    {
+        return Ok(());
+    }
+
+    let state = app.state::<NotifyState>();
+    let mut last_shown = state.0.lock().unwrap();
+    let now = Instant::now();
+    if let Some(last) = last_shown.get(kind) {
+        if now.duration_since(*last) < RATE_LIMIT {
+            return Ok(());
+        }
+    }
+    last_shown.insert(kind.to_owned(), now);
+    drop(last_shown);
+
+    let mut builder = app.notification().builder().title(title).body(body);
+    if let Some(sound) = sound {
+        builder = builder.sound(sound);
+    }
+    builder.show().map_err(|e| e.to_string())
+}
+
+/// Notifications sent from the frontend are rate-limited by title.
+#[tauri::command]
+pub fn notify(app: AppHandle, title: String, body: String) -> Result<(), AppError> {
+    send(&app, &title, &title, &body).map_err(AppError::Platform)
+}
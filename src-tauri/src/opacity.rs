@@ -0,0 +1,88 @@
+use tauri::{Manager, WebviewWindow};
+
+use crate::{error::AppError, settings, AppState};
+
+pub const MIN_OPACITY: f64 = 0.1;
+pub const MAX_OPACITY: f64 = 1.0;
+
+pub fn validate(opacity: f64) -> Result<(), String> {
+    if !(MIN_OPACITY..=MAX_OPACITY).contains(&opacity) {
+        return Err(format!(
+            "opacity must be between {MIN_OPACITY} and {MAX_OPACITY}, got {opacity}"
+        ));
+    }
+    Ok(())
+}
+
+/// Clamps and applies whole-window opacity, remembering it so it can be
+/// re-applied when the window is shown again.
+pub fn set(window: &WebviewWindow, opacity: f64) -> Result<(), AppError> {
+    let opacity = opacity.clamp(MIN_OPACITY, MAX_OPACITY);
+    apply_platform(window, opacity).map_err(AppError::Platform)?;
+    *window.state::<AppState>().opacity.lock().unwrap() = opacity;
+    settings::update(window.app_handle(), |s| s.opacity = opacity).map_err(AppError::Io)
+}
+
+/// Applies `opacity` without remembering it.
+pub fn apply(window: &WebviewWindow, opacity: f64) -> Result<(), String> {
+    apply_platform(window, opacity)
+}
+
+#[tauri::command]
+pub fn set_window_opacity(window: WebviewWindow, opacity: f64) -> Result<(), AppError> {
+    set(&window, opacity)
+}
+
+#[cfg(target_os = "windows")]
+fn apply_platform(window: &WebviewWindow, opacity: f64) -> Result<(), String> {
+    use windows::Win32::{
+        Foundation::COLORREF,
+        UI::WindowsAndMessaging::{
+            GetWindowLongPtrW, SetLayeredWindowAttributes, SetWindowLongPtrW, GWL_EXSTYLE,
+            LWA_ALPHA, WS_EX_LAYERED,
+        },
+    };
+
+    let hwnd = window.hwnd().map_err(|e| e.to_string())?;
+    let alpha = (opacity * 255.0).round() as u8;
+    unsafe {
+        let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style | WS_EX_LAYERED.0 as isize);
+        SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn apply_platform(window: &WebviewWindow, opacity: f64) -> Result<(), String> {
+    use objc2::{msg_send, runtime::AnyObject};
+
+    let ns_window = window.ns_window().map_err(|e| e.to_string())? as usize;
+    window
+        .run_on_main_thread(move || {
+            // SAFETY: the pointer comes from the live NSWindow backing this window and
+            // AppKit is only touched on the main thread.
+            let ns_window = unsafe { &*(ns_window as *const AnyObject) };
+            let _: () = unsafe { msg_send![ns_window, setAlphaValue: opacity] };
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn apply_platform(window: &WebviewWindow, opacity: f64) -> Result<(), String> {
+    use gtk::prelude::WidgetExt;
+
+    let handle = window.clone();
+    window
+        .run_on_main_thread(move || {
+            // GTK sets `_NET_WM_WINDOW_OPACITY` on the toplevel for us.
+            if let Ok(gtk_window) = handle.gtk_window() {
+                gtk_window.set_opacity(opacity);
+            }
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn apply_platform(_window: &WebviewWindow, _opacity: f64) -> Result<(), String> {
+    Err("window opacity is not supported on this platform".into())
+}
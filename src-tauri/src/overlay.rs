@@ -0,0 +1,609 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    Mutex,
+};
+
+use tauri::{AppHandle, EventTarget, Manager, State, WebviewWindow, WebviewWindowBuilder};
+#[cfg(desktop)]
+use tauri::{Monitor, WebviewUrl};
+
+#[cfg(desktop)]
+use crate::foreground;
+use crate::{
+    error::AppError,
+    opacity, platform, position,
+    region::{self, Rect},
+    settings::{self, SavedOverlay, Settings, SettingsState, WindowPosition},
+    sim::SimState,
+};
+
+pub struct AppState {
+    pub click_through: AtomicBool,
+    pub always_on_top: AtomicBool,
+    pub opacity: Mutex<f64>,
+    pub show_in_taskbar: AtomicBool,
+    pub over_fullscreen: AtomicBool,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            click_through: AtomicBool::new(false),
+            always_on_top: AtomicBool::new(true),
+            opacity: Mutex::new(opacity::MAX_OPACITY),
+            show_in_taskbar: AtomicBool::new(false),
+            over_fullscreen: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Window label prefix for overlays spawned with `spawn_overlay`.
+const OVERLAY_LABEL_PREFIX: &str = "overlay-";
+
+/// Labels of the extra overlay windows spawned on other monitors.
+#[derive(Default)]
+pub struct Overlays {
+    labels: Mutex<Vec<String>>,
+    next_id: AtomicU32,
+    /// Saved overlays not reopened because their monitor is gone, kept so
+    /// they come back once it's plugged in again.
+    orphans: Mutex<Vec<SavedOverlay>>,
+}
+
+/// Labels of every window showing the simulation, starting with "main".
+pub fn labels(app: &AppHandle) -> Vec<String> {
+    let mut labels = vec!["main".to_string()];
+    labels.extend(
+        app.state::<Overlays>()
+            .labels
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned(),
+    );
+    labels
+}
+
+/// Matches events aimed at any of the given window labels.
+pub fn targets_any(labels: &[String], target: &EventTarget) -> bool {
+    match target {
+        EventTarget::AnyLabel { label }
+        | EventTarget::Window { label }
+        | EventTarget::Webview { label }
+        | EventTarget::WebviewWindow { label } => labels.contains(label),
+        _ => false,
+    }
+}
+
+/// Drops a destroyed window from the spawned overlays.
+pub fn forget(app: &AppHandle, label: &str) {
+    if let Some(overlays) = app.try_state::<Overlays>() {
+        overlays.labels.lock().unwrap().retain(|l| l != label);
+    }
+}
+
+/// Destroys every spawned overlay window.
+pub fn close_all(app: &AppHandle) {
+    let labels = std::mem::take(&mut *app.state::<Overlays>().labels.lock().unwrap());
+    for label in labels {
+        if let Some(window) = app.get_webview_window(&label) {
+            if let Err(e) = window.destroy() {
+                log::error!("failed to close overlay {label}: {e}");
+            }
+        }
+    }
+}
+
+/// Copies the window-related settings into the live state.
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    let state = app.state::<AppState>();
+    state
+        .click_through
+        .store(settings.click_through, Ordering::Relaxed);
+    state
+        .always_on_top
+        .store(settings.always_on_top, Ordering::Relaxed);
+    state
+        .show_in_taskbar
+        .store(settings.show_in_taskbar, Ordering::Relaxed);
+    state
+        .over_fullscreen
+        .store(settings.over_fullscreen, Ordering::Relaxed);
+    *state.opacity.lock().unwrap() = settings
+        .opacity
+        .clamp(opacity::MIN_OPACITY, opacity::MAX_OPACITY);
+}
+
+/// Everything [`apply_window_state`] puts back on a window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowState {
+    pub click_through: bool,
+    /// Stays interactive while click-through is on.
+    pub region: Option<Rect>,
+    pub always_on_top: bool,
+    /// `None` leaves it alone.
+    pub over_fullscreen: Option<bool>,
+    pub show_in_taskbar: bool,
+    pub opacity: f64,
+}
+
+/// What [`AppState`] says `window` should look like. Spawned overlays are
+/// always click-through and on top; only "main" follows the settings.
+pub fn window_state(window: &WebviewWindow) -> WindowState {
+    let state = window.state::<AppState>();
+    let main = window.label() == "main";
+    let opacity = *state.opacity.lock().unwrap();
+    WindowState {
+        click_through: click_through_of(window),
+        region: region::get(window),
+        always_on_top: !main || state.always_on_top.load(Ordering::Relaxed),
+        over_fullscreen: main.then(|| state.over_fullscreen.load(Ordering::Relaxed)),
+        show_in_taskbar: state.show_in_taskbar.load(Ordering::Relaxed),
+        opacity,
+    }
+}
+
+/// The calls [`apply_window_state`] makes on a window.
+pub trait OverlayWindow {
+    fn show(&self) -> tauri::Result<()>;
+    fn set_in_taskbar(&self, shown: bool) -> Result<(), String>;
+    fn set_click_through(&self, ignore: bool, interactive: Option<Rect>) -> tauri::Result<()>;
+    fn set_level(&self, always_on_top: bool, over_fullscreen: Option<bool>) -> tauri::Result<()>;
+    fn set_opacity(&self, opacity: f64) -> Result<(), String>;
+}
+
+impl OverlayWindow for WebviewWindow {
+    fn show(&self) -> tauri::Result<()> {
+        WebviewWindow::show(self)
+    }
+
+    fn set_in_taskbar(&self, shown: bool) -> Result<(), String> {
+        platform::set_in_taskbar(self, shown)
+    }
+
+    fn set_click_through(&self, ignore: bool, interactive: Option<Rect>) -> tauri::Result<()> {
+        #[cfg(target_os = "linux")]
+        return crate::linux_clickthrough::set_ignore_cursor_events(self, ignore, interactive);
+        // The cursor poller looks the region up itself.
+        #[cfg(all(desktop, not(target_os = "linux")))]
+        {
+            let _ = interactive;
+            region::invalidate(self);
+            self.set_ignore_cursor_events(ignore)
+        }
+        // Mobile windows fill the screen and always take touches.
+        #[cfg(mobile)]
+        {
+            let _ = (ignore, interactive);
+            Ok(())
+        }
+    }
+
+    fn set_level(&self, always_on_top: bool, over_fullscreen: Option<bool>) -> tauri::Result<()> {
+        #[cfg(desktop)]
+        {
+            self.set_always_on_top(always_on_top)?;
+            if let Some(over) = over_fullscreen {
+                if let Err(e) = platform::set_over_fullscreen(self, over) {
+                    log::error!("failed to set over-fullscreen: {e}");
+                }
+            }
+        }
+        #[cfg(mobile)]
+        let _ = (always_on_top, over_fullscreen);
+        Ok(())
+    }
+
+    fn set_opacity(&self, opacity: f64) -> Result<(), String> {
+        opacity::apply(self, opacity)
+    }
+}
+
+/// Re-applies click-through and the interactive region, always-on-top,
+/// taskbar visibility and opacity together, since hiding a window can reset
+/// any of them.
+pub fn apply_window_state(window: &impl OverlayWindow, state: &WindowState) -> tauri::Result<()> {
+    // Before click-through, so a style change can't undo it.
+    if let Err(e) = window.set_in_taskbar(state.show_in_taskbar) {
+        log::error!("failed to set taskbar visibility: {e}");
+    }
+    window.set_click_through(state.click_through, state.region)?;
+    window.set_level(state.always_on_top, state.over_fullscreen)?;
+    if let Err(e) = window.set_opacity(state.opacity) {
+        log::error!("failed to re-apply opacity: {e}");
+    }
+    Ok(())
+}
+
+/// Shows the window with its stored state re-applied.
+pub fn show(window: &WebviewWindow) -> tauri::Result<()> {
+    show_with(window, &window_state(window))
+}
+
+fn show_with(window: &impl OverlayWindow, state: &WindowState) -> tauri::Result<()> {
+    window.show()?;
+    apply_window_state(window, state)
+}
+
+/// Returns the main window, rebuilding it if the user closed it.
+pub fn main_window_or_recreate(app: &AppHandle) -> Option<WebviewWindow> {
+    get_or_create(app.get_webview_window("main"), || recreate_main(app))
+}
+
+fn get_or_create<W>(existing: Option<W>, create: impl FnOnce() -> Result<W, String>) -> Option<W> {
+    existing.or_else(|| {
+        log::warn!("main window not found, recreating it");
+        create()
+            .map_err(|e| log::error!("failed to recreate main window: {e}"))
+            .ok()
+    })
+}
+
+/// Builds "main" again from the app config, at its saved size and position.
+fn recreate_main(app: &AppHandle) -> Result<WebviewWindow, String> {
+    let config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|w| w.label == "main")
+        .ok_or("no main window in the app config")?;
+    let window = WebviewWindowBuilder::from_config(app, config)
+        .and_then(|builder| builder.build())
+        .map_err(|e| e.to_string())?;
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    position::restore_saved(&window, &settings);
+    Ok(window)
+}
+
+/// Hides the main window if it's visible and shows it otherwise.
+pub fn toggle_visibility(app: &AppHandle) {
+    let Some(window) = main_window_or_recreate(app) else {
+        return;
+    };
+    let hide = window.is_visible().unwrap_or(false);
+    #[cfg(desktop)]
+    foreground::set_manually_hidden(app, hide);
+    let result = if hide { window.hide() } else { show(&window) };
+    if let Err(e) = result {
+        log::error!("failed to toggle window visibility: {e}");
+    }
+}
+
+/// Lets clicks pass through the window to whatever is behind it, apart from
+/// its interactive region if it has one.
+pub fn set_ignore_cursor_events(window: &WebviewWindow, ignore: bool) -> tauri::Result<()> {
+    window.set_click_through(ignore, region::get(window))
+}
+
+/// Puts the window back at the level its settings call for, after something
+/// raised it. Spawned overlays are always on top; only "main" follows the
+/// settings.
+#[cfg(desktop)]
+pub fn restore_level(window: &WebviewWindow) -> tauri::Result<()> {
+    let state = window_state(window);
+    window.set_level(state.always_on_top, state.over_fullscreen)
+}
+
+/// Whether the window should currently let clicks through. Spawned overlays
+/// always do; only "main" follows the setting.
+pub fn click_through_of(window: &WebviewWindow) -> bool {
+    window.label() != "main"
+        || window
+            .state::<AppState>()
+            .click_through
+            .load(Ordering::Relaxed)
+}
+
+/// Flips click-through and returns the new value.
+pub fn toggle_click_through(window: &WebviewWindow) -> tauri::Result<bool> {
+    let state = window.state::<AppState>();
+    let enabled = !state.click_through.load(Ordering::Relaxed);
+    set_ignore_cursor_events(window, enabled)?;
+    state.click_through.store(enabled, Ordering::Relaxed);
+    persist(window.app_handle(), |s| s.click_through = enabled);
+    Ok(enabled)
+}
+
+#[cfg(desktop)]
+pub fn set_always_on_top(window: &WebviewWindow, enabled: bool) -> tauri::Result<()> {
+    window
+        .state::<AppState>()
+        .always_on_top
+        .store(enabled, Ordering::Relaxed);
+    restore_level(window)?;
+    persist(window.app_handle(), |s| s.always_on_top = enabled);
+    // Re-entering the topmost layer doesn't always raise the window, so order it front.
+    if enabled && window.is_visible()? {
+        show(window)?;
+    }
+    Ok(())
+}
+
+fn persist(app: &AppHandle, f: impl FnOnce(&mut Settings)) {
+    if let Err(e) = settings::update(app, f) {
+        log::error!("failed to save settings: {e}");
+    }
+}
+
+/// Keeps the window visible over fullscreen apps. Only has an effect on macOS.
+#[tauri::command]
+pub fn set_over_fullscreen(window: WebviewWindow, enabled: bool) -> Result<(), AppError> {
+    window
+        .state::<AppState>()
+        .over_fullscreen
+        .store(enabled, Ordering::Relaxed);
+    platform::set_over_fullscreen(&window, enabled).map_err(AppError::Platform)?;
+    settings::update(window.app_handle(), |s| s.over_fullscreen = enabled).map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub fn is_click_through(state: State<AppState>) -> bool {
+    state.click_through.load(Ordering::Relaxed)
+}
+
+/// Records the open overlays in the settings, so they're reopened next launch.
+pub fn save_open(app: &AppHandle) -> Result<(), AppError> {
+    let overlays = app.state::<Overlays>();
+    let sims = app.state::<SimState>();
+    let mut open: Vec<SavedOverlay> = overlays
+        .labels
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|label| {
+            let window = app.get_webview_window(label)?;
+            let monitor = window.current_monitor().ok()??;
+            let origin = monitor.position();
+            Some(SavedOverlay {
+                monitor: monitor.name().cloned(),
+                origin: WindowPosition {
+                    x: origin.x,
+                    y: origin.y,
+                },
+                params: sims.overlay_params(label),
+            })
+        })
+        .collect();
+    open.extend(overlays.orphans.lock().unwrap().iter().cloned());
+    settings::update(app, |s| s.open_overlays = open).map_err(AppError::Io)
+}
+
+/// Finds the monitor a saved overlay was on: by name, or by position for
+/// monitors the OS doesn't name.
+#[cfg(desktop)]
+fn saved_monitor(monitors: &[Monitor], saved: &SavedOverlay) -> Option<Monitor> {
+    monitors
+        .iter()
+        .find(|m| match &saved.monitor {
+            Some(name) => m.name() == Some(name),
+            None => {
+                let origin = m.position();
+                m.name().is_none() && (origin.x, origin.y) == (saved.origin.x, saved.origin.y)
+            }
+        })
+        .cloned()
+}
+
+/// Reopens the overlays that were open when the app last quit. Those whose
+/// monitor is gone are skipped, or with `relocate_orphaned_overlays` moved
+/// to the primary monitor.
+#[cfg(desktop)]
+pub fn restore_open(app: &AppHandle, settings: &Settings) {
+    if settings.open_overlays.is_empty() {
+        return;
+    }
+    let monitors = match app.available_monitors() {
+        Ok(monitors) => monitors,
+        Err(e) => {
+            log::error!("failed to list monitors, not reopening overlays: {e}");
+            return;
+        }
+    };
+    let sims = app.state::<SimState>();
+    for saved in &settings.open_overlays {
+        let name = saved.monitor.as_deref().unwrap_or("unnamed monitor");
+        let monitor = match saved_monitor(&monitors, saved) {
+            Some(monitor) => monitor,
+            None if settings.relocate_orphaned_overlays => {
+                let primary = app.primary_monitor().ok().flatten();
+                let Some(monitor) = primary.or_else(|| monitors.first().cloned()) else {
+                    continue;
+                };
+                log::info!("{name} is gone, reopening its overlay on the primary monitor");
+                monitor
+            }
+            None => {
+                log::info!("{name} is gone, not reopening its overlay");
+                app.state::<Overlays>()
+                    .orphans
+                    .lock()
+                    .unwrap()
+                    .push(saved.clone());
+                continue;
+            }
+        };
+        match create(app, &monitor) {
+            Ok(label) => {
+                if let Some(params) = saved.params {
+                    sims.add_overlay(&label, params);
+                }
+            }
+            Err(e) => log::error!("failed to reopen the overlay on {name}: {e}"),
+        }
+    }
+}
+
+/// Opens a click-through overlay covering `monitor` and returns its label.
+/// The tick loop starts sending it frames right away.
+#[cfg(desktop)]
+fn create(app: &AppHandle, monitor: &Monitor) -> Result<String, AppError> {
+    let overlays = app.state::<Overlays>();
+    let id = overlays.next_id.fetch_add(1, Ordering::Relaxed);
+    let label = format!("{OVERLAY_LABEL_PREFIX}{id}");
+
+    let window = WebviewWindowBuilder::new(app, &label, WebviewUrl::default())
+        .title("Shibuya Traffic")
+        .transparent(true)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .shadow(false)
+        .resizable(false)
+        .visible(false)
+        .build()?;
+    overlays.labels.lock().unwrap().push(label.clone());
+
+    let setup = || -> tauri::Result<()> {
+        window.set_position(*monitor.position())?;
+        window.set_size(*monitor.size())?;
+        set_ignore_cursor_events(&window, true)?;
+        window.show()
+    };
+    if let Err(e) = setup() {
+        if let Err(e) = window.destroy() {
+            log::error!("failed to close overlay {label}: {e}");
+        }
+        return Err(e.into());
+    }
+    Ok(label)
+}
+
+// Async so the window is built off the main thread, which would otherwise
+// deadlock on Windows.
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn spawn_overlay(app: AppHandle, monitor_index: usize) -> Result<String, AppError> {
+    let monitor = app
+        .available_monitors()?
+        .into_iter()
+        .nth(monitor_index)
+        .ok_or_else(|| AppError::InvalidParam(format!("no monitor at index {monitor_index}")))?;
+    let label = create(&app, &monitor)?;
+    save_open(&app)?;
+    Ok(label)
+}
+
+#[tauri::command]
+pub fn close_overlay(app: AppHandle, label: String) -> Result<(), AppError> {
+    let known = app
+        .state::<Overlays>()
+        .labels
+        .lock()
+        .unwrap()
+        .contains(&label);
+    if !known {
+        return Err(AppError::InvalidParam(format!(
+            "no overlay named {label:?}"
+        )));
+    }
+    forget(&app, &label);
+    if let Some(window) = app.get_webview_window(&label) {
+        window.destroy()?;
+    }
+    save_open(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// Loses everything when hidden, like a window on Windows can.
+    struct FakeWindow(RefCell<WindowState>);
+
+    const RESET: WindowState = WindowState {
+        click_through: false,
+        region: None,
+        always_on_top: false,
+        over_fullscreen: None,
+        show_in_taskbar: true,
+        opacity: 1.0,
+    };
+
+    impl FakeWindow {
+        fn hide(&self) {
+            *self.0.borrow_mut() = RESET;
+        }
+    }
+
+    impl OverlayWindow for FakeWindow {
+        fn show(&self) -> tauri::Result<()> {
+            Ok(())
+        }
+
+        fn set_in_taskbar(&self, shown: bool) -> Result<(), String> {
+            self.0.borrow_mut().show_in_taskbar = shown;
+            Ok(())
+        }
+
+        fn set_click_through(&self, ignore: bool, interactive: Option<Rect>) -> tauri::Result<()> {
+            let mut state = self.0.borrow_mut();
+            state.click_through = ignore;
+            state.region = interactive;
+            Ok(())
+        }
+
+        fn set_level(
+            &self,
+            always_on_top: bool,
+            over_fullscreen: Option<bool>,
+        ) -> tauri::Result<()> {
+            let mut state = self.0.borrow_mut();
+            state.always_on_top = always_on_top;
+            state.over_fullscreen = over_fullscreen;
+            Ok(())
+        }
+
+        fn set_opacity(&self, opacity: f64) -> Result<(), String> {
+            self.0.borrow_mut().opacity = opacity;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn showing_again_restores_the_stored_state() {
+        let stored = WindowState {
+            click_through: true,
+            region: Some(Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 200.0,
+                height: 40.0,
+            }),
+            always_on_top: true,
+            over_fullscreen: Some(false),
+            show_in_taskbar: false,
+            opacity: 0.6,
+        };
+        let window = FakeWindow(RefCell::new(RESET));
+        show_with(&window, &stored).unwrap();
+        window.hide();
+        show_with(&window, &stored).unwrap();
+        assert_eq!(*window.0.borrow(), stored);
+    }
+
+    #[test]
+    fn keeps_an_existing_window() {
+        let window = get_or_create(Some("main"), || panic!("recreated an existing window"));
+        assert_eq!(window, Some("main"));
+    }
+
+    #[test]
+    fn recreates_a_missing_window() {
+        let mut recreated = false;
+        let window = get_or_create(None, || {
+            recreated = true;
+            Ok("main")
+        });
+        assert!(recreated);
+        assert_eq!(window, Some("main"));
+    }
+
+    #[test]
+    fn gives_up_when_recreating_fails() {
+        let window: Option<&str> = get_or_create(None, || Err("no config".into()));
+        assert_eq!(window, None);
+    }
+}
@@ -0,0 +1,249 @@
+//! OS-specific integration that doesn't belong to a single feature.
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, WebviewWindow, Wry};
+
+use crate::netstat::NetworkRate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub enum DisplayServer {
+    X11,
+    Wayland,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlatformInfo {
+    pub os: &'static str,
+    pub os_version: Option<String>,
+    pub arch: &'static str,
+    pub app_name: String,
+    pub app_version: &'static str,
+    pub tauri_version: &'static str,
+    pub debug: bool,
+    /// Which display server the webview runs on; only set on Linux, where
+    /// click-through doesn't work under Wayland.
+    pub display_server: Option<DisplayServer>,
+}
+
+/// Which optional subsystems this build has, so the UI can hide the rest.
+/// Fields are only ever added, never renamed or removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    pub auto_update: bool,
+    pub global_shortcuts: bool,
+    pub notifications: bool,
+    pub clipboard: bool,
+    pub deep_link: bool,
+    pub audio_reactive: bool,
+    pub network_driven: bool,
+    pub screen_capture: bool,
+}
+
+/// Follows GTK's own backend choice: `GDK_BACKEND` wins, then Wayland if a
+/// compositor is reachable.
+#[cfg(target_os = "linux")]
+pub fn display_server() -> Option<DisplayServer> {
+    let var = |name| std::env::var(name).ok().filter(|v| !v.is_empty());
+    if let Some(backend) = var("GDK_BACKEND") {
+        match backend.split(',').next().map(str::trim) {
+            Some("x11") => return Some(DisplayServer::X11),
+            Some("wayland") => return Some(DisplayServer::Wayland),
+            _ => {}
+        }
+    }
+    let session = var("XDG_SESSION_TYPE");
+    if var("WAYLAND_DISPLAY").is_some() || session.as_deref() == Some("wayland") {
+        Some(DisplayServer::Wayland)
+    } else if var("DISPLAY").is_some() || session.as_deref() == Some("x11") {
+        Some(DisplayServer::X11)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn display_server() -> Option<DisplayServer> {
+    None
+}
+
+/// Whether the window can actually be drawn see-through. X11 needs a
+/// compositing manager and an RGBA visual; Wayland, Windows and macOS always
+/// composite.
+#[cfg(target_os = "linux")]
+pub fn transparency_available(window: &WebviewWindow) -> bool {
+    use gtk::prelude::*;
+
+    let Ok(gtk_window) = window.gtk_window() else {
+        return true;
+    };
+    let Some(screen) = WidgetExt::screen(&gtk_window) else {
+        return true;
+    };
+    screen.is_composited() && screen.rgba_visual().is_some()
+}
+
+#[cfg(all(desktop, not(target_os = "linux")))]
+pub fn transparency_available(_window: &WebviewWindow) -> bool {
+    true
+}
+
+/// Hides the app from the Dock and app switcher, leaving only the menu bar
+/// icon. Windows can still be shown and focused from the tray.
+#[cfg(target_os = "macos")]
+pub fn set_dock_hidden(app: &AppHandle, hidden: bool) {
+    use tauri::ActivationPolicy;
+
+    let policy = if hidden {
+        ActivationPolicy::Accessory
+    } else {
+        ActivationPolicy::Regular
+    };
+    if let Err(e) = app.set_activation_policy(policy) {
+        log::error!("failed to set activation policy: {e}");
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_dock_hidden(_app: &AppHandle, _hidden: bool) {}
+
+/// Shows the window on every Space, fullscreen ones included, at a level above
+/// fullscreen apps. Must run after always-on-top is set, which resets the
+/// level. Neither change orders the window front or touches whether it
+/// ignores the mouse, so focus and click-through are left alone.
+#[cfg(target_os = "macos")]
+pub fn set_over_fullscreen(window: &WebviewWindow, enabled: bool) -> Result<(), String> {
+    use objc2::{msg_send, runtime::AnyObject};
+
+    const CAN_JOIN_ALL_SPACES: usize = 1 << 0;
+    const STATIONARY: usize = 1 << 4;
+    const FULL_SCREEN_AUXILIARY: usize = 1 << 8;
+    const BEHAVIOR: usize = CAN_JOIN_ALL_SPACES | STATIONARY | FULL_SCREEN_AUXILIARY;
+    const STATUS_WINDOW_LEVEL: isize = 25;
+
+    let ns_window = window.ns_window().map_err(|e| e.to_string())? as usize;
+    window
+        .run_on_main_thread(move || {
+            // SAFETY: the pointer comes from the live NSWindow backing this window and
+            // AppKit is only touched on the main thread.
+            let ns_window = unsafe { &*(ns_window as *const AnyObject) };
+            let behavior: usize = unsafe { msg_send![ns_window, collectionBehavior] };
+            // Only these bits change, leaving whatever Tauri set alone.
+            let behavior = if enabled {
+                behavior | BEHAVIOR
+            } else {
+                behavior & !BEHAVIOR
+            };
+            let _: () = unsafe { msg_send![ns_window, setCollectionBehavior: behavior] };
+            if enabled {
+                let _: () = unsafe { msg_send![ns_window, setLevel: STATUS_WINDOW_LEVEL] };
+            }
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_over_fullscreen(_window: &WebviewWindow, _enabled: bool) -> Result<(), String> {
+    Ok(())
+}
+
+/// Keeps the window out of the taskbar and, on Windows, the alt-tab list.
+#[cfg(desktop)]
+pub fn set_in_taskbar(window: &WebviewWindow, shown: bool) -> Result<(), String> {
+    window.set_skip_taskbar(!shown).map_err(|e| e.to_string())?;
+    set_tool_window(window, !shown)
+}
+
+/// A tool window is left out of both the taskbar and the task switcher.
+#[cfg(target_os = "windows")]
+fn set_tool_window(window: &WebviewWindow, tool: bool) -> Result<(), String> {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetWindowLongPtrW, SetWindowLongPtrW, SetWindowPos, GWL_EXSTYLE, SWP_FRAMECHANGED,
+        SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SWP_NOZORDER, WS_EX_APPWINDOW, WS_EX_TOOLWINDOW,
+    };
+
+    let hwnd = window.hwnd().map_err(|e| e.to_string())?;
+    unsafe {
+        let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        // Only these two bits change, so the layered/transparent bits behind
+        // click-through and opacity are left alone.
+        let new_style = if tool {
+            (style | WS_EX_TOOLWINDOW.0 as isize) & !(WS_EX_APPWINDOW.0 as isize)
+        } else {
+            (style & !(WS_EX_TOOLWINDOW.0 as isize)) | WS_EX_APPWINDOW.0 as isize
+        };
+        if new_style == style {
+            return Ok(());
+        }
+        SetWindowLongPtrW(hwnd, GWL_EXSTYLE, new_style);
+        // The shell only notices the change once the frame is refreshed.
+        SetWindowPos(
+            hwnd,
+            None,
+            0,
+            0,
+            0,
+            0,
+            SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE | SWP_FRAMECHANGED,
+        )
+        .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(all(desktop, not(target_os = "windows")))]
+fn set_tool_window(_window: &WebviewWindow, _tool: bool) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(mobile)]
+pub fn set_in_taskbar(_window: &WebviewWindow, _shown: bool) -> Result<(), String> {
+    Ok(())
+}
+
+#[tauri::command]
+pub fn platform_info(app: AppHandle) -> PlatformInfo {
+    PlatformInfo {
+        os: std::env::consts::OS,
+        os_version: sysinfo::System::os_version(),
+        arch: std::env::consts::ARCH,
+        app_name: app.package_info().name.clone(),
+        app_version: env!("CARGO_PKG_VERSION"),
+        tauri_version: tauri::VERSION,
+        debug: cfg!(debug_assertions),
+        display_server: display_server(),
+    }
+}
+
+/// What [`Capabilities`] this build has: compiled in, and for plugins also
+/// registered.
+#[tauri::command]
+pub fn capabilities(app: AppHandle) -> Capabilities {
+    #[cfg(desktop)]
+    let (auto_update, global_shortcuts, audio_reactive) = {
+        (
+            crate::updater::available(&app),
+            app.try_state::<tauri_plugin_global_shortcut::GlobalShortcut<Wry>>()
+                .is_some(),
+            app.try_state::<crate::audio::AudioState>().is_some(),
+        )
+    };
+    #[cfg(mobile)]
+    let (auto_update, global_shortcuts, audio_reactive) = (false, false, false);
+    Capabilities {
+        auto_update,
+        global_shortcuts,
+        notifications: app
+            .try_state::<tauri_plugin_notification::Notification<Wry>>()
+            .is_some(),
+        clipboard: app
+            .try_state::<tauri_plugin_clipboard_manager::Clipboard<Wry>>()
+            .is_some(),
+        deep_link: app
+            .try_state::<tauri_plugin_deep_link::DeepLink<Wry>>()
+            .is_some(),
+        audio_reactive,
+        network_driven: app.try_state::<NetworkRate>().is_some(),
+        screen_capture: cfg!(desktop),
+    }
+}
@@ -0,0 +1,730 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+use tauri::{
+    AppHandle, Monitor, PhysicalPosition, PhysicalRect, PhysicalSize, State, WebviewWindow,
+};
+
+use crate::{
+    error::AppError,
+    settings::{self, MonitorPlacement, Settings, SettingsState, WindowPosition, WindowSize},
+};
+
+pub const DEFAULT_SNAP_THRESHOLD: u32 = 20;
+pub const DEFAULT_ANCHOR_MARGIN: u32 = 0;
+/// Smallest width and height the window can be resized to.
+const MIN_WINDOW_SIZE: u32 = 100;
+
+/// A named spot in the work area to move the window to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+#[derive(Clone, Copy)]
+enum Align {
+    Start,
+    Middle,
+    End,
+}
+
+impl Anchor {
+    pub const ALL: [Anchor; 9] = [
+        Anchor::TopLeft,
+        Anchor::Top,
+        Anchor::TopRight,
+        Anchor::Left,
+        Anchor::Center,
+        Anchor::Right,
+        Anchor::BottomLeft,
+        Anchor::Bottom,
+        Anchor::BottomRight,
+    ];
+
+    /// The i18n key of the anchor's tray label.
+    pub fn key(self) -> &'static str {
+        match self {
+            Anchor::TopLeft => "top_left",
+            Anchor::Top => "top",
+            Anchor::TopRight => "top_right",
+            Anchor::Left => "left",
+            Anchor::Center => "center",
+            Anchor::Right => "right",
+            Anchor::BottomLeft => "bottom_left",
+            Anchor::Bottom => "bottom",
+            Anchor::BottomRight => "bottom_right",
+        }
+    }
+
+    /// Horizontal and vertical alignment.
+    fn align(self) -> (Align, Align) {
+        match self {
+            Anchor::TopLeft => (Align::Start, Align::Start),
+            Anchor::Top => (Align::Middle, Align::Start),
+            Anchor::TopRight => (Align::End, Align::Start),
+            Anchor::Left => (Align::Start, Align::Middle),
+            Anchor::Center => (Align::Middle, Align::Middle),
+            Anchor::Right => (Align::End, Align::Middle),
+            Anchor::BottomLeft => (Align::Start, Align::End),
+            Anchor::Bottom => (Align::Middle, Align::End),
+            Anchor::BottomRight => (Align::End, Align::End),
+        }
+    }
+}
+
+/// Where the window sits on screen, in physical pixels.
+#[derive(Debug, Clone, Serialize)]
+pub struct WindowGeometry {
+    pub outer_position: PhysicalPosition<i32>,
+    pub inner_size: PhysicalSize<u32>,
+    pub scale_factor: f64,
+    pub monitor_name: Option<String>,
+    pub work_area: PhysicalRect<i32, u32>,
+}
+
+fn contains(monitor: &Monitor, x: i32, y: i32) -> bool {
+    let origin = monitor.position();
+    let size = monitor.size();
+    x >= origin.x
+        && x < origin.x + size.width as i32
+        && y >= origin.y
+        && y < origin.y + size.height as i32
+}
+
+/// Clamps a top-left position so a window of the given size stays inside the
+/// rectangle at `origin`.
+fn clamp_into(
+    origin: PhysicalPosition<i32>,
+    area: PhysicalSize<u32>,
+    pos: WindowPosition,
+    width: u32,
+    height: u32,
+) -> WindowPosition {
+    let max_x = origin.x + area.width.saturating_sub(width) as i32;
+    let max_y = origin.y + area.height.saturating_sub(height) as i32;
+    WindowPosition {
+        x: pos.x.clamp(origin.x, max_x),
+        y: pos.y.clamp(origin.y, max_y),
+    }
+}
+
+/// Moves the window to a saved position, pulling it back onto the primary
+/// monitor if the display it was on is no longer connected.
+pub fn restore_position(window: &WebviewWindow, saved: WindowPosition) -> tauri::Result<()> {
+    let monitors = window.available_monitors()?;
+    let target = if monitors.iter().any(|m| contains(m, saved.x, saved.y)) {
+        saved
+    } else if let Some(primary) = window.primary_monitor()? {
+        let size = window.outer_size()?;
+        clamp_into(
+            *primary.position(),
+            *primary.size(),
+            saved,
+            size.width,
+            size.height,
+        )
+    } else {
+        return Ok(());
+    };
+    window.set_position(PhysicalPosition::new(target.x, target.y))
+}
+
+/// A monitor's name and work area, apart from `Monitor` so placement can be
+/// worked out without a real display.
+#[derive(Debug, Clone)]
+struct Display {
+    name: Option<String>,
+    area: PhysicalRect<i32, u32>,
+}
+
+impl From<&Monitor> for Display {
+    fn from(monitor: &Monitor) -> Self {
+        Self {
+            name: monitor.name().cloned(),
+            area: *monitor.work_area(),
+        }
+    }
+}
+
+/// Records which monitor a window at `pos` is on and where in its work area.
+/// `None` for monitors the OS doesn't name.
+pub fn placement(window: &WebviewWindow, pos: WindowPosition) -> Option<MonitorPlacement> {
+    let monitor = window.current_monitor().ok()??;
+    let area = monitor.work_area();
+    Some(MonitorPlacement {
+        monitor: monitor.name()?.clone(),
+        offset: WindowPosition {
+            x: pos.x - area.position.x,
+            y: pos.y - area.position.y,
+        },
+    })
+}
+
+/// Where a window of `size` goes for `saved`: at the same offset on the same
+/// monitor if it's still connected, otherwise centered on the primary one (or
+/// the first, if none is primary). Either way it's kept inside the work area.
+fn placement_target(
+    displays: &[Display],
+    primary: Option<&Display>,
+    saved: &MonitorPlacement,
+    size: PhysicalSize<u32>,
+) -> Option<WindowPosition> {
+    if let Some(display) = displays
+        .iter()
+        .find(|d| d.name.as_deref() == Some(saved.monitor.as_str()))
+    {
+        let area = display.area;
+        let pos = WindowPosition {
+            x: area.position.x + saved.offset.x,
+            y: area.position.y + saved.offset.y,
+        };
+        return Some(clamp_into(
+            area.position,
+            area.size,
+            pos,
+            size.width,
+            size.height,
+        ));
+    }
+    let area = primary.or(displays.first())?.area;
+    let pos = WindowPosition {
+        x: area.position.x + (area.size.width as i32 - size.width as i32) / 2,
+        y: area.position.y + (area.size.height as i32 - size.height as i32) / 2,
+    };
+    Some(clamp_into(
+        area.position,
+        area.size,
+        pos,
+        size.width,
+        size.height,
+    ))
+}
+
+fn restore_placement(window: &WebviewWindow, saved: &MonitorPlacement) -> tauri::Result<()> {
+    let displays: Vec<Display> = window
+        .available_monitors()?
+        .iter()
+        .map(Display::from)
+        .collect();
+    let primary = window.primary_monitor()?.as_ref().map(Display::from);
+    let size = window.outer_size()?;
+    match placement_target(&displays, primary.as_ref(), saved, size) {
+        Some(target) => window.set_position(PhysicalPosition::new(target.x, target.y)),
+        None => Ok(()),
+    }
+}
+
+/// Puts the window back at its saved size and position, on the monitor it
+/// was last on if that's known.
+pub fn restore_saved(window: &WebviewWindow, settings: &Settings) {
+    if let Some(size) = settings.window_size {
+        if let Err(e) = resize(window, size.width, size.height) {
+            log::warn!("failed to restore window size: {e}");
+        }
+    }
+    let result = match (&settings.window_monitor, settings.window_position) {
+        (Some(placement), _) => restore_placement(window, placement),
+        (None, Some(saved)) => restore_position(window, saved),
+        (None, None) => Ok(()),
+    };
+    if let Err(e) = result {
+        log::warn!("failed to restore window position: {e}");
+    }
+}
+
+type Area = PhysicalRect<i32, u32>;
+
+fn area_end(area: &Area) -> (i32, i32) {
+    (
+        area.position.x + area.size.width as i32,
+        area.position.y + area.size.height as i32,
+    )
+}
+
+/// Whether a window at `pos` lies entirely within the union of `areas`. The
+/// window is cut into a grid along every area edge; it's covered if each
+/// cell is inside some area.
+fn covered(areas: &[Area], pos: WindowPosition, size: PhysicalSize<u32>) -> bool {
+    let (right, bottom) = (pos.x + size.width as i32, pos.y + size.height as i32);
+    let cuts = |start: i32, end: i32, edges: &dyn Fn(&Area) -> [i32; 2]| {
+        let mut cuts: Vec<i32> = areas
+            .iter()
+            .flat_map(edges)
+            .filter(|&e| e > start && e < end)
+            .chain([start, end])
+            .collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+        cuts
+    };
+    let xs = cuts(pos.x, right, &|a| [a.position.x, area_end(a).0]);
+    let ys = cuts(pos.y, bottom, &|a| [a.position.y, area_end(a).1]);
+    xs.windows(2).all(|x| {
+        ys.windows(2).all(|y| {
+            areas.iter().any(|a| {
+                let (end_x, end_y) = area_end(a);
+                x[0] >= a.position.x && x[1] <= end_x && y[0] >= a.position.y && y[1] <= end_y
+            })
+        })
+    })
+}
+
+/// Where a window at `pos` ends up when moved by `(dx, dy)` without leaving
+/// the work areas. Each axis moves as far as it can, so a window can slide
+/// across the shared edge of two displays but not into the gap beside a
+/// smaller one. A window that doesn't fit to begin with, such as one
+/// covering the taskbar, is kept inside the areas' bounding box instead.
+fn nudge_target(
+    areas: &[Area],
+    pos: WindowPosition,
+    size: PhysicalSize<u32>,
+    dx: i32,
+    dy: i32,
+) -> WindowPosition {
+    if !covered(areas, pos, size) {
+        let Some(left) = areas.iter().map(|a| a.position.x).min() else {
+            return pos;
+        };
+        let top = areas.iter().map(|a| a.position.y).min().unwrap_or(0);
+        let right = areas.iter().map(|a| area_end(a).0).max().unwrap_or(left);
+        let bottom = areas.iter().map(|a| area_end(a).1).max().unwrap_or(top);
+        let bounds = PhysicalSize::new((right - left) as u32, (bottom - top) as u32);
+        let target = WindowPosition {
+            x: pos.x.saturating_add(dx),
+            y: pos.y.saturating_add(dy),
+        };
+        return clamp_into(
+            PhysicalPosition::new(left, top),
+            bounds,
+            target,
+            size.width,
+            size.height,
+        );
+    }
+    let x = furthest(
+        areas,
+        pos.x,
+        dx,
+        size.width,
+        |a| [a.position.x, area_end(a).0],
+        |x| covered(areas, WindowPosition { x, ..pos }, size),
+    );
+    let pos = WindowPosition { x, ..pos };
+    let y = furthest(
+        areas,
+        pos.y,
+        dy,
+        size.height,
+        |a| [a.position.y, area_end(a).1],
+        |y| covered(areas, WindowPosition { y, ..pos }, size),
+    );
+    WindowPosition { y, ..pos }
+}
+
+/// The furthest spot between `from` and `from + delta` on one axis where a
+/// window `length` long is `covered`, or `from` if there's none. Coverage
+/// only changes where a window edge meets an area edge, so only those spots
+/// and the target itself are checked, after pulling the target inside the
+/// areas' bounding box.
+fn furthest(
+    areas: &[Area],
+    from: i32,
+    delta: i32,
+    length: u32,
+    edges: impl Fn(&Area) -> [i32; 2],
+    covered: impl Fn(i32) -> bool,
+) -> i32 {
+    let (Some(low), Some(high)) = (
+        areas.iter().flat_map(&edges).min(),
+        areas.iter().flat_map(&edges).max(),
+    ) else {
+        return from;
+    };
+    let target = (from as i64 + delta as i64)
+        .min(high as i64 - length as i64)
+        .max(low as i64) as i32;
+    let range = from.min(target)..=from.max(target);
+    let mut spots: Vec<i32> = areas
+        .iter()
+        .flat_map(&edges)
+        .flat_map(|edge| [edge, edge.saturating_sub_unsigned(length)])
+        .filter(|spot| range.contains(spot))
+        .chain([target])
+        .collect();
+    spots.sort_unstable_by_key(|&spot| Reverse(spot.abs_diff(from)));
+    spots
+        .into_iter()
+        .find(|&spot| covered(spot))
+        .unwrap_or(from)
+}
+
+/// Moves the window by `(dx, dy)` physical pixels, keeping it on screen.
+pub fn nudge(window: &WebviewWindow, dx: i32, dy: i32) -> Result<WindowPosition, AppError> {
+    let areas: Vec<Area> = window
+        .available_monitors()?
+        .iter()
+        .map(|m| *m.work_area())
+        .collect();
+    let current = window.outer_position()?;
+    let current = WindowPosition {
+        x: current.x,
+        y: current.y,
+    };
+    let target = nudge_target(&areas, current, window.outer_size()?, dx, dy);
+    if target != current {
+        window.set_position(PhysicalPosition::new(target.x, target.y))?;
+    }
+    Ok(target)
+}
+
+/// Saves `pos` as the window's position, along with the monitor it's on.
+pub fn save(app: &AppHandle, window: &WebviewWindow, pos: WindowPosition) -> Result<(), AppError> {
+    let placement = placement(window, pos);
+    settings::update(app, |s| {
+        s.window_position = Some(pos);
+        s.window_monitor = placement;
+    })
+    .map_err(AppError::Io)
+}
+
+/// Resizes the window to at least `MIN_WINDOW_SIZE` and at most its monitor's
+/// work area, moving it back inside the work area if it would overhang.
+pub fn resize(window: &WebviewWindow, width: u32, height: u32) -> Result<WindowSize, AppError> {
+    let monitor = window
+        .current_monitor()?
+        .ok_or_else(|| AppError::Platform("window is not on any monitor".into()))?;
+    let area = monitor.work_area();
+    let size = WindowSize {
+        width: width.clamp(MIN_WINDOW_SIZE, area.size.width.max(MIN_WINDOW_SIZE)),
+        height: height.clamp(MIN_WINDOW_SIZE, area.size.height.max(MIN_WINDOW_SIZE)),
+    };
+    window.set_size(PhysicalSize::new(size.width, size.height))?;
+
+    let pos = window.outer_position()?;
+    let current = WindowPosition { x: pos.x, y: pos.y };
+    let target = clamp_into(area.position, area.size, current, size.width, size.height);
+    if target != current {
+        window.set_position(PhysicalPosition::new(target.x, target.y))?;
+    }
+    Ok(size)
+}
+
+/// Centers the window in `monitor`'s work area. The window keeps its logical
+/// size when it moves to a display with a different scale factor, so the
+/// physical size it will have there is used for the math.
+pub fn center_on_monitor(window: &WebviewWindow, monitor: &Monitor) -> tauri::Result<()> {
+    let size = window.outer_size()?;
+    let ratio = monitor.scale_factor() / window.scale_factor()?;
+    let width = (size.width as f64 * ratio).round() as i32;
+    let height = (size.height as f64 * ratio).round() as i32;
+    let area = monitor.work_area();
+    let x = area.position.x + (area.size.width as i32 - width) / 2;
+    let y = area.position.y + (area.size.height as i32 - height) / 2;
+    window.set_position(PhysicalPosition::new(x, y))
+}
+
+/// Places a window of length `len` along one axis of the work area. A window
+/// bigger than the area is pinned to its start edge.
+fn anchor_axis(align: Align, len: u32, area_start: i32, area_len: u32, margin: u32) -> i32 {
+    let Some(free) = area_len.checked_sub(len) else {
+        return area_start;
+    };
+    let offset = match align {
+        Align::Start => margin.min(free),
+        Align::Middle => free / 2,
+        Align::End => free.saturating_sub(margin),
+    };
+    area_start + offset as i32
+}
+
+/// Moves the window to `anchor` within the work area of the monitor it's on.
+pub fn move_to_anchor(window: &WebviewWindow, anchor: Anchor, margin: u32) -> Result<(), AppError> {
+    let monitor = window
+        .current_monitor()?
+        .ok_or_else(|| AppError::Platform("window is not on any monitor".into()))?;
+    let size = window.outer_size()?;
+    let area = monitor.work_area();
+    let (horizontal, vertical) = anchor.align();
+    let x = anchor_axis(
+        horizontal,
+        size.width,
+        area.position.x,
+        area.size.width,
+        margin,
+    );
+    let y = anchor_axis(
+        vertical,
+        size.height,
+        area.position.y,
+        area.size.height,
+        margin,
+    );
+    Ok(window.set_position(PhysicalPosition::new(x, y))?)
+}
+
+/// Pulls a window edge flush with the work-area edge when it's within
+/// `threshold` pixels of it.
+fn snap_axis(start: i32, len: u32, area_start: i32, area_len: u32, threshold: i32) -> i32 {
+    let end = start + len as i32;
+    let area_end = area_start + area_len as i32;
+    if (start - area_start).abs() <= threshold {
+        area_start
+    } else if (area_end - end).abs() <= threshold {
+        area_end - len as i32
+    } else {
+        start
+    }
+}
+
+#[tauri::command]
+pub fn snap_if_near(window: WebviewWindow, state: State<SettingsState>) -> Result<(), AppError> {
+    let threshold = state.0.lock().unwrap().snap_threshold as i32;
+    let pos = window.outer_position()?;
+    let size = window.outer_size()?;
+
+    // A window straddling two displays snaps to the one holding its center.
+    let center_x = pos.x + size.width as i32 / 2;
+    let center_y = pos.y + size.height as i32 / 2;
+    let monitors = window.available_monitors()?;
+    let Some(monitor) = monitors.iter().find(|m| contains(m, center_x, center_y)) else {
+        return Ok(());
+    };
+
+    let area = monitor.work_area();
+    let x = snap_axis(
+        pos.x,
+        size.width,
+        area.position.x,
+        area.size.width,
+        threshold,
+    );
+    let y = snap_axis(
+        pos.y,
+        size.height,
+        area.position.y,
+        area.size.height,
+        threshold,
+    );
+    if (x, y) != (pos.x, pos.y) {
+        window.set_position(PhysicalPosition::new(x, y))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn anchor_window(
+    window: WebviewWindow,
+    anchor: Anchor,
+    state: State<SettingsState>,
+) -> Result<(), AppError> {
+    let margin = state.0.lock().unwrap().anchor_margin;
+    move_to_anchor(&window, anchor, margin)
+}
+
+#[tauri::command]
+pub fn resize_window(
+    app: AppHandle,
+    window: WebviewWindow,
+    width: u32,
+    height: u32,
+) -> Result<(), AppError> {
+    let size = resize(&window, width, height)?;
+    settings::update(&app, |s| s.window_size = Some(size)).map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub fn get_window_geometry(window: WebviewWindow) -> Result<WindowGeometry, AppError> {
+    let monitor = window
+        .current_monitor()?
+        .ok_or_else(|| AppError::Platform("window is not on any monitor".into()))?;
+    Ok(WindowGeometry {
+        outer_position: window.outer_position()?,
+        inner_size: window.inner_size()?,
+        scale_factor: window.scale_factor()?,
+        monitor_name: monitor.name().cloned(),
+        work_area: *monitor.work_area(),
+    })
+}
+
+#[tauri::command]
+pub fn save_window_position(
+    app: AppHandle,
+    window: WebviewWindow,
+    x: i32,
+    y: i32,
+) -> Result<(), AppError> {
+    save(&app, &window, WindowPosition { x, y })
+}
+
+/// Shifts the window by a few pixels, e.g. for positioning it from the
+/// keyboard, and remembers where it ends up.
+#[tauri::command]
+pub fn nudge_window(
+    app: AppHandle,
+    window: WebviewWindow,
+    dx: i32,
+    dy: i32,
+) -> Result<(), AppError> {
+    let pos = nudge(&window, dx, dy)?;
+    save(&app, &window, pos)
+}
+
+#[tauri::command]
+pub fn saved_window_position(state: State<SettingsState>) -> Option<WindowPosition> {
+    state.0.lock().unwrap().window_position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display(name: &str, x: i32, y: i32, width: u32, height: u32) -> Display {
+        Display {
+            name: Some(name.into()),
+            area: PhysicalRect {
+                position: PhysicalPosition::new(x, y),
+                size: PhysicalSize::new(width, height),
+            },
+        }
+    }
+
+    fn saved(monitor: &str, x: i32, y: i32) -> MonitorPlacement {
+        MonitorPlacement {
+            monitor: monitor.into(),
+            offset: WindowPosition { x, y },
+        }
+    }
+
+    const WINDOW: PhysicalSize<u32> = PhysicalSize::new(800, 400);
+
+    #[test]
+    fn follows_its_monitor_when_the_layout_moves() {
+        // The external display used to sit right of the laptop; now it's left.
+        let laptop = display("Built-in", 0, 0, 1920, 1080);
+        let external = display("DELL U2720Q", -2560, 0, 2560, 1440);
+        let target = placement_target(
+            &[laptop.clone(), external],
+            Some(&laptop),
+            &saved("DELL U2720Q", 100, 900),
+            WINDOW,
+        );
+        assert_eq!(target, Some(WindowPosition { x: -2460, y: 900 }));
+    }
+
+    #[test]
+    fn centers_on_the_primary_monitor_when_its_monitor_is_gone() {
+        let side = display("Side", -1920, 0, 1920, 1080);
+        let laptop = display("Built-in", 0, 0, 1920, 1080);
+        let target = placement_target(
+            &[side, laptop.clone()],
+            Some(&laptop),
+            &saved("DELL U2720Q", 100, 900),
+            WINDOW,
+        );
+        assert_eq!(target, Some(WindowPosition { x: 560, y: 340 }));
+    }
+
+    #[test]
+    fn centers_on_the_first_monitor_without_a_primary() {
+        let first = display("First", 1920, 0, 1920, 1080);
+        let target = placement_target(
+            &[first, display("Second", 0, 0, 1280, 720)],
+            None,
+            &saved("Missing", 0, 0),
+            WINDOW,
+        );
+        assert_eq!(target, Some(WindowPosition { x: 2480, y: 340 }));
+    }
+
+    #[test]
+    fn stays_inside_a_smaller_work_area() {
+        // Same monitor, now running at a lower resolution.
+        let external = display("DELL U2720Q", 0, 0, 1280, 720);
+        let target = placement_target(&[external], None, &saved("DELL U2720Q", 2000, 1200), WINDOW);
+        assert_eq!(target, Some(WindowPosition { x: 480, y: 320 }));
+    }
+
+    fn area(x: i32, y: i32, width: u32, height: u32) -> Area {
+        display("", x, y, width, height).area
+    }
+
+    fn at(x: i32, y: i32) -> WindowPosition {
+        WindowPosition { x, y }
+    }
+
+    #[test]
+    fn nudges_stop_at_the_work_area_edge() {
+        let areas = [area(0, 0, 1920, 1080)];
+        assert_eq!(nudge_target(&areas, at(10, 10), WINDOW, 5, -3), at(15, 7));
+        assert_eq!(
+            nudge_target(&areas, at(1115, 0), WINDOW, 10, -10),
+            at(1120, 0)
+        );
+    }
+
+    #[test]
+    fn nudges_cross_between_aligned_displays() {
+        let areas = [area(0, 0, 1920, 1080), area(1920, 0, 1920, 1080)];
+        assert_eq!(
+            nudge_target(&areas, at(1115, 100), WINDOW, 10, 0),
+            at(1125, 100)
+        );
+    }
+
+    #[test]
+    fn nudges_dont_enter_the_gap_beside_a_shorter_display() {
+        // The right display is only 720 tall, so below that is off screen.
+        let areas = [area(0, 0, 1920, 1080), area(1920, 0, 1280, 720)];
+        assert_eq!(
+            nudge_target(&areas, at(1120, 600), WINDOW, 10, 0),
+            at(1120, 600)
+        );
+        assert_eq!(
+            nudge_target(&areas, at(1120, 320), WINDOW, 10, 0),
+            at(1130, 320)
+        );
+    }
+
+    #[test]
+    fn huge_nudges_stop_at_the_edge() {
+        let areas = [area(0, 0, 1920, 1080), area(1920, 0, 1920, 1080)];
+        assert_eq!(
+            nudge_target(&areas, at(10, 10), WINDOW, i32::MAX, i32::MIN),
+            at(3040, 0)
+        );
+        assert_eq!(
+            nudge_target(&areas, at(10, 10), WINDOW, i32::MIN, i32::MAX),
+            at(0, 680)
+        );
+        // Off screen to begin with, too.
+        let full = PhysicalSize::new(1920, 1200);
+        assert_eq!(
+            nudge_target(&areas, at(0, 0), full, i32::MAX, i32::MAX),
+            at(1920, 0)
+        );
+    }
+
+    #[test]
+    fn oversized_windows_stay_in_the_bounding_box() {
+        let areas = [area(0, 0, 1920, 1040)];
+        let full = PhysicalSize::new(1920, 1080);
+        assert_eq!(nudge_target(&areas, at(0, 0), full, 1, 1), at(0, 0));
+    }
+
+    #[test]
+    fn gives_up_without_any_monitor() {
+        assert_eq!(
+            placement_target(&[], None, &saved("Any", 0, 0), WINDOW),
+            None
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::settings::SettingsState;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Limits applied while running on battery with `reduce_on_battery` on.
+pub const BATTERY_TICK_RATE: u32 = 15;
+pub const BATTERY_MAX_VEHICLES: usize = 20;
+
+/// Whether the machine is running on battery.
+#[derive(Default)]
+pub struct PowerState(AtomicBool);
+
+#[derive(Clone, Copy, Serialize)]
+struct PowerStatus {
+    on_battery: bool,
+    power_saver: bool,
+}
+
+/// True if any battery is discharging. Desktops, and batteries that can't be
+/// read, count as AC power.
+#[cfg(desktop)]
+fn on_battery() -> bool {
+    use starship_battery::State;
+
+    let Ok(manager) = starship_battery::Manager::new() else {
+        return false;
+    };
+    let Ok(batteries) = manager.batteries() else {
+        return false;
+    };
+    batteries
+        .flatten()
+        .any(|battery| battery.state() == State::Discharging)
+}
+
+// starship-battery has no mobile backend.
+#[cfg(mobile)]
+fn on_battery() -> bool {
+    false
+}
+
+/// Whether the tick loop should currently throttle itself.
+pub fn power_saver(app: &AppHandle) -> bool {
+    app.state::<PowerState>().0.load(Ordering::Relaxed)
+        && app
+            .state::<SettingsState>()
+            .0
+            .lock()
+            .unwrap()
+            .reduce_on_battery
+}
+
+/// Spawns the thread that polls the power source and emits
+/// `power-state-changed` when it switches.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || loop {
+        let on_battery = on_battery();
+        let previous = app
+            .state::<PowerState>()
+            .0
+            .swap(on_battery, Ordering::Relaxed);
+        if previous != on_battery {
+            log::info!(
+                "running on {}",
+                if on_battery { "battery" } else { "AC power" }
+            );
+            let status = PowerStatus {
+                on_battery,
+                power_saver: power_saver(&app),
+            };
+            if let Err(e) = app.emit("power-state-changed", status) {
+                log::error!("failed to emit power-state-changed: {e}");
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    });
+}
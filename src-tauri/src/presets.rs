@@ -0,0 +1,288 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    error::AppError,
+    opacity, overlay,
+    settings::{self, Settings, SettingsState},
+    sim::{self, SimParams},
+    telemetry::{self, Event},
+    theme,
+};
+
+const DEFAULT_PRESET: &str = "Default";
+/// Bumped whenever shared presets change incompatibly.
+pub const PRESET_VERSION: u32 = 1;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Presets are a few hundred bytes; anything much bigger isn't one.
+const MAX_FETCH_BYTES: u64 = 64 * 1024;
+
+/// A named bundle of simulation params and appearance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    pub sim_params: SimParams,
+    pub opacity: f64,
+    pub theme: String,
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_PRESET.into(),
+            sim_params: SimParams::default(),
+            opacity: opacity::MAX_OPACITY,
+            theme: theme::DEFAULT_THEME.into(),
+        }
+    }
+}
+
+impl Preset {
+    /// Checks every field, including that the theme exists in `settings`, so
+    /// a bad preset is never half applied.
+    pub fn validate(&self, settings: &Settings) -> Result<(), String> {
+        self.sim_params.validate()?;
+        opacity::validate(self.opacity)?;
+        if !theme::exists(settings, &self.theme) {
+            return Err(format!("theme {:?} doesn't exist", self.theme));
+        }
+        Ok(())
+    }
+}
+
+fn user(settings: &Settings) -> impl Iterator<Item = &Preset> {
+    settings.presets.iter().filter(|p| p.name != DEFAULT_PRESET)
+}
+
+/// The built-in "Default" preset followed by the user's presets.
+pub fn all(settings: &Settings) -> Vec<Preset> {
+    std::iter::once(Preset::default())
+        .chain(user(settings).cloned())
+        .collect()
+}
+
+/// Applies every field of `preset` and tells the UI about it.
+pub fn apply(app: &AppHandle, preset: &Preset) -> Result<(), AppError> {
+    preset
+        .validate(&app.state::<SettingsState>().0.lock().unwrap())
+        .map_err(|e| AppError::InvalidParam(format!("preset {:?}: {e}", preset.name)))?;
+    let window =
+        overlay::main_window_or_recreate(app).ok_or_else(|| AppError::window_missing("main"))?;
+    sim::set_params(app, preset.sim_params)?;
+    opacity::set(&window, preset.opacity)?;
+    settings::update(app, |s| {
+        s.theme = preset.theme.clone();
+        s.active_preset = Some(preset.name.clone());
+    })
+    .map_err(AppError::Io)?;
+    theme::load_settings(app, &app.state::<SettingsState>().0.lock().unwrap());
+    if let Err(e) = app.emit("preset-applied", preset) {
+        log::error!("failed to emit preset-applied: {e}");
+    }
+    telemetry::record(
+        app,
+        Event::PresetUsed {
+            builtin: preset.name == DEFAULT_PRESET,
+        },
+    );
+    Ok(())
+}
+
+/// Looks up a preset by name.
+pub fn by_name(app: &AppHandle, name: &str) -> Option<Preset> {
+    let settings = app.state::<SettingsState>();
+    let settings = settings.0.lock().unwrap();
+    all(&settings).into_iter().find(|p| p.name == name)
+}
+
+/// Looks up a preset by its index in [`all`].
+pub fn get(app: &AppHandle, index: usize) -> Option<Preset> {
+    let settings = app.state::<SettingsState>();
+    let settings = settings.0.lock().unwrap();
+    all(&settings).into_iter().nth(index)
+}
+
+/// A preset as published online, tagged with [`PRESET_VERSION`].
+#[derive(Deserialize)]
+struct SharedPreset {
+    version: u32,
+    #[serde(flatten)]
+    preset: Preset,
+}
+
+/// Parses a downloaded preset. A bad document is an `InvalidParam`, so it
+/// can be told apart from a failed download.
+fn parse_shared(body: &[u8]) -> Result<Preset, AppError> {
+    let shared: SharedPreset = serde_json::from_slice(body)
+        .map_err(|e| AppError::InvalidParam(format!("not a valid preset: {e}")))?;
+    if shared.version != PRESET_VERSION {
+        return Err(AppError::InvalidParam(format!(
+            "preset version {} isn't supported, expected {PRESET_VERSION}",
+            shared.version
+        )));
+    }
+    shared
+        .preset
+        .sim_params
+        .validate()
+        .map_err(|e| AppError::InvalidParam(format!("not a valid preset: {e}")))?;
+    Ok(shared.preset)
+}
+
+/// Blocking, so `fetch_preset` runs it on a blocking thread. Uses the same
+/// `ureq` client as telemetry rather than pulling in a second HTTP stack.
+fn download(url: &str) -> Result<Vec<u8>, AppError> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(FETCH_TIMEOUT))
+        .https_only(true)
+        .build()
+        .into();
+    let mut response = agent
+        .get(url)
+        .call()
+        .map_err(|e| AppError::Io(format!("couldn't fetch {url}: {e}")))?;
+    response
+        .body_mut()
+        .with_config()
+        .limit(MAX_FETCH_BYTES)
+        .read_to_vec()
+        .map_err(|e| match e {
+            ureq::Error::BodyExceedsLimit(_) => AppError::InvalidParam(format!(
+                "not a valid preset: larger than {} KiB",
+                MAX_FETCH_BYTES / 1024
+            )),
+            e => AppError::Io(format!("couldn't fetch {url}: {e}")),
+        })
+}
+
+/// Downloads a shared preset without applying it, so it can be previewed.
+/// Network failures are `io` errors and bad presets `invalid_param` ones.
+#[tauri::command]
+pub async fn fetch_preset(url: String) -> Result<Preset, AppError> {
+    let uri: ureq::http::Uri = url
+        .parse()
+        .map_err(|e| AppError::InvalidParam(format!("invalid URL {url:?}: {e}")))?;
+    if uri.scheme_str() != Some("https") {
+        return Err(AppError::InvalidParam(format!(
+            "only https URLs can be fetched, got {url:?}"
+        )));
+    }
+    let body = tauri::async_runtime::spawn_blocking(move || download(&url))
+        .await
+        .map_err(AppError::from)??;
+    parse_shared(&body)
+}
+
+#[tauri::command]
+pub fn apply_preset(app: AppHandle, preset: Preset) -> Result<(), AppError> {
+    apply(&app, &preset)
+}
+
+/// Where `direction` steps from `current` land among `len` presets, wrapping
+/// around. Without a current preset, forward starts at the first and back at
+/// the last.
+fn cycle_index(current: Option<usize>, direction: i32, len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let len = len as i64;
+    let start = match current {
+        Some(i) => i as i64,
+        None if direction > 0 => -1,
+        None => len,
+    };
+    Some((start + i64::from(direction)).rem_euclid(len) as usize)
+}
+
+/// Applies the user preset `direction` steps from the active one. Does
+/// nothing when there are no user presets.
+pub fn cycle(app: &AppHandle, direction: i32) -> Result<(), AppError> {
+    let (presets, active) = {
+        let settings = app.state::<SettingsState>();
+        let settings = settings.0.lock().unwrap();
+        let presets: Vec<Preset> = user(&settings).cloned().collect();
+        (presets, settings.active_preset.clone())
+    };
+    let current = active.and_then(|name| presets.iter().position(|p| p.name == name));
+    let Some(index) = cycle_index(current, direction, presets.len()) else {
+        log::debug!("no presets to cycle through");
+        return Ok(());
+    };
+    apply(app, &presets[index])
+}
+
+/// Moves `direction` presets forward (positive) or back (negative).
+#[tauri::command]
+pub fn cycle_preset(app: AppHandle, direction: i32) -> Result<(), AppError> {
+    cycle(&app, direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_need_a_theme_and_opacity_that_exist() {
+        let settings = Settings::default();
+        assert_eq!(Preset::default().validate(&settings), Ok(()));
+
+        let faded = Preset {
+            opacity: 0.0,
+            ..Preset::default()
+        };
+        assert!(faded.validate(&settings).unwrap_err().contains("opacity"));
+
+        let unthemed = Preset {
+            theme: "no such theme".into(),
+            ..Preset::default()
+        };
+        assert!(unthemed.validate(&settings).unwrap_err().contains("theme"));
+    }
+
+    #[test]
+    fn shared_presets_are_parsed() {
+        let body = br#"{ "version": 1, "name": "Rush hour", "sim_params": { "spawn_rate": 5 } }"#;
+        let preset = parse_shared(body).unwrap();
+        assert_eq!(preset.name, "Rush hour");
+        assert_eq!(preset.sim_params.spawn_rate, 5.0);
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let body = br#"{ "version": 2, "name": "Rush hour" }"#;
+        assert!(matches!(parse_shared(body), Err(AppError::InvalidParam(_))));
+    }
+
+    #[test]
+    fn invalid_presets_are_rejected() {
+        for body in [
+            &b"<html>not found</html>"[..],
+            br#"{ "name": "No version" }"#,
+            br#"{ "version": 1, "sim_params": { "speed_multiplier": 1000 } }"#,
+        ] {
+            assert!(matches!(parse_shared(body), Err(AppError::InvalidParam(_))));
+        }
+    }
+
+    #[test]
+    fn cycling_wraps_around() {
+        assert_eq!(cycle_index(Some(2), 1, 3), Some(0));
+        assert_eq!(cycle_index(Some(0), -1, 3), Some(2));
+        assert_eq!(cycle_index(Some(1), 4, 3), Some(2));
+    }
+
+    #[test]
+    fn cycling_starts_from_either_end() {
+        assert_eq!(cycle_index(None, 1, 3), Some(0));
+        assert_eq!(cycle_index(None, -1, 3), Some(2));
+    }
+
+    #[test]
+    fn no_presets_is_a_no_op() {
+        assert_eq!(cycle_index(None, 1, 0), None);
+        assert_eq!(cycle_index(Some(0), -1, 0), None);
+    }
+}
@@ -0,0 +1,100 @@
+//! Holds the tick loop back until the webview is listening, so the first
+//! frames aren't emitted into the void.
+
+use std::{
+    sync::{atomic::Ordering, Condvar, Mutex},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::{
+    daylight::{self, TimeOfDay},
+    motion,
+    road::{RoadNetwork, RoadState},
+    settings::{Settings, SettingsState},
+    sim::{SimParams, SimState},
+    viewport::Viewport,
+};
+
+/// The simulation starts anyway if the frontend hasn't said it's ready by
+/// then.
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct ReadyState {
+    ready: Mutex<bool>,
+    arrived: Condvar,
+}
+
+/// Payload of `sim-init`: everything the canvas needs before its first frame.
+#[derive(Debug, Clone, Serialize)]
+struct SimInit {
+    settings: Settings,
+    params: SimParams,
+    paused: bool,
+    seed: u64,
+    viewport: Viewport,
+    network: RoadNetwork,
+    reduce_motion: bool,
+    time_of_day: TimeOfDay,
+}
+
+fn snapshot(app: &AppHandle) -> SimInit {
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    let state = app.state::<SimState>();
+    let params = *state.params.lock().unwrap();
+    let viewport = *state.viewport.lock().unwrap();
+    let network = app.state::<RoadState>().0.lock().unwrap().network().clone();
+    SimInit {
+        settings,
+        params: motion::adjust(app, params),
+        paused: state.paused.load(Ordering::Relaxed),
+        seed: state.seed.load(Ordering::Relaxed),
+        viewport,
+        network,
+        reduce_motion: motion::reduced(app),
+        time_of_day: daylight::current(app),
+    }
+}
+
+/// Blocks until `frontend_ready` is called or [`READY_TIMEOUT`] passes, then
+/// sends every window `sim-init`.
+pub fn wait(app: &AppHandle) {
+    let state = app.state::<ReadyState>();
+    let ready = state.ready.lock().unwrap();
+    let (mut ready, timeout) = state
+        .arrived
+        .wait_timeout_while(ready, READY_TIMEOUT, |ready| !*ready)
+        .unwrap();
+    if timeout.timed_out() {
+        log::warn!("frontend not ready after {READY_TIMEOUT:?}, starting the simulation anyway");
+        // Late arrivals are then caught up one by one.
+        *ready = true;
+    }
+    drop(ready);
+    if let Err(e) = app.emit("sim-init", snapshot(app)) {
+        log::error!("failed to emit sim-init: {e}");
+    }
+}
+
+/// Called by each webview once its listeners are set up. The first call
+/// starts the simulation; later ones, from reloaded or spawned windows, get
+/// their own `sim-init`.
+#[tauri::command]
+pub fn frontend_ready(window: WebviewWindow) {
+    let state = window.state::<ReadyState>();
+    let mut ready = state.ready.lock().unwrap();
+    if !*ready {
+        log::info!("frontend ready");
+        *ready = true;
+        state.arrived.notify_all();
+        return;
+    }
+    drop(ready);
+    let init = snapshot(window.app_handle());
+    if let Err(e) = window.emit_to(window.label(), "sim-init", init) {
+        log::error!("failed to emit sim-init: {e}");
+    }
+}
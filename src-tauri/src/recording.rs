@@ -0,0 +1,169 @@
+//! Records the vehicles at a fixed frame rate for a sped-up replay, and
+//! writes the frames out as a timelapse file when stopped.
+
+use std::{
+    fs,
+    ops::RangeInclusive,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    artifacts::ArtifactKind,
+    error::AppError,
+    road::{RoadNetwork, RoadState},
+    sim::{Sim, Vehicle},
+    snapshot,
+};
+
+/// Bumped whenever the timelapse format changes incompatibly.
+pub const TIMELAPSE_VERSION: u32 = 1;
+pub const FPS_RANGE: RangeInclusive<u32> = 1..=30;
+/// Recording stops taking frames once this many vehicle positions are
+/// buffered, which keeps it to a few tens of megabytes.
+const MAX_SAMPLES: usize = 500_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedFrame {
+    pub tick: u64,
+    pub vehicles: Vec<Vehicle>,
+}
+
+/// What `stop_recording` writes.
+#[derive(Debug, Serialize)]
+pub struct Timelapse {
+    pub version: u32,
+    pub fps: u32,
+    pub network: RoadNetwork,
+    pub frames: Vec<RecordedFrame>,
+}
+
+/// Payload of `recording-progress`, sent once per recorded second and when
+/// the buffer fills up.
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Progress {
+    frames: usize,
+    seconds: f64,
+    /// No more frames will be taken.
+    full: bool,
+}
+
+struct Recording {
+    fps: u32,
+    started: Instant,
+    next_frame: Instant,
+    frames: Vec<RecordedFrame>,
+    samples: usize,
+    full: bool,
+}
+
+impl Recording {
+    fn progress(&self) -> Progress {
+        Progress {
+            frames: self.frames.len(),
+            seconds: self.started.elapsed().as_secs_f64(),
+            full: self.full,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RecordingState(Mutex<Option<Recording>>);
+
+/// Called by the tick loop after each step; takes a frame when one is due.
+pub fn capture(app: &AppHandle, sim: &Sim) {
+    let state = app.state::<RecordingState>();
+    let mut recording = state.0.lock().unwrap();
+    let Some(recording) = recording.as_mut().filter(|r| !r.full) else {
+        return;
+    };
+    let now = Instant::now();
+    if now < recording.next_frame {
+        return;
+    }
+    let interval = Duration::from_secs(1) / recording.fps;
+    // After a stall, carry on from now rather than taking a burst of frames.
+    recording.next_frame = (recording.next_frame + interval).max(now);
+
+    let vehicles = sim.vehicles();
+    if recording.samples + vehicles.len() > MAX_SAMPLES {
+        recording.full = true;
+        log::warn!(
+            "recording is full after {} frames, no more will be taken",
+            recording.frames.len()
+        );
+    } else {
+        recording.samples += vehicles.len();
+        recording.frames.push(RecordedFrame {
+            tick: sim.tick(),
+            vehicles: vehicles.to_vec(),
+        });
+    }
+    if recording.full || recording.frames.len() % recording.fps as usize == 0 {
+        if let Err(e) = app.emit("recording-progress", recording.progress()) {
+            log::error!("failed to emit recording-progress: {e}");
+        }
+    }
+}
+
+/// Starts taking `fps` frames per second.
+#[tauri::command]
+pub fn start_recording(app: AppHandle, fps: u32) -> Result<(), AppError> {
+    if !FPS_RANGE.contains(&fps) {
+        return Err(AppError::InvalidParam(format!(
+            "fps must be between {} and {}, got {fps}",
+            FPS_RANGE.start(),
+            FPS_RANGE.end()
+        )));
+    }
+    let state = app.state::<RecordingState>();
+    let mut recording = state.0.lock().unwrap();
+    if recording.is_some() {
+        return Err(AppError::InvalidParam("already recording".into()));
+    }
+    let now = Instant::now();
+    *recording = Some(Recording {
+        fps,
+        started: now,
+        next_frame: now,
+        frames: Vec::new(),
+        samples: 0,
+        full: false,
+    });
+    log::info!("recording at {fps} fps");
+    Ok(())
+}
+
+/// Stops recording and writes the frames to the downloads folder, returning
+/// where they went.
+#[tauri::command]
+pub async fn stop_recording(app: AppHandle) -> Result<String, AppError> {
+    let recording = app
+        .state::<RecordingState>()
+        .0
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| AppError::InvalidParam("not recording".into()))?;
+    let timelapse = Timelapse {
+        version: TIMELAPSE_VERSION,
+        fps: recording.fps,
+        network: app.state::<RoadState>().0.lock().unwrap().network().clone(),
+        frames: recording.frames,
+    };
+    let path = snapshot::download_path(&app, ArtifactKind::Recording).map_err(AppError::Io)?;
+    // Large recordings take a moment to serialize; keep it off the main thread.
+    let written = path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let contents = serde_json::to_vec(&timelapse).map_err(|e| AppError::Io(e.to_string()))?;
+        fs::write(&written, contents)
+            .map_err(|e| AppError::Io(format!("failed to write {}: {e}", written.display())))
+    })
+    .await
+    .map_err(AppError::from)??;
+    log::info!("saved timelapse to {}", path.display());
+    Ok(path.display().to_string())
+}
@@ -0,0 +1,165 @@
+//! An interactive rectangle inside an otherwise click-through window, e.g.
+//! for a control bar along the top.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::{error::AppError, overlay};
+
+/// An area of a window in logical pixels from its top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    #[cfg_attr(any(mobile, target_os = "linux"), allow(dead_code))]
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// The part of the rect inside a window of the given logical size.
+    pub fn clip(&self, width: f64, height: f64) -> Option<Rect> {
+        let x = self.x.max(0.0);
+        let y = self.y.max(0.0);
+        let right = (self.x + self.width).min(width);
+        let bottom = (self.y + self.height).min(height);
+        (right > x && bottom > y).then_some(Rect {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        })
+    }
+}
+
+/// Interactive rects by window label.
+#[derive(Default)]
+pub struct InteractiveRegions {
+    rects: Mutex<HashMap<String, Rect>>,
+    /// Whether the cursor was last seen inside each window's rect.
+    #[cfg_attr(any(mobile, target_os = "linux"), allow(dead_code))]
+    inside: Mutex<HashMap<String, bool>>,
+}
+
+pub fn get(window: &WebviewWindow) -> Option<Rect> {
+    let regions = window.try_state::<InteractiveRegions>()?;
+    let rect = regions.rects.lock().unwrap().get(window.label()).copied();
+    rect
+}
+
+/// Drops a destroyed window's rect.
+pub fn forget(app: &AppHandle, label: &str) {
+    if let Some(regions) = app.try_state::<InteractiveRegions>() {
+        regions.rects.lock().unwrap().remove(label);
+        regions.inside.lock().unwrap().remove(label);
+    }
+}
+
+/// Makes the cursor poller re-apply the window's state on its next pass,
+/// after something else set click-through for the whole window.
+#[cfg(all(desktop, not(target_os = "linux")))]
+pub fn invalidate(window: &WebviewWindow) {
+    if let Some(regions) = window.try_state::<InteractiveRegions>() {
+        regions.inside.lock().unwrap().remove(window.label());
+    }
+}
+
+/// Re-fits the rect to the window's new size.
+pub fn on_resized(window: &WebviewWindow) {
+    if get(window).is_none() {
+        return;
+    }
+    if let Err(e) = overlay::set_ignore_cursor_events(window, overlay::click_through_of(window)) {
+        log::error!("failed to update interactive region: {e}");
+    }
+}
+
+/// Windows and macOS only make whole windows click-through, so watch the
+/// cursor and flip it as the cursor enters and leaves the rect. Linux uses
+/// the input shape instead and needs none of this.
+#[cfg(all(desktop, not(target_os = "linux")))]
+pub fn start(app: AppHandle) {
+    use std::{thread, time::Duration};
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(33);
+
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        let regions = app.state::<InteractiveRegions>();
+        let rects = regions.rects.lock().unwrap().clone();
+        if rects.is_empty() {
+            continue;
+        }
+        let Ok(cursor) = app.cursor_position() else {
+            continue;
+        };
+        for (label, rect) in rects {
+            let Some(window) = app.get_webview_window(&label) else {
+                continue;
+            };
+            if !overlay::click_through_of(&window) {
+                regions.inside.lock().unwrap().remove(&label);
+                continue;
+            }
+            let (Ok(origin), Ok(size), Ok(scale)) = (
+                window.inner_position(),
+                window.inner_size(),
+                window.scale_factor(),
+            ) else {
+                continue;
+            };
+            let x = (cursor.x - origin.x as f64) / scale;
+            let y = (cursor.y - origin.y as f64) / scale;
+            let size = size.to_logical::<f64>(scale);
+            let inside = rect
+                .clip(size.width, size.height)
+                .is_some_and(|r| r.contains(x, y));
+            let previous = regions.inside.lock().unwrap().insert(label, inside);
+            if previous != Some(inside) {
+                if let Err(e) = window.set_ignore_cursor_events(!inside) {
+                    log::error!("failed to update click-through: {e}");
+                }
+            }
+        }
+    });
+}
+
+#[cfg(any(mobile, target_os = "linux"))]
+pub fn start(_app: AppHandle) {}
+
+/// Keeps `rect` interactive while the rest of the window is click-through;
+/// `None` makes the whole window click-through again. Only matters while
+/// click-through is on.
+#[tauri::command]
+pub fn set_interactive_region(window: WebviewWindow, rect: Option<Rect>) -> Result<(), AppError> {
+    if let Some(rect) = rect {
+        let valid = [rect.x, rect.y, rect.width, rect.height]
+            .iter()
+            .all(|v| v.is_finite())
+            && rect.width > 0.0
+            && rect.height > 0.0;
+        if !valid {
+            return Err(AppError::InvalidParam(format!(
+                "invalid interactive region {rect:?}"
+            )));
+        }
+    }
+    {
+        let regions = window.state::<InteractiveRegions>();
+        let mut rects = regions.rects.lock().unwrap();
+        match rect {
+            Some(rect) => rects.insert(window.label().to_string(), rect),
+            None => rects.remove(window.label()),
+        };
+    }
+    Ok(overlay::set_ignore_cursor_events(
+        &window,
+        overlay::click_through_of(&window),
+    )?)
+}
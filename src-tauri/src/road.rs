@@ -0,0 +1,535 @@
+//! The roads vehicles drive on: nodes joined by one-way edges. Vehicles enter
+//! at dead ends, nodes joined to only one other, and leave when they reach a
+//! node with no way on that isn't a U-turn. Traffic keeps left, as it does in
+//! Tokyo.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+    error::AppError,
+    settings::{self, Settings},
+    sim::{Direction, OverlaySim, SimState, SCENE_H, SCENE_W},
+    viewport,
+};
+
+const LANE_W: f64 = 20.0;
+/// The shortest edge a network may have.
+const MIN_EDGE_LENGTH: f64 = LANE_W;
+/// How far off-screen vehicles enter and leave the built-in layouts.
+const SPAWN_MARGIN: f64 = 40.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Node {
+    pub id: String,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A one-way road from one node to another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub lanes: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoadNetwork {
+    pub name: String,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl Default for RoadNetwork {
+    fn default() -> Self {
+        intersection()
+    }
+}
+
+fn node(id: &str, x: f64, y: f64) -> Node {
+    Node {
+        id: id.into(),
+        x,
+        y,
+    }
+}
+
+/// Edges both ways between each pair.
+fn two_way(pairs: &[(&str, &str)], lanes: u8) -> Vec<Edge> {
+    pairs
+        .iter()
+        .flat_map(|(a, b)| [(a, b), (b, a)])
+        .map(|(from, to)| Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            lanes,
+        })
+        .collect()
+}
+
+fn highway() -> RoadNetwork {
+    let y = SCENE_H / 2.0;
+    RoadNetwork {
+        name: "highway".into(),
+        nodes: vec![
+            node("west", -SPAWN_MARGIN, y),
+            node("east", SCENE_W + SPAWN_MARGIN, y),
+        ],
+        edges: two_way(&[("west", "east")], 3),
+    }
+}
+
+/// The scramble crossing the scene is drawn around.
+fn intersection() -> RoadNetwork {
+    let (x, y) = (400.0, 208.0);
+    RoadNetwork {
+        name: "intersection".into(),
+        nodes: vec![
+            node("center", x, y),
+            node("north", x, -SPAWN_MARGIN),
+            node("south", x, SCENE_H + SPAWN_MARGIN),
+            node("west", -SPAWN_MARGIN, y),
+            node("east", SCENE_W + SPAWN_MARGIN, y),
+        ],
+        edges: two_way(
+            &[
+                ("north", "center"),
+                ("south", "center"),
+                ("west", "center"),
+                ("east", "center"),
+            ],
+            2,
+        ),
+    }
+}
+
+/// A one-lane ring, driven clockwise, with a road in from each side.
+fn roundabout() -> RoadNetwork {
+    let (x, y, r) = (400.0, 150.0, 70.0);
+    let mut edges = two_way(
+        &[
+            ("north", "ring_n"),
+            ("east", "ring_e"),
+            ("south", "ring_s"),
+            ("west", "ring_w"),
+        ],
+        1,
+    );
+    for (from, to) in [
+        ("ring_n", "ring_e"),
+        ("ring_e", "ring_s"),
+        ("ring_s", "ring_w"),
+        ("ring_w", "ring_n"),
+    ] {
+        edges.push(Edge {
+            from: from.into(),
+            to: to.into(),
+            lanes: 1,
+        });
+    }
+    RoadNetwork {
+        name: "roundabout".into(),
+        nodes: vec![
+            node("ring_n", x, y - r),
+            node("ring_e", x + r, y),
+            node("ring_s", x, y + r),
+            node("ring_w", x - r, y),
+            node("north", x, -SPAWN_MARGIN),
+            node("east", SCENE_W + SPAWN_MARGIN, y),
+            node("south", x, SCENE_H + SPAWN_MARGIN),
+            node("west", -SPAWN_MARGIN, y),
+        ],
+        edges,
+    }
+}
+
+pub fn builtins() -> Vec<RoadNetwork> {
+    vec![highway(), intersection(), roundabout()]
+}
+
+/// An edge resolved to coordinates.
+#[derive(Debug)]
+struct Segment {
+    from: usize,
+    to: usize,
+    x: f64,
+    y: f64,
+    /// Unit vector along the edge.
+    dx: f64,
+    dy: f64,
+    length: f64,
+    lanes: u8,
+}
+
+impl Segment {
+    /// The way the edge mostly runs, on screen.
+    fn direction(&self) -> Direction {
+        if self.dx.abs() >= self.dy.abs() {
+            if self.dx >= 0.0 {
+                Direction::E
+            } else {
+                Direction::W
+            }
+        } else if self.dy >= 0.0 {
+            Direction::S
+        } else {
+            Direction::N
+        }
+    }
+}
+
+/// A validated network, indexed for routing.
+#[derive(Debug)]
+pub struct Layout {
+    network: RoadNetwork,
+    segments: Vec<Segment>,
+    /// Edges leaving each node.
+    outgoing: Vec<Vec<usize>>,
+    /// Edges vehicles spawn on.
+    entries: Vec<usize>,
+}
+
+/// The box around every node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+/// Where a vehicle is drawn and which way it faces.
+pub struct Placement {
+    pub x: f64,
+    pub y: f64,
+    pub dx: f64,
+    pub dy: f64,
+    pub direction: Direction,
+}
+
+impl Layout {
+    /// Checks `network` can be driven: nodes are unique and finite, edges
+    /// join two existing nodes at least a lane apart with at least one lane,
+    /// and there's a dead end for vehicles to enter at.
+    pub fn new(network: RoadNetwork) -> Result<Self, String> {
+        let mut index = HashMap::new();
+        for (i, node) in network.nodes.iter().enumerate() {
+            if node.id.is_empty() {
+                return Err(format!("node {i} has no id"));
+            }
+            if !node.x.is_finite() || !node.y.is_finite() {
+                return Err(format!("node {:?} has an invalid position", node.id));
+            }
+            if index.insert(node.id.as_str(), i).is_some() {
+                return Err(format!("node {:?} is defined twice", node.id));
+            }
+        }
+        if network.edges.is_empty() {
+            return Err("network has no edges".into());
+        }
+
+        let mut segments = Vec::with_capacity(network.edges.len());
+        let mut outgoing = vec![Vec::new(); network.nodes.len()];
+        let mut neighbors = vec![Vec::new(); network.nodes.len()];
+        for edge in &network.edges {
+            let name = format!("edge {:?} -> {:?}", edge.from, edge.to);
+            let (Some(&from), Some(&to)) =
+                (index.get(edge.from.as_str()), index.get(edge.to.as_str()))
+            else {
+                return Err(format!("{name} refers to a node that doesn't exist"));
+            };
+            if edge.lanes == 0 {
+                return Err(format!("{name} has no lanes"));
+            }
+            let (a, b) = (&network.nodes[from], &network.nodes[to]);
+            let length = (b.x - a.x).hypot(b.y - a.y);
+            // Bounds how many edges a vehicle crosses in one step, so a cycle
+            // of tiny edges can't keep `step` busy.
+            if !(MIN_EDGE_LENGTH..=f64::MAX).contains(&length) {
+                return Err(format!(
+                    "{name} must be at least {MIN_EDGE_LENGTH} px long, got {length}"
+                ));
+            }
+            outgoing[from].push(segments.len());
+            for (node, other) in [(from, to), (to, from)] {
+                if !neighbors[node].contains(&other) {
+                    neighbors[node].push(other);
+                }
+            }
+            segments.push(Segment {
+                from,
+                to,
+                x: a.x,
+                y: a.y,
+                dx: (b.x - a.x) / length,
+                dy: (b.y - a.y) / length,
+                length,
+                lanes: edge.lanes,
+            });
+        }
+        let entries: Vec<usize> = (0..segments.len())
+            .filter(|&i| neighbors[segments[i].from].len() == 1)
+            .collect();
+        if entries.is_empty() {
+            return Err("network has no dead ends, so vehicles have nowhere to enter".into());
+        }
+        Ok(Self {
+            network,
+            segments,
+            outgoing,
+            entries,
+        })
+    }
+
+    pub fn network(&self) -> &RoadNetwork {
+        &self.network
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        let init = Bounds {
+            min_x: f64::INFINITY,
+            min_y: f64::INFINITY,
+            max_x: f64::NEG_INFINITY,
+            max_y: f64::NEG_INFINITY,
+        };
+        // Validation guarantees at least the two nodes of an edge.
+        self.network.nodes.iter().fold(init, |b, node| Bounds {
+            min_x: b.min_x.min(node.x),
+            min_y: b.min_y.min(node.y),
+            max_x: b.max_x.max(node.x),
+            max_y: b.max_y.max(node.y),
+        })
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn length(&self, edge: usize) -> f64 {
+        self.segments[edge].length
+    }
+
+    pub fn lanes(&self, edge: usize) -> u8 {
+        self.segments[edge].lanes
+    }
+
+    /// A random entry edge and lane.
+    pub fn entry(&self, rng: &mut impl Rng) -> (usize, u8) {
+        let edge = self.entries[rng.gen_range(0..self.entries.len())];
+        (edge, rng.gen_range(0..self.segments[edge].lanes))
+    }
+
+    /// A random edge onward from the end of `edge` that doesn't turn back.
+    /// `None` means the vehicle has left the network.
+    pub fn next(&self, edge: usize, rng: &mut impl Rng) -> Option<usize> {
+        let segment = &self.segments[edge];
+        let ahead: Vec<usize> = self.outgoing[segment.to]
+            .iter()
+            .copied()
+            .filter(|&e| self.segments[e].to != segment.from)
+            .collect();
+        if ahead.is_empty() {
+            return None;
+        }
+        Some(ahead[rng.gen_range(0..ahead.len())])
+    }
+
+    /// The point `progress` along `edge`, shifted left into `lane`. Lane 0
+    /// is nearest the middle of the road.
+    pub fn place(&self, edge: usize, lane: u8, progress: f64) -> Placement {
+        let s = &self.segments[edge];
+        let offset = (lane as f64 + 0.5) * LANE_W;
+        // In screen coordinates, with y pointing down, left of (dx, dy) is
+        // (dy, -dx).
+        Placement {
+            x: s.x + s.dx * progress + s.dy * offset,
+            y: s.y + s.dy * progress - s.dx * offset,
+            dx: s.dx,
+            dy: s.dy,
+            direction: s.direction(),
+        }
+    }
+
+    /// The edge running the other way between the same two nodes.
+    pub fn reverse(&self, edge: usize) -> Option<usize> {
+        let segment = &self.segments[edge];
+        self.segments
+            .iter()
+            .position(|s| s.from == segment.to && s.to == segment.from)
+    }
+
+    /// Where a vehicle leaving `bounds` from `(x, y)` on `edge` comes back
+    /// in: the point nearest the same spot on the opposite side, on an edge
+    /// heading the same way, as an edge and progress along it.
+    pub fn wrap(&self, edge: usize, x: f64, y: f64, bounds: &Bounds) -> Option<(usize, f64)> {
+        let direction = self.segments[edge].direction();
+        let (tx, ty) = match direction {
+            Direction::E => (bounds.min_x, y),
+            Direction::W => (bounds.max_x, y),
+            Direction::S => (x, bounds.min_y),
+            Direction::N => (x, bounds.max_y),
+        };
+        self.segments
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.direction() == direction)
+            .filter_map(|(i, s)| {
+                let progress = ((tx - s.x) * s.dx + (ty - s.y) * s.dy).clamp(0.0, s.length);
+                // The very end would leave again straight away.
+                if progress >= s.length {
+                    return None;
+                }
+                let distance = (s.x + s.dx * progress - tx).hypot(s.y + s.dy * progress - ty);
+                Some((i, progress, distance))
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(i, progress, _)| (i, progress))
+    }
+}
+
+/// The network the tick loop routes vehicles along.
+pub struct RoadState(pub Mutex<Arc<Layout>>);
+
+impl Default for RoadState {
+    fn default() -> Self {
+        let layout = Layout::new(RoadNetwork::default()).expect("built-in network is valid");
+        Self(Mutex::new(Arc::new(layout)))
+    }
+}
+
+/// Swaps in `layout` and clears the road, since vehicles are tied to the old
+/// edges.
+fn install(app: &AppHandle, layout: Layout) {
+    let network = layout.network().clone();
+    {
+        // Held so the tick loop can't step the old vehicles on the new edges.
+        let state = app.state::<SimState>();
+        let mut sim = state.sim.lock().unwrap();
+        let mut overlays = state.overlays.lock().unwrap();
+        *app.state::<RoadState>().0.lock().unwrap() = Arc::new(layout);
+        sim.clear();
+        overlays.values_mut().for_each(OverlaySim::clear);
+    }
+    log::info!("road network is now {:?}", network.name);
+    viewport::refit(app);
+    if let Err(e) = app.emit("road-network", network) {
+        log::error!("failed to emit road-network: {e}");
+    }
+}
+
+/// Uses the saved network, or the default when there isn't one.
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    let network = settings.road_network.clone().unwrap_or_default();
+    if *app.state::<RoadState>().0.lock().unwrap().network() == network {
+        return;
+    }
+    match Layout::new(network) {
+        Ok(layout) => install(app, layout),
+        Err(e) => log::warn!("ignoring saved road network: {e}"),
+    }
+}
+
+/// Validates, saves and switches to `network`. An invalid network leaves the
+/// current one in place.
+pub fn set(app: &AppHandle, network: RoadNetwork) -> Result<(), AppError> {
+    let layout = Layout::new(network.clone())
+        .map_err(|e| AppError::InvalidParam(format!("invalid road network: {e}")))?;
+    install(app, layout);
+    settings::update(app, |s| s.road_network = Some(network)).map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub fn get_road_network(state: State<RoadState>) -> RoadNetwork {
+    state.0.lock().unwrap().network().clone()
+}
+
+#[tauri::command]
+pub fn set_road_network(app: AppHandle, network: RoadNetwork) -> Result<(), AppError> {
+    set(&app, network)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_are_valid() {
+        for network in builtins() {
+            let name = network.name.clone();
+            assert!(Layout::new(network).is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn dangling_edge_is_rejected() {
+        let mut network = highway();
+        network.edges[0].to = "nowhere".into();
+        let err = Layout::new(network).unwrap_err();
+        assert!(err.contains("doesn't exist"), "{err}");
+    }
+
+    #[test]
+    fn edge_without_lanes_is_rejected() {
+        let mut network = highway();
+        network.edges[1].lanes = 0;
+        let err = Layout::new(network).unwrap_err();
+        assert!(err.contains("no lanes"), "{err}");
+    }
+
+    #[test]
+    fn tiny_edges_are_rejected() {
+        let mut network = highway();
+        (network.nodes[0].x, network.nodes[0].y) = (0.0, 0.0);
+        (network.nodes[1].x, network.nodes[1].y) = (1e-300, 0.0);
+        let err = Layout::new(network).unwrap_err();
+        assert!(err.contains("at least 20 px long"), "{err}");
+    }
+
+    #[test]
+    fn network_without_an_entry_is_rejected() {
+        let mut network = roundabout();
+        network.edges.retain(|e| e.from.starts_with("ring"));
+        let err = Layout::new(network).unwrap_err();
+        assert!(err.contains("nowhere to enter"), "{err}");
+    }
+
+    #[test]
+    fn lanes_keep_left() {
+        let layout = Layout::new(highway()).unwrap();
+        // Edge 0 runs west to east, so its left is up the screen.
+        let placement = layout.place(0, 0, 100.0);
+        assert_eq!(placement.direction, Direction::E);
+        assert_eq!(placement.x, 100.0 - SPAWN_MARGIN);
+        assert_eq!(placement.y, SCENE_H / 2.0 - LANE_W / 2.0);
+    }
+
+    #[test]
+    fn routing_avoids_u_turns() {
+        let layout = Layout::new(intersection()).unwrap();
+        let mut rng = rand::thread_rng();
+        // Edge 0 runs north to the center; edge 1 is the way back.
+        for _ in 0..20 {
+            let next = layout.next(0, &mut rng).unwrap();
+            assert_ne!(next, 1);
+        }
+        // Edge 1 ends at the north edge of the scene.
+        assert_eq!(layout.next(1, &mut rng), None);
+    }
+
+    #[test]
+    fn edges_find_their_way_back() {
+        let layout = Layout::new(intersection()).unwrap();
+        assert_eq!(layout.reverse(0), Some(1));
+        assert_eq!(layout.reverse(1), Some(0));
+        let mut network = intersection();
+        network.edges.remove(1);
+        assert_eq!(Layout::new(network).unwrap().reverse(0), None);
+    }
+}
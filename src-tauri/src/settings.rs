@@ -0,0 +1,867 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+#[cfg(desktop)]
+use crate::{audio, foreground, shortcut, tray, tray_load};
+use crate::{
+    collision::CollisionBehavior,
+    daylight,
+    error::{self, AppError},
+    heartbeat, i18n, idle, motion, opacity, overlay, platform, position,
+    presets::{self, Preset},
+    road::{self, RoadNetwork},
+    sim::{self, OverlayMode, SimParams},
+    telemetry,
+    theme::{self, Theme},
+    viewport::Waypoint,
+    watch,
+};
+
+const SETTINGS_FILE: &str = "settings.json";
+pub const SETTINGS_VERSION: u32 = 2;
+/// How long shutdown waits for the settings to reach disk.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+pub const DEFAULT_TOGGLE_SHORTCUT: &str = "CmdOrCtrl+Shift+T";
+pub const DEFAULT_NEXT_PRESET_SHORTCUT: &str = "Ctrl+Alt+Right";
+pub const DEFAULT_PREVIOUS_PRESET_SHORTCUT: &str = "Ctrl+Alt+Left";
+pub const DEFAULT_TIME_OF_DAY: f64 = 12.0;
+pub const DEFAULT_AUDIO_SENSITIVITY: f64 = 1.0;
+/// Held with the arrow keys to nudge the window.
+pub const DEFAULT_NUDGE_MODIFIER: &str = "Alt+Super";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// The monitor the window was last on and its offset from the top-left of
+/// that monitor's work area, so it can go back to the same screen after the
+/// layout changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorPlacement {
+    pub monitor: String,
+    pub offset: WindowPosition,
+}
+
+/// A spawned overlay to reopen on the next launch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedOverlay {
+    /// The monitor it covered. Unnamed monitors are found by `origin`.
+    pub monitor: Option<String>,
+    pub origin: WindowPosition,
+    /// Its own params, if it was given any.
+    pub params: Option<SimParams>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// What double-clicking the tray icon does. Only Windows reports double-clicks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayDoubleClickAction {
+    /// Center the window and bring it to the front.
+    #[default]
+    ResetPosition,
+    ToggleClickThrough,
+    None,
+}
+
+/// An entry in the tray menu. "Quit" always follows them and can't be removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayAction {
+    Show,
+    Hide,
+    ClickThrough,
+    AlwaysOnTop,
+    ResetPosition,
+    /// The "Move to…" submenu of screen spots.
+    Anchors,
+    Monitors,
+    Opacity,
+    Speed,
+    /// The submenu of built-in road layouts.
+    Roads,
+    /// The submenu of saved waypoints.
+    Waypoints,
+    Themes,
+    /// The submenu of every preset.
+    Presets,
+    /// Applies the next user preset.
+    NextPreset,
+    /// A single preset, by name.
+    ApplyPreset(String),
+    Pause,
+    ExportSnapshot,
+    CopySettings,
+    PasteSettings,
+    Notifications,
+    NetworkDriven,
+    Telemetry,
+    CheckUpdates,
+    Autostart,
+    CloseToTray,
+    Settings,
+    OpenConfig,
+    ResetSettings,
+}
+
+impl TrayAction {
+    /// The menu shipped before it was configurable.
+    pub fn defaults() -> Vec<TrayAction> {
+        vec![
+            TrayAction::Show,
+            TrayAction::Hide,
+            TrayAction::ClickThrough,
+            TrayAction::AlwaysOnTop,
+            TrayAction::ResetPosition,
+            TrayAction::Anchors,
+            TrayAction::Monitors,
+            TrayAction::Opacity,
+            TrayAction::Speed,
+            TrayAction::Roads,
+            TrayAction::Waypoints,
+            TrayAction::Themes,
+            TrayAction::Presets,
+            TrayAction::NextPreset,
+            TrayAction::Pause,
+            TrayAction::ExportSnapshot,
+            TrayAction::CopySettings,
+            TrayAction::PasteSettings,
+            TrayAction::Notifications,
+            TrayAction::NetworkDriven,
+            TrayAction::Telemetry,
+            TrayAction::CheckUpdates,
+            TrayAction::Autostart,
+            TrayAction::CloseToTray,
+            TrayAction::Settings,
+            TrayAction::OpenConfig,
+            TrayAction::ResetSettings,
+        ]
+    }
+}
+
+/// Skips tray entries this version doesn't know instead of rejecting the
+/// whole file.
+fn known_tray_actions<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<TrayAction>, D::Error> {
+    let entries = Vec::<Value>::deserialize(d)?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| match TrayAction::deserialize(&entry) {
+            Ok(action) => Some(action),
+            Err(e) => {
+                log::warn!("dropping tray item {entry}: {e}");
+                None
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub window_position: Option<WindowPosition>,
+    pub window_monitor: Option<MonitorPlacement>,
+    pub window_size: Option<WindowSize>,
+    pub click_through: bool,
+    pub always_on_top: bool,
+    pub opacity: f64,
+    pub toggle_shortcut: String,
+    pub next_preset_shortcut: String,
+    pub previous_preset_shortcut: String,
+    pub nudge_modifier: String,
+    pub tick_rate: u32,
+    /// Length of a physics step in milliseconds.
+    pub timestep_ms: f64,
+    /// Vehicles allowed on the road at once.
+    pub max_vehicles: u32,
+    pub sim_params: SimParams,
+    /// Seed for the simulation RNG; a random one is picked when unset.
+    pub seed: Option<u64>,
+    /// Distance in pixels at which a dragged window snaps to a screen edge.
+    pub snap_threshold: u32,
+    /// Gap in pixels kept between an anchored window and the work-area edge.
+    pub anchor_margin: u32,
+    pub notifications_enabled: bool,
+    /// Seconds without keyboard or mouse input before the simulation pauses;
+    /// 0 never pauses.
+    pub idle_timeout_secs: u64,
+    /// Lower the tick rate and vehicle count while running on battery.
+    pub reduce_on_battery: bool,
+    /// Spawn vehicles in proportion to real network throughput.
+    pub network_driven: bool,
+    /// Process names, e.g. "firefox"; the overlay only shows while one of
+    /// them is in front. Empty shows it always. Case and `.exe` are ignored.
+    pub app_allowlist: Vec<String>,
+    /// Calm the simulation down while the OS asks for reduced motion.
+    pub respect_reduce_motion: bool,
+    /// Play a sound when vehicles crash. The sound comes with a notification,
+    /// so it also needs `notifications_enabled`.
+    pub collision_sounds: bool,
+    pub collision_behavior: CollisionBehavior,
+    /// Whether spawned overlays mirror "main" or run their own traffic.
+    pub overlay_mode: OverlayMode,
+    /// Spawned overlays, reopened at launch.
+    pub open_overlays: Vec<SavedOverlay>,
+    /// Reopen overlays whose monitor is gone on the primary one instead of
+    /// skipping them.
+    pub relocate_orphaned_overlays: bool,
+    /// Keep the window transparent even where the desktop doesn't seem to
+    /// support it.
+    pub force_transparent: bool,
+    /// Whether the user has been told about the opaque fallback window.
+    pub transparency_warned: bool,
+    /// Left-clicking the tray icon toggles the window instead of opening the
+    /// menu. Off by default on macOS, where left-click opens the menu.
+    pub tray_click_toggles: bool,
+    /// The window's close button hides it to the tray instead of quitting.
+    pub close_to_tray: bool,
+    /// Whether the user has been told the app keeps running after a close.
+    pub close_to_tray_notified: bool,
+    /// Login launches start in the tray instead of showing the overlay.
+    pub start_hidden_on_autostart: bool,
+    pub tray_double_click_action: TrayDoubleClickAction,
+    /// Badge the tray icon green, amber or red by how full the road is.
+    pub dynamic_tray_icon: bool,
+    /// macOS only: keep the app out of the Dock and app switcher.
+    pub hide_from_dock: bool,
+    pub show_in_taskbar: bool,
+    /// Keep the overlay visible over fullscreen apps and on every Space. macOS only.
+    pub over_fullscreen: bool,
+    /// Send anonymous usage events; off until the user opts in.
+    pub telemetry_enabled: bool,
+    /// Whether the first-run telemetry question has been answered.
+    pub telemetry_prompted: bool,
+    /// Where telemetry batches are posted. Nothing is sent without one.
+    pub telemetry_endpoint: Option<String>,
+    /// Look for a new release in the background at startup.
+    pub auto_check_updates: bool,
+    /// Seconds between pings to the webview.
+    pub heartbeat_interval_secs: u64,
+    /// Unanswered pings in a row before the webview counts as hung.
+    pub heartbeat_missed_threshold: u32,
+    /// Reload a hung webview instead of only reporting it.
+    pub auto_recover_webview: bool,
+    /// Name of the active theme: a built-in or `custom_theme`.
+    pub theme: String,
+    /// The last theme set that isn't built in.
+    pub custom_theme: Option<Theme>,
+    /// Hour the scene is tinted for, from 0 to 24.
+    pub time_of_day: f64,
+    /// Follow the system clock instead of `time_of_day`.
+    pub auto_day_night: bool,
+    /// Scale spawning and speed with the microphone's level.
+    pub audio_reactive: bool,
+    /// Multiplies the microphone's level; higher needs less noise.
+    pub audio_sensitivity: f64,
+    /// The roads vehicles drive on; the built-in intersection when unset.
+    pub road_network: Option<RoadNetwork>,
+    /// Bookmarked viewports, listed in the tray.
+    pub waypoints: Vec<Waypoint>,
+    /// Language of the tray menu, e.g. "de"; the system's when unset.
+    pub locale: Option<String>,
+    /// What the tray menu shows, in order.
+    #[serde(deserialize_with = "known_tray_actions")]
+    pub tray_items: Vec<TrayAction>,
+    pub presets: Vec<Preset>,
+    /// Name of the preset applied last, where cycling continues from.
+    pub active_preset: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            window_position: None,
+            window_monitor: None,
+            window_size: None,
+            click_through: false,
+            always_on_top: true,
+            opacity: opacity::MAX_OPACITY,
+            toggle_shortcut: DEFAULT_TOGGLE_SHORTCUT.into(),
+            next_preset_shortcut: DEFAULT_NEXT_PRESET_SHORTCUT.into(),
+            previous_preset_shortcut: DEFAULT_PREVIOUS_PRESET_SHORTCUT.into(),
+            nudge_modifier: DEFAULT_NUDGE_MODIFIER.into(),
+            tick_rate: sim::DEFAULT_TICK_RATE,
+            timestep_ms: sim::DEFAULT_TIMESTEP_MS,
+            max_vehicles: sim::DEFAULT_MAX_VEHICLES,
+            sim_params: SimParams::default(),
+            seed: None,
+            snap_threshold: position::DEFAULT_SNAP_THRESHOLD,
+            anchor_margin: position::DEFAULT_ANCHOR_MARGIN,
+            notifications_enabled: true,
+            idle_timeout_secs: idle::DEFAULT_IDLE_TIMEOUT_SECS,
+            reduce_on_battery: true,
+            network_driven: false,
+            app_allowlist: Vec::new(),
+            respect_reduce_motion: true,
+            collision_sounds: false,
+            collision_behavior: CollisionBehavior::default(),
+            overlay_mode: OverlayMode::default(),
+            open_overlays: Vec::new(),
+            relocate_orphaned_overlays: false,
+            force_transparent: false,
+            transparency_warned: false,
+            tray_click_toggles: cfg!(not(target_os = "macos")),
+            close_to_tray: true,
+            close_to_tray_notified: false,
+            start_hidden_on_autostart: false,
+            tray_double_click_action: TrayDoubleClickAction::default(),
+            dynamic_tray_icon: false,
+            hide_from_dock: cfg!(target_os = "macos"),
+            show_in_taskbar: false,
+            over_fullscreen: false,
+            telemetry_enabled: false,
+            telemetry_prompted: false,
+            telemetry_endpoint: None,
+            auto_check_updates: true,
+            heartbeat_interval_secs: heartbeat::DEFAULT_INTERVAL_SECS,
+            heartbeat_missed_threshold: heartbeat::DEFAULT_MISSED_THRESHOLD,
+            auto_recover_webview: false,
+            theme: theme::DEFAULT_THEME.into(),
+            time_of_day: DEFAULT_TIME_OF_DAY,
+            auto_day_night: false,
+            audio_reactive: false,
+            audio_sensitivity: DEFAULT_AUDIO_SENSITIVITY,
+            custom_theme: None,
+            road_network: None,
+            waypoints: Vec::new(),
+            locale: None,
+            tray_items: TrayAction::defaults(),
+            presets: Vec::new(),
+            active_preset: None,
+        }
+    }
+}
+
+/// The live settings, shared between commands, the tray and the tick loop.
+#[derive(Default)]
+pub struct SettingsState(pub Mutex<Settings>);
+
+/// A settings file chosen with `--config`, used instead of the default.
+pub struct ConfigOverride(pub PathBuf);
+
+/// Managed once the settings file turns out to be from a newer version of the
+/// app. While set, nothing is saved, so running this version doesn't throw
+/// the newer settings away.
+pub struct NewerOnDisk(AtomicBool);
+
+fn newer_on_disk(app: &AppHandle) -> bool {
+    app.try_state::<NewerOnDisk>()
+        .is_some_and(|newer| newer.0.load(Ordering::Relaxed))
+}
+
+/// Lets settings be saved again, once the file on disk is one this version
+/// can read or the user has asked to overwrite it.
+pub fn allow_saving(app: &AppHandle) {
+    if let Some(newer) = app.try_state::<NewerOnDisk>() {
+        newer.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// The version a settings document claims, if it's newer than this app's.
+fn newer_version(contents: &str) -> Option<u32> {
+    let doc: Value = serde_json::from_str(contents).ok()?;
+    let version = u32::try_from(doc.get("version")?.as_u64()?).ok()?;
+    (version > SETTINGS_VERSION).then_some(version)
+}
+
+pub fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if let Some(config) = app.try_state::<ConfigOverride>() {
+        return Ok(config.0.clone());
+    }
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(SETTINGS_FILE))
+        .map_err(|e| e.to_string())
+}
+
+/// The folder holding the settings file, created if it doesn't exist yet.
+pub fn config_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let path = settings_path(app)?;
+    let dir = path
+        .parent()
+        .ok_or_else(|| format!("{} has no parent folder", path.display()))?;
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    Ok(dir.to_path_buf())
+}
+
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// v1 stored `transparency` as 0-255; v2 calls it `opacity` and uses 0.0-1.0.
+fn v1_to_v2(doc: &mut Map<String, Value>) {
+    if let Some(transparency) = doc.remove("transparency") {
+        if let Some(t) = transparency.as_f64() {
+            doc.insert("opacity".into(), (t / 255.0).into());
+        }
+    }
+}
+
+/// Upgrades a settings document to `SETTINGS_VERSION` one version at a time,
+/// returning the version it started at. Files without a version are v1.
+pub fn migrate(doc: &mut Value) -> Result<u32, String> {
+    let doc = doc
+        .as_object_mut()
+        .ok_or("settings must be a JSON object")?;
+    let from = match doc.get("version") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("invalid settings version {v}"))?,
+    };
+    if from > SETTINGS_VERSION {
+        return Err(format!(
+            "settings version {from} is newer than this app supports ({SETTINGS_VERSION})"
+        ));
+    }
+    for version in from..SETTINGS_VERSION {
+        match version {
+            1 => v1_to_v2(doc),
+            _ => return Err(format!("invalid settings version {version}")),
+        }
+    }
+    doc.insert("version".into(), SETTINGS_VERSION.into());
+    Ok(from)
+}
+
+impl Settings {
+    /// Parses a settings document, migrating it from older versions.
+    pub fn parse(contents: &str) -> Result<Settings, String> {
+        Self::parse_versioned(contents).map(|(settings, _)| settings)
+    }
+
+    fn parse_versioned(contents: &str) -> Result<(Settings, u32), String> {
+        let mut doc: Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+        let from = migrate(&mut doc)?;
+        let mut settings: Settings = serde_json::from_value(doc).map_err(|e| e.to_string())?;
+        settings.tray_items = settings.valid_tray_items();
+        Ok((settings, from))
+    }
+
+    /// `tray_items` without duplicates or presets that don't exist, logging
+    /// what's dropped. Falls back to the defaults rather than leaving only
+    /// "Quit".
+    pub fn valid_tray_items(&self) -> Vec<TrayAction> {
+        let presets = presets::all(self);
+        let mut valid: Vec<TrayAction> = Vec::new();
+        for action in &self.tray_items {
+            if let TrayAction::ApplyPreset(name) = action {
+                if !presets.iter().any(|p| &p.name == name) {
+                    log::warn!("dropping tray item for missing preset {name:?}");
+                    continue;
+                }
+            }
+            if valid.contains(action) {
+                log::warn!("dropping duplicate tray item {action:?}");
+                continue;
+            }
+            valid.push(action.clone());
+        }
+        if valid.is_empty() {
+            log::warn!("no usable tray items, using the defaults");
+            return TrayAction::defaults();
+        }
+        valid
+    }
+
+    /// Rejects what loading would refuse or quietly fix: another schema
+    /// version, tray items that `valid_tray_items` would drop, and invalid
+    /// simulation params.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != SETTINGS_VERSION {
+            return Err(format!(
+                "settings version must be {SETTINGS_VERSION}, got {}",
+                self.version
+            ));
+        }
+        if self.valid_tray_items() != self.tray_items {
+            return Err("tray items must be unique and name existing presets".into());
+        }
+        self.sim_params.validate()?;
+        for preset in &self.presets {
+            preset
+                .validate(self)
+                .map_err(|e| format!("preset {:?}: {e}", preset.name))?;
+        }
+        Ok(())
+    }
+
+    /// Reads `settings.json` from the app config dir, returning defaults when
+    /// the file is missing. Older versions are migrated and written back. A
+    /// too-new file is left alone and nothing is saved over it; a corrupt one
+    /// is moved to `settings.json.bak` before defaults are written in its place.
+    pub fn load(app: &AppHandle) -> Settings {
+        let path = match settings_path(app) {
+            Ok(path) => path,
+            Err(e) => {
+                log::error!("failed to locate settings: {e}");
+                return Settings::default();
+            }
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Settings::default(),
+            Err(e) => {
+                log::error!("failed to read {}: {e}", path.display());
+                return Settings::default();
+            }
+        };
+        match Self::parse_versioned(&contents) {
+            Ok((settings, from)) => {
+                if from < SETTINGS_VERSION {
+                    log::info!("migrated settings from v{from} to v{SETTINGS_VERSION}");
+                    if let Err(e) = settings.save(app) {
+                        log::error!("failed to write migrated settings: {e}");
+                    }
+                }
+                settings
+            }
+            Err(e) if newer_version(&contents).is_some() => {
+                app.manage(NewerOnDisk(AtomicBool::new(true)));
+                let message = format!(
+                    "{} was written by a newer version of the app ({e}). Running on \
+                     defaults without saving; update the app to use your settings.",
+                    path.display()
+                );
+                if let Err(e) = app
+                    .notification()
+                    .builder()
+                    .title("Settings from a newer version")
+                    .body(&message)
+                    .show()
+                {
+                    log::error!("failed to show notification: {e}");
+                }
+                error::report(
+                    app,
+                    "ignoring settings from a newer version",
+                    AppError::InvalidParam(message),
+                );
+                Settings::default()
+            }
+            Err(e) => {
+                log::warn!(
+                    "{} is unusable ({e}), resetting to defaults",
+                    path.display()
+                );
+                if let Err(e) = fs::rename(&path, with_extension(&path, ".bak")) {
+                    log::error!("failed to back up settings: {e}");
+                }
+                let settings = Settings::default();
+                if let Err(e) = settings.save(app) {
+                    log::error!("failed to write default settings: {e}");
+                }
+                settings
+            }
+        }
+    }
+
+    /// Writes the settings atomically by renaming a fully written temp file
+    /// over the old one.
+    pub fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let path = settings_path(app)?;
+        if newer_on_disk(app) {
+            return Err(format!(
+                "not overwriting {}, which is from a newer version of the app",
+                path.display()
+            ));
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let tmp = with_extension(&path, ".tmp");
+        fs::write(&tmp, contents).map_err(|e| e.to_string())?;
+        watch::record_save(app);
+        fs::rename(&tmp, &path).map_err(|e| e.to_string())
+    }
+}
+
+/// Mutates the live settings and persists the result.
+pub fn update(app: &AppHandle, f: impl FnOnce(&mut Settings)) -> Result<(), String> {
+    let state = app.state::<SettingsState>();
+    let mut settings = state.0.lock().unwrap();
+    f(&mut settings);
+    settings.save(app)
+}
+
+/// Records the window's final position and writes the settings to disk. Gives
+/// up after `FLUSH_TIMEOUT` so a locked file can't block shutdown.
+pub fn flush(app: &AppHandle) {
+    if app.try_state::<SettingsState>().is_none() {
+        return;
+    }
+    // A minimized window reports a bogus off-screen position on Windows.
+    let window = app
+        .get_webview_window("main")
+        .filter(|w| !w.is_minimized().unwrap_or(false));
+    let position = window.as_ref().and_then(|w| {
+        let pos = w.outer_position().ok()?;
+        let pos = WindowPosition { x: pos.x, y: pos.y };
+        Some((pos, position::placement(w, pos)))
+    });
+
+    let app = app.clone();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let result = update(&app, |s| {
+            if let Some((pos, placement)) = position {
+                s.window_position = Some(pos);
+                s.window_monitor = placement;
+            }
+        });
+        let _ = tx.send(result);
+    });
+    match rx.recv_timeout(FLUSH_TIMEOUT) {
+        Ok(Ok(())) => log::info!("settings saved"),
+        Ok(Err(e)) => log::error!("failed to save settings on exit: {e}"),
+        Err(_) => log::error!("timed out saving settings on exit"),
+    }
+}
+
+/// Pushes settings into the running app: window state, tick rate, shortcut.
+pub fn apply(app: &AppHandle, settings: &Settings) {
+    overlay::load_settings(app, settings);
+    sim::load_settings(app, settings);
+    motion::load_settings(app, settings);
+    platform::set_dock_hidden(app, settings.hide_from_dock);
+    telemetry::load_settings(app, settings);
+    theme::load_settings(app, settings);
+    daylight::load_settings(app, settings);
+    i18n::load_settings(app, settings);
+    road::load_settings(app, settings);
+    #[cfg(desktop)]
+    {
+        if let Err(e) = shortcut::register_toggle(app, &settings.toggle_shortcut) {
+            log::error!("failed to register toggle shortcut: {e}");
+        }
+        if let Err(e) = shortcut::register_preset_cycle(
+            app,
+            &settings.next_preset_shortcut,
+            &settings.previous_preset_shortcut,
+        ) {
+            log::error!("failed to register preset shortcuts: {e}");
+        }
+        if let Err(e) = shortcut::register_nudge(app, &settings.nudge_modifier) {
+            log::error!("failed to register nudge shortcuts: {e}");
+        }
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        if let Some(size) = settings.window_size {
+            if let Err(e) = position::resize(&window, size.width, size.height) {
+                log::error!("failed to resize window: {e}");
+            }
+        }
+        if let Err(e) = overlay::apply_window_state(&window, &overlay::window_state(&window)) {
+            log::error!("failed to apply window state: {e}");
+        }
+    }
+    #[cfg(desktop)]
+    {
+        foreground::load_settings(app, settings);
+        audio::load_settings(app, settings);
+        tray::load_settings(app, settings);
+        tray_load::load_settings(app, settings);
+        tray::sync(app);
+        tray::refresh_presets(app);
+        tray::refresh_waypoints(app);
+    }
+}
+
+/// Makes `new` the live settings and applies it to the running app.
+pub fn replace(app: &AppHandle, new: Settings) {
+    *app.state::<SettingsState>().0.lock().unwrap() = new.clone();
+    apply(app, &new);
+}
+
+/// Restores the defaults, keeping the old file as `settings.json.bak`, and
+/// moves the window back to the middle of its monitor.
+pub fn reset(app: &AppHandle) -> Result<(), String> {
+    let path = settings_path(app)?;
+    if path.exists() {
+        fs::copy(&path, with_extension(&path, ".bak"))
+            .map_err(|e| format!("failed to back up settings: {e}"))?;
+    }
+    allow_saving(app);
+    let defaults = Settings::default();
+    defaults.save(app)?;
+    replace(app, defaults.clone());
+
+    if let Some(window) = app.get_webview_window("main") {
+        match window.current_monitor() {
+            Ok(Some(monitor)) => {
+                if let Err(e) = position::center_on_monitor(&window, &monitor) {
+                    log::error!("failed to reset position: {e}");
+                }
+            }
+            Ok(None) => log::warn!("window is not on any monitor"),
+            Err(e) => log::error!("failed to query current monitor: {e}"),
+        }
+    }
+    log::info!("settings reset to defaults");
+    app.emit("settings-reset", defaults)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_settings(state: State<SettingsState>) -> Settings {
+    state.0.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_settings(app: AppHandle, new: Settings) -> Result<(), AppError> {
+    new.validate().map_err(AppError::InvalidParam)?;
+    new.save(&app).map_err(AppError::Io)?;
+    replace(&app, new);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn reset_settings(app: AppHandle) -> Result<(), AppError> {
+    reset(&app).map_err(AppError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn parse(doc: Value) -> Result<Settings, String> {
+        Settings::parse(&doc.to_string())
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(Settings::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn validation_rejects_what_loading_would_fix() {
+        let old = Settings {
+            version: 1,
+            ..Settings::default()
+        };
+        assert!(old.validate().unwrap_err().contains("version"));
+
+        let mut twice = Settings::default();
+        twice.tray_items.push(twice.tray_items[0].clone());
+        assert!(twice.validate().unwrap_err().contains("tray items"));
+
+        let mut fast = Settings::default();
+        fast.sim_params.speed_multiplier = 1000.0;
+        assert!(fast.validate().unwrap_err().contains("speed"));
+    }
+
+    #[test]
+    fn v1_transparency_becomes_opacity() {
+        let settings = parse(json!({ "version": 1, "transparency": 51 })).unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert!((settings.opacity - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn missing_version_is_v1() {
+        let settings = parse(json!({ "transparency": 255, "tick_rate": 60 })).unwrap();
+        assert_eq!(settings.opacity, 1.0);
+        assert_eq!(settings.tick_rate, 60);
+    }
+
+    #[test]
+    fn v1_without_transparency_keeps_other_fields() {
+        let settings =
+            parse(json!({ "version": 1, "opacity": 0.5, "click_through": true })).unwrap();
+        assert_eq!(settings.opacity, 0.5);
+        assert!(settings.click_through);
+    }
+
+    #[test]
+    fn current_version_is_untouched() {
+        let expected = Settings {
+            opacity: 0.75,
+            ..Settings::default()
+        };
+        let doc = serde_json::to_value(&expected).unwrap();
+        assert_eq!(parse(doc).unwrap(), expected);
+    }
+
+    #[test]
+    fn unknown_tray_items_are_dropped() {
+        let settings = parse(json!({
+            "version": SETTINGS_VERSION,
+            "tray_items": ["show", "fly_to_the_moon", { "apply_preset": "Default" }],
+        }))
+        .unwrap();
+        assert_eq!(
+            settings.tray_items,
+            [TrayAction::Show, TrayAction::ApplyPreset("Default".into())]
+        );
+    }
+
+    #[test]
+    fn tray_items_for_missing_presets_and_duplicates_are_dropped() {
+        let settings = parse(json!({
+            "version": SETTINGS_VERSION,
+            "tray_items": ["pause", { "apply_preset": "Rush Hour" }, "pause", "hide"],
+        }))
+        .unwrap();
+        assert_eq!(settings.tray_items, [TrayAction::Pause, TrayAction::Hide]);
+    }
+
+    #[test]
+    fn unusable_tray_items_fall_back_to_defaults() {
+        let settings = parse(json!({
+            "version": SETTINGS_VERSION,
+            "tray_items": ["fly_to_the_moon"],
+        }))
+        .unwrap();
+        assert_eq!(settings.tray_items, TrayAction::defaults());
+    }
+
+    #[test]
+    fn future_version_is_rejected() {
+        let err = parse(json!({ "version": SETTINGS_VERSION + 1 })).unwrap_err();
+        assert!(err.contains("newer than this app supports"), "{err}");
+    }
+
+    #[test]
+    fn only_newer_versions_count_as_newer() {
+        let doc = |version: u32| json!({ "version": version }).to_string();
+        assert_eq!(
+            newer_version(&doc(SETTINGS_VERSION + 1)),
+            Some(SETTINGS_VERSION + 1)
+        );
+        assert_eq!(newer_version(&doc(SETTINGS_VERSION)), None);
+        assert_eq!(newer_version("{ not json"), None);
+    }
+
+    #[test]
+    fn version_zero_is_rejected() {
+        let err = parse(json!({ "version": 0 })).unwrap_err();
+        assert!(err.contains("invalid settings version 0"), "{err}");
+    }
+}
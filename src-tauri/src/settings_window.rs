@@ -0,0 +1,31 @@
+//! A regular window for editing the settings, apart from the overlay.
+
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+pub const SETTINGS_LABEL: &str = "settings";
+
+/// Focuses the settings window, opening it first if it isn't already.
+pub fn open(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window(SETTINGS_LABEL) {
+        window.unminimize()?;
+        window.show()?;
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(app, SETTINGS_LABEL, WebviewUrl::App("settings.html".into()))
+        .title("Shibuya Traffic Settings")
+        .inner_size(520.0, 640.0)
+        .min_inner_size(360.0, 240.0)
+        .resizable(true)
+        .focused(true)
+        .build()?;
+    Ok(())
+}
+
+/// Closes the settings window if it's open.
+pub fn close(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(SETTINGS_LABEL) {
+        if let Err(e) = window.destroy() {
+            log::error!("failed to close the settings window: {e}");
+        }
+    }
+}
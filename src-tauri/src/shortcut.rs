@@ -0,0 +1,193 @@
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::{
+    error::{self, AppError},
+    overlay, position, presets, settings,
+};
+
+/// Pixels moved per arrow key press, and with Shift held.
+const NUDGE_STEP: i32 = 1;
+const NUDGE_STEP_LARGE: i32 = 10;
+/// Arrow key names and the direction each one nudges in.
+const NUDGE_KEYS: [(&str, i32, i32); 4] = [
+    ("Left", -1, 0),
+    ("Right", 1, 0),
+    ("Up", 0, -1),
+    ("Down", 0, 1),
+];
+
+/// The accelerator currently bound to toggling the overlay.
+#[derive(Default)]
+pub struct ToggleShortcut(Mutex<Option<Shortcut>>);
+
+/// The accelerators currently bound to cycling presets.
+#[derive(Default)]
+pub struct PresetShortcuts {
+    next: Mutex<Option<Shortcut>>,
+    previous: Mutex<Option<Shortcut>>,
+}
+
+/// The modifier+arrow accelerators currently bound to nudging the window:
+/// the [`NUDGE_KEYS`] in order, then the same with Shift.
+#[derive(Default)]
+pub struct NudgeShortcuts([Mutex<Option<Shortcut>>; 8]);
+
+/// What a shortcut does when pressed.
+type OnPress = Box<dyn Fn(&AppHandle) + Send + Sync>;
+
+fn unregister(app: &AppHandle, shortcut: Shortcut) {
+    if let Err(e) = app.global_shortcut().unregister(shortcut) {
+        log::error!("failed to unregister shortcut {shortcut:?}: {e}");
+    }
+}
+
+/// Binds each accelerator to its handler, replacing the binding held in its
+/// slot. Either every new shortcut is registered or none is: the old ones
+/// stay active until all the new ones have taken.
+fn bind(
+    app: &AppHandle,
+    bindings: Vec<(&Mutex<Option<Shortcut>>, String, OnPress)>,
+) -> Result<(), AppError> {
+    let mut parsed = Vec::with_capacity(bindings.len());
+    for (slot, accelerator, on_press) in bindings {
+        let shortcut: Shortcut = accelerator.parse().map_err(|e| {
+            AppError::InvalidParam(format!("invalid accelerator {accelerator:?}: {e}"))
+        })?;
+        parsed.push((slot, shortcut, on_press));
+    }
+
+    let global = app.global_shortcut();
+    let mut slots = Vec::with_capacity(parsed.len());
+    let mut registered = Vec::new();
+    for (slot, shortcut, on_press) in parsed {
+        let current = slot.lock().unwrap();
+        if *current != Some(shortcut) || !global.is_registered(shortcut) {
+            let result = global.on_shortcut(shortcut, move |app, _, event| {
+                if event.state() == ShortcutState::Pressed {
+                    on_press(app);
+                }
+            });
+            if let Err(e) = result {
+                // Drops what this call added, so nothing is half rebound.
+                for shortcut in registered {
+                    unregister(app, shortcut);
+                }
+                return Err(AppError::Platform(e.to_string()));
+            }
+            registered.push(shortcut);
+        }
+        slots.push((current, shortcut));
+    }
+
+    let new: Vec<Shortcut> = slots.iter().map(|(_, shortcut)| *shortcut).collect();
+    for (mut current, shortcut) in slots {
+        if let Some(old) = current.replace(shortcut) {
+            if !new.contains(&old) {
+                unregister(app, old);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Binds `accelerator` to the visibility toggle.
+pub fn register_toggle(app: &AppHandle, accelerator: &str) -> Result<(), AppError> {
+    let state = app.state::<ToggleShortcut>();
+    bind(
+        app,
+        vec![(
+            &state.0,
+            accelerator.into(),
+            Box::new(overlay::toggle_visibility),
+        )],
+    )
+}
+
+fn cycle_preset(app: &AppHandle, direction: i32) {
+    if let Err(e) = presets::cycle(app, direction) {
+        error::report(app, "failed to switch preset", e);
+    }
+}
+
+/// Binds `next` and `previous` to cycling forward and back through the
+/// presets.
+pub fn register_preset_cycle(app: &AppHandle, next: &str, previous: &str) -> Result<(), AppError> {
+    let state = app.state::<PresetShortcuts>();
+    bind(
+        app,
+        vec![
+            (
+                &state.next,
+                next.into(),
+                Box::new(|app| cycle_preset(app, 1)),
+            ),
+            (
+                &state.previous,
+                previous.into(),
+                Box::new(|app| cycle_preset(app, -1)),
+            ),
+        ],
+    )
+}
+
+fn nudge(app: &AppHandle, dx: i32, dy: i32) {
+    let Some(window) = overlay::main_window_or_recreate(app) else {
+        return;
+    };
+    let result = position::nudge(&window, dx, dy).and_then(|pos| position::save(app, &window, pos));
+    if let Err(e) = result {
+        error::report(app, "failed to move window", e);
+    }
+}
+
+/// Binds `modifier` plus each arrow key to nudging the main window a pixel,
+/// or [`NUDGE_STEP_LARGE`] with Shift as well.
+pub fn register_nudge(app: &AppHandle, modifier: &str) -> Result<(), AppError> {
+    let state = app.state::<NudgeShortcuts>();
+    let (small, large) = state.0.split_at(NUDGE_KEYS.len());
+    let mut bindings: Vec<(_, _, OnPress)> = Vec::with_capacity(state.0.len());
+    for (&(key, x, y), slot) in NUDGE_KEYS.iter().zip(small) {
+        bindings.push((
+            slot,
+            format!("{modifier}+{key}"),
+            Box::new(move |app| nudge(app, x * NUDGE_STEP, y * NUDGE_STEP)),
+        ));
+    }
+    for (&(key, x, y), slot) in NUDGE_KEYS.iter().zip(large) {
+        bindings.push((
+            slot,
+            format!("{modifier}+Shift+{key}"),
+            Box::new(move |app| nudge(app, x * NUDGE_STEP_LARGE, y * NUDGE_STEP_LARGE)),
+        ));
+    }
+    bind(app, bindings)
+}
+
+#[tauri::command]
+pub fn set_toggle_shortcut(app: AppHandle, accelerator: String) -> Result<(), AppError> {
+    register_toggle(&app, &accelerator)?;
+    settings::update(&app, |s| s.toggle_shortcut = accelerator).map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub fn set_preset_shortcuts(
+    app: AppHandle,
+    next: String,
+    previous: String,
+) -> Result<(), AppError> {
+    register_preset_cycle(&app, &next, &previous)?;
+    settings::update(&app, |s| {
+        s.next_preset_shortcut = next;
+        s.previous_preset_shortcut = previous;
+    })
+    .map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub fn set_nudge_modifier(app: AppHandle, modifier: String) -> Result<(), AppError> {
+    register_nudge(&app, &modifier)?;
+    settings::update(&app, |s| s.nudge_modifier = modifier).map_err(AppError::Io)
+}
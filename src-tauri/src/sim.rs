@@ -0,0 +1,1615 @@
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+#[cfg(desktop)]
+use crate::{audio, tray, tray_load};
+use crate::{
+    collision::{self, Collision, CollisionBehavior},
+    crash,
+    error::{self, AppError},
+    frame::FrameEmitter,
+    idle::IdleMonitor,
+    metrics::{self, Recorder},
+    motion, netstat, notify, overlay, power, ready, recording,
+    road::{Bounds, Layout, RoadState},
+    settings::{self, Settings, SettingsState},
+    stats,
+    viewport::Viewport,
+};
+
+// Scene size, mirroring `src/scene/Road.ts`.
+pub const SCENE_W: f64 = 800.0;
+pub const SCENE_H: f64 = 300.0;
+
+pub const DEFAULT_TICK_RATE: u32 = 30;
+pub const MIN_TICK_RATE: u32 = 1;
+pub const MAX_TICK_RATE: u32 = 120;
+/// Physics timestep in milliseconds. Frames are emitted at the tick rate,
+/// with as many fixed steps in between as the elapsed time calls for.
+pub const DEFAULT_TIMESTEP_MS: f64 = 1000.0 / DEFAULT_TICK_RATE as f64;
+pub const TIMESTEP_MS_RANGE: RangeInclusive<f64> = 1.0..=200.0;
+/// Steps taken per frame at most; time beyond that is dropped rather than
+/// letting a slow host fall further and further behind.
+const MAX_STEPS_PER_FRAME: u32 = 1000;
+/// Tick rate used while the overlay is hidden, to save battery.
+const HIDDEN_TICK_RATE: u32 = 1;
+/// Vehicle count above which the scene counts as a traffic jam.
+const JAM_THRESHOLD: usize = 50;
+/// How often to ask the OS how long the user has been idle.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Times the tick loop is restarted after a panic before giving up, and the
+/// delay before the first restart, doubled each time.
+const MAX_RESTARTS: u32 = 5;
+const RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+pub const SPEED_MULTIPLIER_RANGE: RangeInclusive<f64> = 0.1..=10.0;
+pub const SPAWN_RATE_RANGE: RangeInclusive<f64> = 0.0..=100.0;
+/// Vehicles allowed on the road at once; spawning waits while it's full.
+pub const DEFAULT_MAX_VEHICLES: u32 = 300;
+pub const MAX_VEHICLES_RANGE: RangeInclusive<u32> = 1..=2000;
+/// `vehicle-cap-reached` is sent at most this often while spawning waits.
+const CAP_EVENT_INTERVAL: Duration = Duration::from_secs(5);
+/// Burst spawns stop once this many vehicles are on the road, or fewer while
+/// saving power.
+pub const BURST_MAX_VEHICLES: usize = 200;
+/// Longest a burst can be spread over.
+const MAX_BURST_DURATION: Duration = Duration::from_secs(60);
+/// Longest `run_for` accepts, in simulated seconds.
+const MAX_RUN_SECS: f64 = 24.0 * 60.0 * 60.0;
+/// Trail lengths in frames; longer ones are cut down rather than rejected.
+pub const TRAIL_LENGTH_RANGE: RangeInclusive<u32> = 1..=120;
+/// Strongest global force, in pixels per second. Below the base vehicle
+/// speed, so a headwind slows traffic without stopping it.
+pub const MAX_GLOBAL_FORCE: f64 = 40.0;
+/// The fastest a restored vehicle may be going, in pixels per second.
+const MAX_VEHICLE_SPEED: f64 = 1000.0;
+/// How far from the origin a restored vehicle may be, in pixels.
+const MAX_VEHICLE_COORD: f64 = 1e6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Vec2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+
+    pub fn length(self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    /// Scales the vector down to at most `max` long.
+    pub fn clamp_length(self, max: f64) -> Self {
+        let length = self.length();
+        if length <= max {
+            return self;
+        }
+        Vec2 {
+            x: self.x / length * max,
+            y: self.y / length * max,
+        }
+    }
+}
+
+/// What happens to a vehicle that drives out of view or off the end of the
+/// network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundaryBehavior {
+    /// It comes back in on the opposite side, on a road going the same way.
+    Wrap,
+    /// It turns round onto the road back, if there is one.
+    Bounce,
+    /// It's removed, and spawning replaces it.
+    #[default]
+    Despawn,
+}
+
+/// User-tunable simulation parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimParams {
+    pub speed_multiplier: f64,
+    /// Vehicles spawned per second.
+    pub spawn_rate: f64,
+    /// Whether the canvas draws fading trails behind vehicles.
+    pub trail_enabled: bool,
+    /// How many frames a trail lasts.
+    pub trail_length: u32,
+    /// A push on every vehicle, like wind or a slope, in pixels per second.
+    /// Vehicles stay on their roads; only the part along a road speeds them
+    /// up or slows them down.
+    pub global_force: Vec2,
+    pub boundary_behavior: BoundaryBehavior,
+}
+
+impl Default for SimParams {
+    fn default() -> Self {
+        Self {
+            speed_multiplier: 1.0,
+            spawn_rate: 0.4,
+            trail_enabled: false,
+            trail_length: 8,
+            global_force: Vec2::ZERO,
+            boundary_behavior: BoundaryBehavior::default(),
+        }
+    }
+}
+
+impl SimParams {
+    /// Pulls `trail_length` into [`TRAIL_LENGTH_RANGE`] and `global_force`
+    /// down to [`MAX_GLOBAL_FORCE`].
+    pub fn clamped(self) -> Self {
+        Self {
+            trail_length: self
+                .trail_length
+                .clamp(*TRAIL_LENGTH_RANGE.start(), *TRAIL_LENGTH_RANGE.end()),
+            global_force: self.global_force.clamp_length(MAX_GLOBAL_FORCE),
+            ..self
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !SPEED_MULTIPLIER_RANGE.contains(&self.speed_multiplier) {
+            return Err(format!(
+                "speed multiplier must be between {} and {}, got {}",
+                SPEED_MULTIPLIER_RANGE.start(),
+                SPEED_MULTIPLIER_RANGE.end(),
+                self.speed_multiplier
+            ));
+        }
+        if !SPAWN_RATE_RANGE.contains(&self.spawn_rate) {
+            return Err(format!(
+                "spawn rate must be between {} and {} vehicles/sec, got {}",
+                SPAWN_RATE_RANGE.start(),
+                SPAWN_RATE_RANGE.end(),
+                self.spawn_rate
+            ));
+        }
+        if !(self.global_force.x.is_finite() && self.global_force.y.is_finite()) {
+            return Err(format!(
+                "global force must be finite, got ({}, {})",
+                self.global_force.x, self.global_force.y
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    N,
+    S,
+    E,
+    W,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vehicle {
+    pub id: u64,
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+    pub direction: Direction,
+    pub lane: u8,
+    /// Index of the road network edge the vehicle is on.
+    pub edge: usize,
+    /// Distance travelled along `edge`.
+    pub progress: f64,
+    pub speed: f64,
+    /// Seconds since the vehicle crashed, if it has.
+    #[serde(default)]
+    pub crashed: Option<f64>,
+}
+
+impl Vehicle {
+    /// Moves the vehicle to `progress` along its edge.
+    fn place(&mut self, layout: &Layout) {
+        let placement = layout.place(self.edge, self.lane, self.progress);
+        self.x = placement.x;
+        self.y = placement.y;
+        self.vx = placement.dx * self.speed;
+        self.vy = placement.dy * self.speed;
+        self.direction = placement.direction;
+    }
+
+    /// Checks a vehicle from outside the app can be driven on `layout`, so a
+    /// bad file can't leave `step` looping over an out-of-range progress.
+    pub fn validate(&self, layout: &Layout) -> Result<(), String> {
+        let id = self.id;
+        if self.edge >= layout.edge_count() {
+            return Err(format!(
+                "vehicle {id} is on edge {}, which doesn't exist",
+                self.edge
+            ));
+        }
+        if self.lane >= layout.lanes(self.edge) {
+            return Err(format!(
+                "vehicle {id} is in lane {} of edge {}, which has {} lanes",
+                self.lane,
+                self.edge,
+                layout.lanes(self.edge)
+            ));
+        }
+        if !(0.0..=MAX_VEHICLE_SPEED).contains(&self.speed) {
+            return Err(format!(
+                "vehicle {id} speed must be between 0 and {MAX_VEHICLE_SPEED}, got {}",
+                self.speed
+            ));
+        }
+        let length = layout.length(self.edge);
+        if !(0.0..=length).contains(&self.progress) {
+            return Err(format!(
+                "vehicle {id} progress must be between 0 and its edge's length {length}, got {}",
+                self.progress
+            ));
+        }
+        let in_range = |v: f64| v.abs() <= MAX_VEHICLE_COORD;
+        if ![self.x, self.y, self.vx, self.vy].into_iter().all(in_range) {
+            return Err(format!(
+                "vehicle {id} position and velocity must be within {MAX_VEHICLE_COORD}, got \
+                 ({}, {}) moving ({}, {})",
+                self.x, self.y, self.vx, self.vy
+            ));
+        }
+        if self
+            .crashed
+            .is_some_and(|secs| !(secs >= 0.0 && secs.is_finite()))
+        {
+            return Err(format!(
+                "vehicle {id} crash time must be a finite, non-negative number of seconds"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the vehicle is outside `bounds` and heading further out.
+    fn leaving(&self, bounds: &Bounds) -> bool {
+        (self.x < bounds.min_x && self.vx < 0.0)
+            || (self.x > bounds.max_x && self.vx > 0.0)
+            || (self.y < bounds.min_y && self.vy < 0.0)
+            || (self.y > bounds.max_y && self.vy > 0.0)
+    }
+}
+
+/// What happened during one [`Sim::step`].
+#[derive(Default)]
+pub struct StepEvents {
+    pub spawned: u64,
+    pub collisions: Vec<Collision>,
+    /// The last vehicle of a burst was spawned.
+    pub burst_ended: bool,
+    /// A spawn was held back because the road is at the vehicle cap.
+    pub capped: bool,
+}
+
+impl StepEvents {
+    fn extend(&mut self, other: StepEvents) {
+        self.spawned += other.spawned;
+        self.collisions.extend(other.collisions);
+        self.burst_ended |= other.burst_ended;
+        self.capped |= other.capped;
+    }
+}
+
+/// Extra vehicles spawned on top of the steady rate, spread evenly over
+/// `secs_left`. Payload of `burst-started`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Burst {
+    pub remaining: u32,
+    pub secs_left: f64,
+    #[serde(skip)]
+    until_spawn: f64,
+}
+
+/// A `run_for` in progress: the simulation pauses once `remaining` simulated
+/// seconds have passed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TimedRun {
+    secs: f64,
+    remaining: f64,
+    spawned: u64,
+    collisions: u64,
+}
+
+impl TimedRun {
+    fn new(secs: f64) -> Self {
+        Self {
+            secs,
+            remaining: secs,
+            spawned: 0,
+            collisions: 0,
+        }
+    }
+
+    /// Counts a step covering `secs` of simulated time and returns whether
+    /// the run is over.
+    fn record(&mut self, secs: f64, events: &StepEvents) -> bool {
+        // Absorbs rounding, so e.g. a hundred 10 ms steps make one second.
+        const EPSILON: f64 = 1e-9;
+        self.spawned += events.spawned;
+        self.collisions += events.collisions.len() as u64;
+        self.remaining -= secs;
+        self.remaining <= EPSILON
+    }
+}
+
+/// Payload of `run-complete`: what happened during a `run_for`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RunSummary {
+    pub sim_secs: f64,
+    pub vehicles_spawned: u64,
+    pub collisions: u64,
+    /// Vehicles on the road when the run ended.
+    pub vehicles: usize,
+}
+
+/// Turns frame intervals into a whole number of fixed-length steps, carrying
+/// the remainder over to the next frame.
+#[derive(Default)]
+struct StepClock {
+    pending: f64,
+}
+
+impl StepClock {
+    /// Adds `elapsed` seconds and returns how many steps of `timestep`
+    /// seconds are now due.
+    fn advance(&mut self, elapsed: f64, timestep: f64) -> u32 {
+        // Absorbs rounding, so e.g. three 1/30 s frames make ten 10 ms steps.
+        const EPSILON: f64 = 1e-9;
+        self.pending += elapsed;
+        let steps = ((self.pending + EPSILON) / timestep).floor();
+        if steps > MAX_STEPS_PER_FRAME as f64 {
+            self.pending = 0.0;
+            return MAX_STEPS_PER_FRAME;
+        }
+        self.pending = (self.pending - steps * timestep).max(0.0);
+        steps as u32
+    }
+}
+
+pub struct Sim {
+    vehicles: Vec<Vehicle>,
+    next_id: u64,
+    until_spawn: f64,
+    /// Steps taken since the simulation started.
+    tick: u64,
+    /// Spawning stops while this many vehicles are on the road.
+    max_vehicles: usize,
+    collision_behavior: CollisionBehavior,
+    burst: Option<Burst>,
+    /// The part of the world in view. Without it only the network's exits
+    /// count as leaving.
+    bounds: Option<Bounds>,
+    /// Drives every random choice, so a seed replays the same traffic.
+    rng: StdRng,
+    clock: StepClock,
+}
+
+impl Sim {
+    pub fn new(seed: u64) -> Self {
+        Self::restore(Vec::new(), 0, seed)
+    }
+
+    /// Resumes from saved vehicles at the given tick.
+    pub fn restore(vehicles: Vec<Vehicle>, tick: u64, seed: u64) -> Self {
+        let next_id = vehicles.iter().map(|v| v.id).max().unwrap_or(0) + 1;
+        Self {
+            vehicles,
+            next_id,
+            until_spawn: 0.0,
+            tick,
+            max_vehicles: usize::MAX,
+            collision_behavior: CollisionBehavior::default(),
+            burst: None,
+            bounds: None,
+            rng: StdRng::seed_from_u64(seed),
+            clock: StepClock::default(),
+        }
+    }
+
+    pub fn vehicles(&self) -> &[Vehicle] {
+        &self.vehicles
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn set_max_vehicles(&mut self, max: usize) {
+        self.max_vehicles = max;
+    }
+
+    pub fn set_collision_behavior(&mut self, behavior: CollisionBehavior) {
+        self.collision_behavior = behavior;
+    }
+
+    pub fn set_bounds(&mut self, bounds: Option<Bounds>) {
+        self.bounds = bounds;
+    }
+
+    /// How many steps of `timestep` are due after a frame of `elapsed`
+    /// seconds.
+    pub fn steps_due(&mut self, elapsed: f64, timestep: f64) -> u32 {
+        self.clock.advance(elapsed, timestep)
+    }
+
+    /// Takes every vehicle off the road.
+    pub fn clear(&mut self) {
+        self.vehicles.clear();
+    }
+
+    /// Adds `count` vehicles to spawn over the next `secs`. A burst that's
+    /// already running takes them on and runs until the later of the two
+    /// would have ended. Returns the merged burst.
+    pub fn add_burst(&mut self, count: u32, secs: f64) -> Burst {
+        let burst = self.burst.get_or_insert(Burst {
+            remaining: 0,
+            secs_left: 0.0,
+            until_spawn: 0.0,
+        });
+        burst.remaining += count;
+        burst.secs_left = burst.secs_left.max(secs);
+        burst.until_spawn = burst
+            .until_spawn
+            .min(burst.secs_left / burst.remaining as f64);
+        *burst
+    }
+
+    /// Advances every vehicle by `dt` seconds along `layout`, spawning at its
+    /// entries. Vehicles reaching an exit or leaving the bounds wrap, bounce
+    /// or despawn as `params.boundary_behavior` says.
+    pub fn step(&mut self, dt: f64, params: &SimParams, layout: &Layout) -> StepEvents {
+        self.tick += 1;
+        let move_dt = dt * params.speed_multiplier;
+        let behavior = self.collision_behavior;
+        let bounds = self.bounds;
+        let rng = &mut self.rng;
+        // Vehicles restored onto a different network have no edge to follow.
+        self.vehicles.retain(|v| v.edge < layout.edge_count());
+        self.vehicles.retain_mut(|vehicle| {
+            if let Some(secs) = &mut vehicle.crashed {
+                *secs += dt;
+                match behavior {
+                    CollisionBehavior::Stop => return true,
+                    CollisionBehavior::Despawn => return *secs < collision::WRECK_CLEAR_SECS,
+                    CollisionBehavior::PassThrough => {}
+                }
+            }
+            let push = if params.global_force == Vec2::ZERO {
+                0.0
+            } else {
+                let along = layout.place(vehicle.edge, vehicle.lane, vehicle.progress);
+                params.global_force.x * along.dx + params.global_force.y * along.dy
+            };
+            // A headwind stronger than the vehicle stalls it; nothing reverses.
+            vehicle.progress += (vehicle.speed + push).max(0.0) * move_dt;
+            let mut exited = false;
+            while vehicle.progress >= layout.length(vehicle.edge) {
+                let Some(next) = layout.next(vehicle.edge, rng) else {
+                    vehicle.progress = layout.length(vehicle.edge);
+                    exited = true;
+                    break;
+                };
+                vehicle.progress -= layout.length(vehicle.edge);
+                vehicle.edge = next;
+                vehicle.lane = vehicle.lane.min(layout.lanes(next) - 1);
+            }
+            vehicle.place(layout);
+            if !exited && !bounds.is_some_and(|b| vehicle.leaving(&b)) {
+                return true;
+            }
+            match params.boundary_behavior {
+                BoundaryBehavior::Despawn => return false,
+                BoundaryBehavior::Wrap => {
+                    let bounds = bounds.unwrap_or_else(|| layout.bounds());
+                    let Some((edge, progress)) =
+                        layout.wrap(vehicle.edge, vehicle.x, vehicle.y, &bounds)
+                    else {
+                        return false;
+                    };
+                    vehicle.edge = edge;
+                    vehicle.progress = progress;
+                }
+                BoundaryBehavior::Bounce => {
+                    let Some(back) = layout.reverse(vehicle.edge) else {
+                        return false;
+                    };
+                    // The same spot, the other way.
+                    vehicle.progress = (layout.length(back) - vehicle.progress).max(0.0);
+                    vehicle.edge = back;
+                }
+            }
+            vehicle.lane = vehicle.lane.min(layout.lanes(vehicle.edge) - 1);
+            vehicle.place(layout);
+            true
+        });
+
+        // Vehicles that have both crashed already are only reported once.
+        let mut events = StepEvents::default();
+        for (i, j) in collision::detect(&self.vehicles) {
+            if self.vehicles[i].crashed.is_some() && self.vehicles[j].crashed.is_some() {
+                continue;
+            }
+            for k in [i, j] {
+                self.vehicles[k].crashed.get_or_insert(0.0);
+            }
+            events
+                .collisions
+                .push(Collision::between(&self.vehicles[i], &self.vehicles[j]));
+        }
+
+        if params.spawn_rate > 0.0 {
+            self.until_spawn -= dt;
+            while self.until_spawn <= 0.0 {
+                // At the cap the spawn is skipped but the schedule keeps
+                // ticking, so the road fills back up as vehicles leave.
+                if self.vehicles.len() < self.max_vehicles {
+                    self.spawn(layout);
+                    events.spawned += 1;
+                } else {
+                    events.capped = true;
+                }
+                // Jitter the interval so spawns don't arrive in lockstep.
+                self.until_spawn += self.rng.gen_range(0.8..1.2) / params.spawn_rate;
+            }
+        }
+
+        if let Some(mut burst) = self.burst.take() {
+            burst.secs_left = (burst.secs_left - dt).max(0.0);
+            burst.until_spawn -= dt;
+            while burst.remaining > 0 && burst.until_spawn <= 0.0 {
+                // Like steady spawns, those over the cap are skipped.
+                if self.vehicles.len() < self.max_vehicles.min(BURST_MAX_VEHICLES) {
+                    self.spawn(layout);
+                    events.spawned += 1;
+                } else {
+                    events.capped = true;
+                }
+                burst.remaining -= 1;
+                if burst.remaining > 0 {
+                    burst.until_spawn += burst.secs_left / burst.remaining as f64;
+                }
+            }
+            if burst.remaining > 0 {
+                self.burst = Some(burst);
+            } else {
+                events.burst_ended = true;
+            }
+        }
+        events
+    }
+
+    fn spawn(&mut self, layout: &Layout) {
+        let (edge, lane) = layout.entry(&mut self.rng);
+        let mut vehicle = Vehicle {
+            id: self.next_id,
+            x: 0.0,
+            y: 0.0,
+            vx: 0.0,
+            vy: 0.0,
+            direction: Direction::N,
+            lane,
+            edge,
+            progress: 0.0,
+            speed: 50.0 + self.rng.gen_range(-2.0..2.0),
+            crashed: None,
+        };
+        vehicle.place(layout);
+        self.vehicles.push(vehicle);
+        self.next_id += 1;
+    }
+}
+
+/// Whether spawned overlays mirror "main" or each run their own traffic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayMode {
+    #[default]
+    Shared,
+    Independent,
+}
+
+/// A spawned overlay's own simulation, used in [`OverlayMode::Independent`].
+pub struct OverlaySim {
+    sim: Sim,
+    params: SimParams,
+}
+
+impl OverlaySim {
+    fn new(params: SimParams) -> Self {
+        Self {
+            sim: Sim::new(random_seed()),
+            params,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.sim.clear();
+    }
+}
+
+pub struct SimState {
+    pub tick_rate: AtomicU32,
+    pub max_vehicles: AtomicU32,
+    /// Physics step length in milliseconds.
+    pub timestep_ms: Mutex<f64>,
+    pub params: Mutex<SimParams>,
+    pub paused: AtomicBool,
+    pub seed: AtomicU64,
+    pub collision_behavior: Mutex<CollisionBehavior>,
+    /// The part of the world the canvas shows.
+    pub viewport: Mutex<Viewport>,
+    /// The live simulation, advanced by the tick loop.
+    pub sim: Mutex<Sim>,
+    pub overlay_mode: Mutex<OverlayMode>,
+    /// Simulations of spawned overlays by window label. Only stepped in
+    /// independent mode; locked after `sim` when both are needed.
+    pub overlays: Mutex<HashMap<String, OverlaySim>>,
+    /// The pending `run_for`, if any. Locked after `sim`.
+    timed_run: Mutex<Option<TimedRun>>,
+    /// Steps the tick loop still has to take while paused, queued by `step`.
+    manual_steps: AtomicU32,
+}
+
+/// Picks a seed that survives a round trip through a JavaScript number.
+fn random_seed() -> u64 {
+    rand::thread_rng().gen_range(0..1 << 53)
+}
+
+impl Default for SimState {
+    fn default() -> Self {
+        let seed = random_seed();
+        Self {
+            tick_rate: AtomicU32::new(DEFAULT_TICK_RATE),
+            max_vehicles: AtomicU32::new(DEFAULT_MAX_VEHICLES),
+            timestep_ms: Mutex::new(DEFAULT_TIMESTEP_MS),
+            params: Mutex::default(),
+            paused: AtomicBool::new(false),
+            seed: AtomicU64::new(seed),
+            collision_behavior: Mutex::default(),
+            viewport: Mutex::default(),
+            sim: Mutex::new(Sim::new(seed)),
+            overlay_mode: Mutex::default(),
+            overlays: Mutex::default(),
+            timed_run: Mutex::default(),
+            manual_steps: AtomicU32::new(0),
+        }
+    }
+}
+
+impl SimState {
+    /// The vehicle cap, lowered further while saving power.
+    fn vehicle_cap(&self, power_saver: bool) -> usize {
+        let cap = self.max_vehicles.load(Ordering::Relaxed) as usize;
+        if power_saver {
+            cap.min(power::BATTERY_MAX_VEHICLES)
+        } else {
+            cap
+        }
+    }
+
+    /// Replaces the running simulation with `sim`, which was seeded from
+    /// `seed`. It brings its own RNG and clock, so the next step is already
+    /// the new run's.
+    pub fn restart(&self, seed: u64, sim: Sim) {
+        let mut running = self.sim.lock().unwrap();
+        *running = sim;
+        self.seed.store(seed, Ordering::Relaxed);
+        log::info!("restarting simulation with seed {seed}");
+    }
+
+    /// Drops a closed overlay's simulation.
+    pub fn forget_overlay(&self, label: &str) {
+        self.overlays.lock().unwrap().remove(label);
+    }
+
+    /// Gives an overlay its own simulation with `params`, ready for the next
+    /// frame.
+    pub fn add_overlay(&self, label: &str, params: SimParams) {
+        self.overlays
+            .lock()
+            .unwrap()
+            .insert(label.to_string(), OverlaySim::new(params));
+    }
+
+    /// The params of an overlay's own simulation, if it has one.
+    pub fn overlay_params(&self, label: &str) -> Option<SimParams> {
+        self.overlays.lock().unwrap().get(label).map(|o| o.params)
+    }
+}
+
+/// Spawns a supervisor that waits for the frontend, then runs the tick loop
+/// on its own thread, restarting it with backoff if it panics.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        ready::wait(&app);
+        let mut restarts = 0;
+        loop {
+            let handle = app.clone();
+            let result = thread::Builder::new()
+                .name(crash::SIM_THREAD.into())
+                .spawn(move || run(&handle))
+                .map(|thread| thread.join());
+            match result {
+                Ok(Ok(())) => return,
+                Ok(Err(_)) if restarts < MAX_RESTARTS => {
+                    let backoff = RESTART_BACKOFF * 2u32.pow(restarts);
+                    restarts += 1;
+                    log::warn!(
+                        "simulation crashed, restarting in {backoff:?} ({restarts}/{MAX_RESTARTS})"
+                    );
+                    thread::sleep(backoff);
+                    // The panic may have happened with these locked.
+                    let state = app.state::<SimState>();
+                    state.sim.clear_poison();
+                    state.overlays.clear_poison();
+                    state.params.clear_poison();
+                    state.timed_run.clear_poison();
+                    state.viewport.clear_poison();
+                    state.timestep_ms.clear_poison();
+                    state.collision_behavior.clear_poison();
+                    state.overlay_mode.clear_poison();
+                    app.state::<SettingsState>().0.clear_poison();
+                }
+                Ok(Err(_)) => {
+                    error::report(
+                        &app,
+                        "simulation stopped",
+                        AppError::Platform(format!("crashed {MAX_RESTARTS} times, giving up")),
+                    );
+                    crash::notify_crash(&app);
+                    return;
+                }
+                Err(e) => {
+                    error::report(
+                        &app,
+                        "failed to start simulation thread",
+                        AppError::Platform(e.to_string()),
+                    );
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Advances the simulation in fixed timesteps and sends frames to every
+/// overlay window as `sim-tick` at the tick rate.
+fn run(app: &AppHandle) {
+    let state = app.state::<SimState>();
+    log::info!("simulation seed: {}", state.seed.load(Ordering::Relaxed));
+    let mut next_tick = Instant::now();
+    let mut jammed = false;
+    let mut frames = FrameEmitter::new();
+    let mut overlay_frames = HashMap::new();
+    let mut cap_reported = None;
+    let mut metrics = Recorder::new();
+    let mut idle_monitor = IdleMonitor::new();
+    if idle_monitor.is_none() {
+        log::warn!("idle detection is unavailable, the simulation won't pause when idle");
+    }
+    let mut idle_checked = Instant::now();
+    let mut idle = false;
+
+    loop {
+        let started = Instant::now();
+
+        let mut targets = overlay::labels(app);
+        let visible = targets.iter().any(|label| {
+            app.get_webview_window(label)
+                .and_then(|w| w.is_visible().ok())
+                .unwrap_or(false)
+        });
+        // "main" comes first; in independent mode the rest get their own frames.
+        let overlays = if *state.overlay_mode.lock().unwrap() == OverlayMode::Independent {
+            targets.split_off(1)
+        } else {
+            Vec::new()
+        };
+        // Checked once a second; while idle the loop already runs at 1 Hz.
+        if let Some(monitor) = &mut idle_monitor {
+            if idle || idle_checked.elapsed() >= IDLE_CHECK_INTERVAL {
+                idle_checked = Instant::now();
+                let timeout = app
+                    .state::<SettingsState>()
+                    .0
+                    .lock()
+                    .unwrap()
+                    .idle_timeout_secs;
+                let now_idle = timeout > 0
+                    && monitor
+                        .idle_time()
+                        .is_some_and(|t| t >= Duration::from_secs(timeout));
+                if now_idle != idle {
+                    log::info!(
+                        "system {}",
+                        if now_idle {
+                            "idle, pausing"
+                        } else {
+                            "active, resuming"
+                        }
+                    );
+                    idle = now_idle;
+                }
+            }
+        }
+
+        let power_saver = power::power_saver(app);
+        let hz = if !visible || idle {
+            HIDDEN_TICK_RATE
+        } else {
+            let mut hz = state.tick_rate.load(Ordering::Relaxed);
+            if power_saver {
+                hz = hz.min(power::BATTERY_TICK_RATE);
+            }
+            if motion::reduced(app) {
+                hz = hz.min(motion::REDUCED_TICK_RATE);
+            }
+            hz
+        };
+        let dt = 1.0 / hz as f64;
+
+        let mut emit = None;
+        let mut events = StepEvents::default();
+        let mut run_complete = None;
+        let count = if idle {
+            state.sim.lock().unwrap().vehicles().len()
+        } else {
+            let mut sim = state.sim.lock().unwrap();
+            sim.set_max_vehicles(state.vehicle_cap(power_saver));
+            sim.set_collision_behavior(*state.collision_behavior.lock().unwrap());
+            sim.set_bounds(Some(state.viewport.lock().unwrap().visible()));
+            // While paused the last frame keeps being emitted so the window
+            // doesn't go blank, and resuming picks up from the frozen state.
+            // Only the steps asked for with `step` are taken meanwhile.
+            let paused = state.paused.load(Ordering::Relaxed);
+            let manual = if paused {
+                state.manual_steps.swap(0, Ordering::Relaxed)
+            } else {
+                0
+            };
+            if !paused || manual > 0 {
+                let params = netstat::adjust(app, *state.params.lock().unwrap());
+                #[cfg(desktop)]
+                let params = audio::adjust(app, params);
+                let layout = app.state::<RoadState>().0.lock().unwrap().clone();
+                let timestep = *state.timestep_ms.lock().unwrap() / 1000.0;
+                let steps = if paused {
+                    manual
+                } else {
+                    sim.steps_due(dt, timestep)
+                };
+                for _ in 0..steps {
+                    let step = sim.step(timestep, &params, &layout);
+                    let done = {
+                        let mut timed_run = state.timed_run.lock().unwrap();
+                        // Simulated time runs faster with the speed multiplier.
+                        let done = timed_run.as_mut().is_some_and(|run| {
+                            run.record(timestep * params.speed_multiplier, &step)
+                        });
+                        if done {
+                            run_complete = timed_run.take();
+                        }
+                        done
+                    };
+                    events.extend(step);
+                    if done {
+                        // Stops on the exact step, so the pause lands on time.
+                        state.paused.store(true, Ordering::Relaxed);
+                        break;
+                    }
+                }
+            }
+            recording::capture(app, &sim);
+            emit = frames.emit(app, &targets, hz, &sim);
+            sim.vehicles().len()
+        };
+        if let Some(run) = run_complete {
+            finish_run(app, run, count);
+        }
+        if !idle {
+            step_overlays(app, &overlays, dt, hz, power_saver, &mut overlay_frames);
+        }
+        collision::report(app, &events.collisions);
+        if events.capped
+            && cap_reported.is_none_or(|at: Instant| at.elapsed() >= CAP_EVENT_INTERVAL)
+        {
+            cap_reported = Some(Instant::now());
+            if let Err(e) = app.emit("vehicle-cap-reached", count) {
+                log::error!("failed to emit vehicle-cap-reached: {e}");
+            }
+        }
+        if events.burst_ended {
+            if let Err(e) = app.emit("burst-ended", ()) {
+                log::error!("failed to emit burst-ended: {e}");
+            }
+        }
+        stats::record(app, events.spawned, events.collisions.len() as u64);
+        if !jammed && count > JAM_THRESHOLD {
+            let body = format!("{count} vehicles are on the road");
+            if let Err(e) = notify::send(app, "traffic-jam", "Traffic jam", &body) {
+                log::error!("failed to send notification: {e}");
+            }
+        }
+        jammed = count > JAM_THRESHOLD;
+        #[cfg(desktop)]
+        tray_load::update(app, count, state.vehicle_cap(power_saver));
+
+        metrics.tick(app, started, emit, count);
+
+        next_tick += Duration::from_secs_f64(dt);
+        let now = Instant::now();
+        if next_tick > now {
+            thread::sleep(next_tick - now);
+        } else {
+            // Fell behind (e.g. the machine slept); don't try to catch up.
+            next_tick = now;
+        }
+    }
+}
+
+/// Steps and emits the independent simulations of the overlays in `labels`.
+/// Only the main simulation reports collisions and counts towards the stats.
+fn step_overlays(
+    app: &AppHandle,
+    labels: &[String],
+    dt: f64,
+    hz: u32,
+    power_saver: bool,
+    frames: &mut HashMap<String, FrameEmitter>,
+) {
+    frames.retain(|label, _| labels.contains(label));
+    if labels.is_empty() {
+        return;
+    }
+    let state = app.state::<SimState>();
+    let main_params = *state.params.lock().unwrap();
+    let behavior = *state.collision_behavior.lock().unwrap();
+    let timestep = *state.timestep_ms.lock().unwrap() / 1000.0;
+    let paused = state.paused.load(Ordering::Relaxed);
+    let bounds = state.viewport.lock().unwrap().visible();
+    let mut overlays = state.overlays.lock().unwrap();
+    let layout = app.state::<RoadState>().0.lock().unwrap().clone();
+    for label in labels {
+        let overlay = overlays
+            .entry(label.clone())
+            .or_insert_with(|| OverlaySim::new(main_params));
+        overlay.sim.set_max_vehicles(state.vehicle_cap(power_saver));
+        overlay.sim.set_collision_behavior(behavior);
+        overlay.sim.set_bounds(Some(bounds));
+        if !paused {
+            let params = netstat::adjust(app, overlay.params);
+            #[cfg(desktop)]
+            let params = audio::adjust(app, params);
+            for _ in 0..overlay.sim.steps_due(dt, timestep) {
+                overlay.sim.step(timestep, &params, &layout);
+            }
+        }
+        frames
+            .entry(label.clone())
+            .or_insert_with(FrameEmitter::new)
+            .emit(app, std::slice::from_ref(label), hz, &overlay.sim);
+    }
+}
+
+/// Copies the simulation settings into the live state.
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    let hz = settings.tick_rate.clamp(MIN_TICK_RATE, MAX_TICK_RATE);
+    let state = app.state::<SimState>();
+    state.tick_rate.store(hz, Ordering::Relaxed);
+    state.max_vehicles.store(
+        settings
+            .max_vehicles
+            .clamp(*MAX_VEHICLES_RANGE.start(), *MAX_VEHICLES_RANGE.end()),
+        Ordering::Relaxed,
+    );
+    *state.timestep_ms.lock().unwrap() = if settings.timestep_ms.is_finite() {
+        settings
+            .timestep_ms
+            .clamp(*TIMESTEP_MS_RANGE.start(), *TIMESTEP_MS_RANGE.end())
+    } else {
+        DEFAULT_TIMESTEP_MS
+    };
+    *state.collision_behavior.lock().unwrap() = settings.collision_behavior;
+    *state.overlay_mode.lock().unwrap() = settings.overlay_mode;
+    // Shared overlays mirror "main" from the next frame on.
+    if settings.overlay_mode == OverlayMode::Shared {
+        state.overlays.lock().unwrap().clear();
+    }
+    match settings.sim_params.validate() {
+        Ok(()) => *state.params.lock().unwrap() = settings.sim_params.clamped(),
+        Err(e) => log::warn!("ignoring saved simulation params: {e}"),
+    }
+    if let Some(seed) = settings.seed {
+        if state.seed.load(Ordering::Relaxed) != seed {
+            state.restart(seed, Sim::new(seed));
+        }
+    }
+}
+
+/// Validates and applies new simulation params, taking effect on the next
+/// tick, and tells the UI about them.
+pub fn set_params(app: &AppHandle, params: SimParams) -> Result<(), AppError> {
+    params.validate().map_err(AppError::InvalidParam)?;
+    let params = params.clamped();
+    *app.state::<SimState>().params.lock().unwrap() = params;
+    if let Err(e) = app.emit("sim-params", motion::adjust(app, params)) {
+        log::error!("failed to emit sim-params: {e}");
+    }
+    settings::update(app, |s| s.sim_params = params).map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub fn set_sim_params(
+    app: AppHandle,
+    speed_multiplier: f64,
+    spawn_rate: f64,
+) -> Result<(), AppError> {
+    let current = *app.state::<SimState>().params.lock().unwrap();
+    set_params(
+        &app,
+        SimParams {
+            speed_multiplier,
+            spawn_rate,
+            ..current
+        },
+    )
+}
+
+/// Sets the params of a spawned overlay's own simulation, which it runs in
+/// independent mode. They're dropped when overlays go back to shared mode.
+/// For "main" this is the same as `set_sim_params`.
+#[tauri::command]
+pub fn set_overlay_params(
+    app: AppHandle,
+    label: String,
+    params: SimParams,
+) -> Result<(), AppError> {
+    if label == "main" {
+        return set_params(&app, params);
+    }
+    if !overlay::labels(&app).contains(&label) {
+        return Err(AppError::InvalidParam(format!(
+            "no overlay named {label:?}"
+        )));
+    }
+    params.validate().map_err(AppError::InvalidParam)?;
+    let params = params.clamped();
+    app.state::<SimState>()
+        .overlays
+        .lock()
+        .unwrap()
+        .entry(label)
+        .or_insert_with(|| OverlaySim::new(params))
+        .params = params;
+    overlay::save_open(&app)
+}
+
+/// Runs spawned overlays in lockstep with "main" or each on its own.
+#[tauri::command]
+pub fn set_overlay_mode(app: AppHandle, mode: OverlayMode) -> Result<(), AppError> {
+    settings::update(&app, |s| s.overlay_mode = mode).map_err(AppError::Io)?;
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    load_settings(&app, &settings);
+    Ok(())
+}
+
+/// Turns vehicle trails on or off and sets how many frames they last,
+/// clamped to [`TRAIL_LENGTH_RANGE`]. Emits `sim-params` so the canvas
+/// updates right away.
+#[tauri::command]
+pub fn set_trails(app: AppHandle, enabled: bool, length: u32) -> Result<(), AppError> {
+    let current = *app.state::<SimState>().params.lock().unwrap();
+    set_params(
+        &app,
+        SimParams {
+            trail_enabled: enabled,
+            trail_length: length,
+            ..current
+        },
+    )
+}
+
+/// Spawns `count` extra vehicles spread over `over_ms`, on top of the steady
+/// spawn rate, and emits `burst-started`. Overlapping bursts are merged.
+/// `burst-ended` follows once the last one is on the road.
+#[tauri::command]
+pub fn spawn_burst(app: AppHandle, count: u32, over_ms: u64) -> Result<(), AppError> {
+    if count == 0 {
+        return Err(AppError::InvalidParam(
+            "burst count must be positive".into(),
+        ));
+    }
+    let count = count.min(BURST_MAX_VEHICLES as u32);
+    let over = Duration::from_millis(over_ms).min(MAX_BURST_DURATION);
+    let burst = app
+        .state::<SimState>()
+        .sim
+        .lock()
+        .unwrap()
+        .add_burst(count, over.as_secs_f64());
+    log::info!("spawning a burst of {count} over {over:?}");
+    if let Err(e) = app.emit("burst-started", burst) {
+        log::error!("failed to emit burst-started: {e}");
+    }
+    Ok(())
+}
+
+/// Announces the end of a `run_for`, once the tick loop has paused.
+fn finish_run(app: &AppHandle, run: TimedRun, vehicles: usize) {
+    let summary = RunSummary {
+        sim_secs: run.secs,
+        vehicles_spawned: run.spawned,
+        collisions: run.collisions,
+        vehicles,
+    };
+    log::info!("timed run of {}s complete: {summary:?}", run.secs);
+    set_paused_state(app, true);
+    if let Err(e) = app.emit("run-complete", summary) {
+        log::error!("failed to emit run-complete: {e}");
+    }
+}
+
+/// Freezes or resumes the simulation and updates the tray checkmark.
+/// Resuming cancels a pending `run_for` and emits `run-cancelled`.
+pub fn set_paused_state(app: &AppHandle, paused: bool) {
+    let state = app.state::<SimState>();
+    state.paused.store(paused, Ordering::Relaxed);
+    if !paused {
+        // Left over from stepping; they'd otherwise run at the next pause.
+        state.manual_steps.store(0, Ordering::Relaxed);
+    }
+    if !paused && state.timed_run.lock().unwrap().take().is_some() {
+        log::info!("timed run cancelled");
+        if let Err(e) = app.emit("run-cancelled", ()) {
+            log::error!("failed to emit run-cancelled: {e}");
+        }
+    }
+    metrics::reset(app);
+    #[cfg(desktop)]
+    tray::sync(app);
+    if let Err(e) = app.emit("sim-paused", paused) {
+        log::error!("failed to emit sim-paused: {e}");
+    }
+}
+
+#[tauri::command]
+pub fn set_paused(app: AppHandle, paused: bool) {
+    set_paused_state(&app, paused);
+}
+
+/// Advances a paused simulation by `n` physics steps. The tick loop takes
+/// them on its next frame and emits the result as `sim-tick`; the simulation
+/// stays paused.
+#[tauri::command]
+pub fn step(app: AppHandle, n: u32) -> Result<(), AppError> {
+    let state = app.state::<SimState>();
+    if !state.paused.load(Ordering::Relaxed) {
+        return Err(AppError::InvalidParam(
+            "pause the simulation before stepping it".into(),
+        ));
+    }
+    // Taken in one go, so as many as a frame may take at most.
+    let queued = state
+        .manual_steps
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending| {
+            pending
+                .checked_add(n)
+                .filter(|&total| total <= MAX_STEPS_PER_FRAME)
+        });
+    if queued.is_err() {
+        return Err(AppError::InvalidParam(format!(
+            "at most {MAX_STEPS_PER_FRAME} steps can be queued at once"
+        )));
+    }
+    Ok(())
+}
+
+/// Pushes every vehicle by `(x, y)` pixels per second, capped at
+/// [`MAX_GLOBAL_FORCE`]. Saved with the other params and in presets.
+#[tauri::command]
+pub fn set_global_force(app: AppHandle, x: f64, y: f64) -> Result<(), AppError> {
+    let current = *app.state::<SimState>().params.lock().unwrap();
+    set_params(
+        &app,
+        SimParams {
+            global_force: Vec2 { x, y },
+            ..current
+        },
+    )
+}
+
+/// Sets what happens to vehicles that drive out of view: they wrap round to
+/// the opposite side, bounce back or despawn. The view is re-read every
+/// frame, so it follows pans and zooms. Saved with the other params and in
+/// presets.
+#[tauri::command]
+pub fn set_boundary_behavior(app: AppHandle, behavior: BoundaryBehavior) -> Result<(), AppError> {
+    let current = *app.state::<SimState>().params.lock().unwrap();
+    set_params(
+        &app,
+        SimParams {
+            boundary_behavior: behavior,
+            ..current
+        },
+    )
+}
+
+/// Runs the simulation for `sim_seconds` of simulated time, resuming it if
+/// paused, then pauses and emits `run-complete`. At a higher speed
+/// multiplier that takes less wall-clock time. Replaces any pending run.
+#[tauri::command]
+pub fn run_for(app: AppHandle, sim_seconds: f64) -> Result<(), AppError> {
+    if !(f64::MIN_POSITIVE..=MAX_RUN_SECS).contains(&sim_seconds) {
+        return Err(AppError::InvalidParam(format!(
+            "run length must be positive and at most {MAX_RUN_SECS}s, got {sim_seconds}"
+        )));
+    }
+    let state = app.state::<SimState>();
+    // Dropped first so that resuming doesn't count as cancelling it.
+    *state.timed_run.lock().unwrap() = None;
+    if state.paused.load(Ordering::Relaxed) {
+        set_paused_state(&app, false);
+    }
+    *state.timed_run.lock().unwrap() = Some(TimedRun::new(sim_seconds));
+    log::info!("running for {sim_seconds}s of simulated time");
+    Ok(())
+}
+
+/// Restarts the simulation from `seed`. The same seed and params replay the
+/// same traffic, provided the timestep doesn't change in between.
+pub fn reseed(app: &AppHandle, seed: u64) -> Result<(), AppError> {
+    app.state::<SimState>().restart(seed, Sim::new(seed));
+    settings::update(app, |s| s.seed = Some(seed)).map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub fn set_seed(app: AppHandle, seed: u64) -> Result<(), AppError> {
+    reseed(&app, seed)
+}
+
+#[tauri::command]
+pub fn get_seed(state: State<SimState>) -> u64 {
+    state.seed.load(Ordering::Relaxed)
+}
+
+#[tauri::command]
+pub fn set_tick_rate(app: AppHandle, hz: u32) -> Result<(), AppError> {
+    let hz = hz.clamp(MIN_TICK_RATE, MAX_TICK_RATE);
+    app.state::<SimState>()
+        .tick_rate
+        .store(hz, Ordering::Relaxed);
+    settings::update(&app, |s| s.tick_rate = hz).map_err(AppError::Io)
+}
+
+/// Caps how many vehicles are on the road at once. Spawning waits while the
+/// road is full; lowering the cap lets the extra vehicles drive off rather
+/// than removing them.
+#[tauri::command]
+pub fn set_max_vehicles(app: AppHandle, n: u32) -> Result<(), AppError> {
+    if !MAX_VEHICLES_RANGE.contains(&n) {
+        return Err(AppError::InvalidParam(format!(
+            "max vehicles must be between {} and {}, got {n}",
+            MAX_VEHICLES_RANGE.start(),
+            MAX_VEHICLES_RANGE.end()
+        )));
+    }
+    app.state::<SimState>()
+        .max_vehicles
+        .store(n, Ordering::Relaxed);
+    settings::update(&app, |s| s.max_vehicles = n).map_err(AppError::Io)
+}
+
+/// Sets how much simulated time each physics step covers, independently of
+/// how often frames are emitted. Smaller steps are smoother and more precise.
+#[tauri::command]
+pub fn set_timestep(app: AppHandle, dt_ms: f64) -> Result<(), AppError> {
+    if !TIMESTEP_MS_RANGE.contains(&dt_ms) {
+        return Err(AppError::InvalidParam(format!(
+            "timestep must be between {} and {} ms, got {dt_ms}",
+            TIMESTEP_MS_RANGE.start(),
+            TIMESTEP_MS_RANGE.end()
+        )));
+    }
+    *app.state::<SimState>().timestep_ms.lock().unwrap() = dt_ms;
+    settings::update(&app, |s| s.timestep_ms = dt_ms).map_err(AppError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::road::RoadNetwork;
+
+    /// Distance one vehicle covers in `secs` of frames emitted at `hz`.
+    fn distance(hz: u32, secs: u32, timestep: f64) -> f64 {
+        let layout = Layout::new(RoadNetwork::default()).unwrap();
+        let mut sim = Sim::new(1);
+        sim.spawn(&layout);
+        let params = SimParams {
+            spawn_rate: 0.0,
+            ..SimParams::default()
+        };
+        let mut clock = StepClock::default();
+        for _ in 0..hz * secs {
+            for _ in 0..clock.advance(1.0 / hz as f64, timestep) {
+                sim.step(timestep, &params, &layout);
+            }
+        }
+        let vehicle = &sim.vehicles()[0];
+        vehicle.progress / vehicle.speed
+    }
+
+    #[test]
+    fn restored_vehicles_must_fit_the_layout() {
+        let layout = Layout::new(RoadNetwork::default()).unwrap();
+        let mut sim = Sim::new(1);
+        sim.spawn(&layout);
+        let vehicle = sim.vehicles()[0].clone();
+        assert_eq!(vehicle.validate(&layout), Ok(()));
+
+        let broken: [fn(&mut Vehicle); 6] = [
+            |v| v.speed = f64::INFINITY,
+            |v| v.speed = f64::NAN,
+            |v| v.progress = 1e300,
+            |v| v.x = f64::NAN,
+            |v| v.lane = u8::MAX,
+            |v| v.edge = usize::MAX,
+        ];
+        for (i, breaking) in broken.into_iter().enumerate() {
+            let mut vehicle = vehicle.clone();
+            breaking(&mut vehicle);
+            assert!(vehicle.validate(&layout).is_err(), "case {i}");
+        }
+    }
+
+    #[test]
+    fn same_seed_replays_the_same_traffic() {
+        let layout = Layout::new(RoadNetwork::default()).unwrap();
+        let run = |seed| {
+            let mut sim = Sim::new(seed);
+            for _ in 0..300 {
+                sim.step(0.05, &SimParams::default(), &layout);
+            }
+            format!("{:?}", sim.vehicles())
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn distance_is_independent_of_the_emit_rate() {
+        let expected = distance(30, 5, 0.01);
+        for hz in [1, 7, 60, 120] {
+            assert!((distance(hz, 5, 0.01) - expected).abs() < 1e-9, "{hz} Hz");
+        }
+        // Every step moved the vehicle, so the whole 5 s was simulated.
+        assert!((expected - 5.0).abs() < 1e-9);
+    }
+
+    fn quiet_sim() -> (Sim, Layout, SimParams) {
+        let params = SimParams {
+            spawn_rate: 0.0,
+            ..SimParams::default()
+        };
+        (
+            Sim::new(1),
+            Layout::new(RoadNetwork::default()).unwrap(),
+            params,
+        )
+    }
+
+    #[test]
+    fn bursts_spread_their_vehicles_and_end() {
+        let (mut sim, layout, params) = quiet_sim();
+        sim.add_burst(10, 1.0);
+        let mut spawned = 0;
+        let mut ended = 0;
+        for _ in 0..50 {
+            let events = sim.step(0.01, &params, &layout);
+            spawned += events.spawned;
+            ended += events.burst_ended as u32;
+        }
+        // Half the time, half the vehicles.
+        assert!((4..=6).contains(&spawned), "{spawned}");
+        for _ in 0..60 {
+            let events = sim.step(0.01, &params, &layout);
+            spawned += events.spawned;
+            ended += events.burst_ended as u32;
+        }
+        assert_eq!(spawned, 10);
+        assert_eq!(ended, 1);
+    }
+
+    #[test]
+    fn overlapping_bursts_merge() {
+        let (mut sim, _, _) = quiet_sim();
+        sim.add_burst(10, 1.0);
+        let burst = sim.add_burst(5, 0.5);
+        assert_eq!(burst.remaining, 15);
+        assert_eq!(burst.secs_left, 1.0);
+    }
+
+    #[test]
+    fn bursts_respect_the_vehicle_cap() {
+        let (mut sim, layout, params) = quiet_sim();
+        sim.set_max_vehicles(5);
+        sim.add_burst(20, 0.0);
+        let events = sim.step(0.01, &params, &layout);
+        assert_eq!(events.spawned, 5);
+        assert!(events.burst_ended);
+    }
+
+    #[test]
+    fn spawning_never_passes_the_cap() {
+        let (mut sim, layout, _) = quiet_sim();
+        let params = SimParams {
+            spawn_rate: 100.0,
+            ..SimParams::default()
+        };
+        sim.set_max_vehicles(10);
+        let mut capped = false;
+        for _ in 0..200 {
+            capped |= sim.step(0.01, &params, &layout).capped;
+            assert!(sim.vehicles().len() <= 10);
+        }
+        assert!(capped);
+    }
+
+    #[test]
+    fn lowering_the_cap_keeps_the_vehicles() {
+        let (mut sim, layout, _) = quiet_sim();
+        let params = SimParams {
+            spawn_rate: 100.0,
+            ..SimParams::default()
+        };
+        for _ in 0..20 {
+            sim.step(0.01, &params, &layout);
+        }
+        let count = sim.vehicles().len();
+        sim.set_max_vehicles(5);
+        let events = sim.step(0.01, &params, &layout);
+        assert_eq!(events.spawned, 0);
+        assert!(sim.vehicles().len() > 5 && sim.vehicles().len() <= count);
+    }
+
+    /// A stopped vehicle under a force along its road, or against it.
+    fn pushed(along: f64) -> ((f64, f64), (f64, f64)) {
+        let layout = Layout::new(RoadNetwork::default()).unwrap();
+        let mut sim = Sim::new(1);
+        sim.spawn(&layout);
+        let vehicle = &mut sim.vehicles[0];
+        let heading = (vehicle.vx / vehicle.speed, vehicle.vy / vehicle.speed);
+        vehicle.speed = 0.0;
+        let start = (vehicle.x, vehicle.y);
+        let params = SimParams {
+            spawn_rate: 0.0,
+            global_force: Vec2 {
+                x: heading.0 * along,
+                y: heading.1 * along,
+            },
+            ..SimParams::default()
+        };
+        for _ in 0..10 {
+            sim.step(1.0 / 30.0, &params, &layout);
+        }
+        let end = (sim.vehicles[0].x, sim.vehicles[0].y);
+        ((end.0 - start.0, end.1 - start.1), heading)
+    }
+
+    #[test]
+    fn global_force_moves_stopped_vehicles_its_way() {
+        let ((dx, dy), heading) = pushed(30.0);
+        // 30 px/s for a third of a second.
+        assert!((dx * heading.0 + dy * heading.1 - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn global_force_never_reverses_vehicles() {
+        let ((dx, dy), _) = pushed(-30.0);
+        assert_eq!((dx, dy), (0.0, 0.0));
+    }
+
+    #[test]
+    fn global_force_is_capped() {
+        let params = SimParams {
+            global_force: Vec2 { x: 300.0, y: 400.0 },
+            ..SimParams::default()
+        }
+        .clamped();
+        assert!((params.global_force.length() - MAX_GLOBAL_FORCE).abs() < 1e-9);
+    }
+
+    /// The intersection's edges in from the west and out to the east.
+    const WEST_IN: usize = 4;
+    const EAST_OUT: usize = 7;
+
+    /// A vehicle heading out east, about to pass the right of the scene,
+    /// after one step under `behavior`.
+    fn at_the_edge(behavior: BoundaryBehavior) -> (Layout, Option<Vehicle>) {
+        let layout = Layout::new(RoadNetwork::default()).unwrap();
+        let mut sim = Sim::new(1);
+        sim.set_bounds(Some(Viewport::default().visible()));
+        sim.spawn(&layout);
+        let vehicle = &mut sim.vehicles[0];
+        vehicle.edge = EAST_OUT;
+        vehicle.lane = 0;
+        vehicle.progress = SCENE_W - 1.0 - layout.place(EAST_OUT, 0, 0.0).x;
+        let params = SimParams {
+            spawn_rate: 0.0,
+            boundary_behavior: behavior,
+            ..SimParams::default()
+        };
+        sim.step(0.1, &params, &layout);
+        (layout, sim.vehicles.first().cloned())
+    }
+
+    #[test]
+    fn wrapped_vehicles_come_back_on_the_other_side() {
+        let (_, vehicle) = at_the_edge(BoundaryBehavior::Wrap);
+        let vehicle = vehicle.unwrap();
+        assert_eq!((vehicle.edge, vehicle.direction), (WEST_IN, Direction::E));
+        assert!((vehicle.x - 0.0).abs() < 1e-9, "{}", vehicle.x);
+        assert!(vehicle.vx > 0.0);
+    }
+
+    #[test]
+    fn bounced_vehicles_turn_round() {
+        let (layout, vehicle) = at_the_edge(BoundaryBehavior::Bounce);
+        let vehicle = vehicle.unwrap();
+        assert_eq!(Some(vehicle.edge), layout.reverse(EAST_OUT));
+        assert_eq!(vehicle.direction, Direction::W);
+        assert!(vehicle.vx < 0.0);
+    }
+
+    #[test]
+    fn despawned_vehicles_leave() {
+        let (_, vehicle) = at_the_edge(BoundaryBehavior::Despawn);
+        assert!(vehicle.is_none());
+    }
+
+    #[test]
+    fn timed_runs_count_simulated_time() {
+        let mut run = TimedRun::new(1.0);
+        let events = StepEvents {
+            spawned: 2,
+            ..StepEvents::default()
+        };
+        // Ten 50 ms steps at double speed make a simulated second.
+        for _ in 0..9 {
+            assert!(!run.record(0.05 * 2.0, &events));
+        }
+        assert!(run.record(0.05 * 2.0, &events));
+        assert_eq!(run.spawned, 20);
+    }
+
+    #[test]
+    fn leftover_time_carries_over() {
+        let mut clock = StepClock::default();
+        assert_eq!(clock.advance(0.015, 0.01), 1);
+        assert_eq!(clock.advance(0.015, 0.01), 2);
+        assert_eq!(clock.advance(0.005, 0.01), 0);
+    }
+
+    #[test]
+    fn a_stalled_host_is_capped() {
+        let mut clock = StepClock::default();
+        assert_eq!(clock.advance(60.0, 0.001), MAX_STEPS_PER_FRAME);
+        assert_eq!(clock.advance(0.0, 0.001), 0);
+    }
+}
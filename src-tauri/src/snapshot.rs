@@ -0,0 +1,120 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    artifacts::ArtifactKind,
+    error::AppError,
+    road::{self, Layout, RoadNetwork, RoadState},
+    sim::{self, Sim, SimParams, SimState, Vehicle},
+};
+
+/// Bumped whenever the snapshot format changes incompatibly.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// A point-in-time copy of the simulation, for sharing and debugging.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub seed: u64,
+    pub params: SimParams,
+    pub tick: u64,
+    /// The roads `vehicles` are on.
+    pub network: RoadNetwork,
+    pub vehicles: Vec<Vehicle>,
+}
+
+impl Snapshot {
+    pub fn capture(state: &SimState, road: &RoadState) -> Self {
+        let sim = state.sim.lock().unwrap();
+        Self {
+            version: SNAPSHOT_VERSION,
+            seed: state.seed.load(Ordering::Relaxed),
+            params: *state.params.lock().unwrap(),
+            tick: sim.tick(),
+            network: road.0.lock().unwrap().network().clone(),
+            vehicles: sim.vehicles().to_vec(),
+        }
+    }
+}
+
+/// A timestamped file name in the user's downloads folder, named so that
+/// `list_artifacts` finds it.
+pub fn download_path(app: &AppHandle, kind: ArtifactKind) -> Result<PathBuf, String> {
+    let dir = app.path().download_dir().map_err(|e| e.to_string())?;
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    Ok(dir.join(format!("{}-{secs}.{}", kind.prefix(), kind.extension())))
+}
+
+/// Writes a snapshot of the running simulation and returns where it went.
+pub fn export(app: &AppHandle, path: Option<PathBuf>) -> Result<PathBuf, AppError> {
+    let path = match path {
+        Some(path) => path,
+        None => download_path(app, ArtifactKind::Snapshot).map_err(AppError::Io)?,
+    };
+    let snapshot = Snapshot::capture(&app.state::<SimState>(), &app.state::<RoadState>());
+    let contents =
+        serde_json::to_string_pretty(&snapshot).map_err(|e| AppError::Io(e.to_string()))?;
+    fs::write(&path, contents)
+        .map_err(|e| AppError::Io(format!("failed to write {}: {e}", path.display())))?;
+    log::info!("exported snapshot to {}", path.display());
+    Ok(path)
+}
+
+/// Replaces the running simulation with one read from a snapshot file.
+pub fn import(app: &AppHandle, path: &Path) -> Result<(), AppError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| AppError::Io(format!("failed to read {}: {e}", path.display())))?;
+    // Check the version before the rest so an incompatible file gets a clear
+    // error instead of a confusing field mismatch.
+    let value: serde_json::Value = serde_json::from_str(&contents).map_err(|e| {
+        AppError::InvalidParam(format!("{} is not valid JSON: {e}", path.display()))
+    })?;
+    let version = value.get("version").and_then(|v| v.as_u64());
+    if version != Some(SNAPSHOT_VERSION as u64) {
+        let found = version.map_or("none".to_string(), |v| v.to_string());
+        return Err(AppError::InvalidParam(format!(
+            "unsupported snapshot version {found} (expected {SNAPSHOT_VERSION})"
+        )));
+    }
+    let snapshot: Snapshot = serde_json::from_value(value).map_err(|e| {
+        AppError::InvalidParam(format!("{} is not a valid snapshot: {e}", path.display()))
+    })?;
+
+    // Everything is checked before anything is replaced.
+    let layout = Layout::new(snapshot.network.clone())
+        .map_err(|e| AppError::InvalidParam(format!("invalid road network: {e}")))?;
+    snapshot.params.validate().map_err(AppError::InvalidParam)?;
+    for vehicle in &snapshot.vehicles {
+        vehicle.validate(&layout).map_err(AppError::InvalidParam)?;
+    }
+
+    road::set(app, snapshot.network)?;
+    sim::set_params(app, snapshot.params)?;
+    app.state::<SimState>().restart(
+        snapshot.seed,
+        Sim::restore(snapshot.vehicles, snapshot.tick, snapshot.seed),
+    );
+    log::info!("imported snapshot from {}", path.display());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn import_state(app: AppHandle, path: String) -> Result<(), AppError> {
+    import(&app, Path::new(&path))
+}
+
+#[tauri::command]
+pub fn export_state(app: AppHandle, path: Option<String>) -> Result<String, AppError> {
+    let path = export(&app, path.map(PathBuf::from))?;
+    Ok(path.display().to_string())
+}
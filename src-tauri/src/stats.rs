@@ -0,0 +1,126 @@
+//! Lifetime totals that survive restarts, kept in `stats.json` next to the
+//! settings.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::{error::AppError, settings};
+
+const STATS_FILE: &str = "stats.json";
+/// The tick loop writes the totals at most this often; the rest waits for
+/// the next write or for quitting.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub vehicles_spawned: u64,
+    pub collisions: u64,
+    /// Seconds the app has been running, over every session.
+    pub uptime_secs: f64,
+}
+
+pub struct StatsState {
+    stats: Mutex<Stats>,
+    /// Uptime up to this instant is already in `stats`.
+    counted: Mutex<Instant>,
+    flushed: Mutex<Instant>,
+}
+
+impl StatsState {
+    fn new(stats: Stats) -> Self {
+        let now = Instant::now();
+        Self {
+            stats: Mutex::new(stats),
+            counted: Mutex::new(now),
+            flushed: Mutex::new(now),
+        }
+    }
+
+    /// The totals, with uptime counted up to now.
+    pub fn current(&self) -> Stats {
+        let mut stats = self.stats.lock().unwrap();
+        let mut counted = self.counted.lock().unwrap();
+        let now = Instant::now();
+        stats.uptime_secs += (now - *counted).as_secs_f64();
+        *counted = now;
+        *stats
+    }
+}
+
+fn stats_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(settings::config_dir(app)?.join(STATS_FILE))
+}
+
+fn save(app: &AppHandle, stats: &Stats) -> Result<(), String> {
+    let path = stats_path(app)?;
+    let contents = serde_json::to_string_pretty(stats).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, contents).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, &path).map_err(|e| e.to_string())
+}
+
+/// Reads the saved totals, starting from zero if there are none or they
+/// can't be read.
+pub fn init(app: &AppHandle) {
+    let stats = stats_path(app)
+        .and_then(|path| fs::read_to_string(path).map_err(|e| e.to_string()))
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            log::info!("starting lifetime stats from zero: {e}");
+            Stats::default()
+        });
+    app.manage(StatsState::new(stats));
+}
+
+/// Adds one tick's worth of activity, writing the totals if the last write
+/// was a minute or more ago.
+pub fn record(app: &AppHandle, spawned: u64, collisions: u64) {
+    let state = app.state::<StatsState>();
+    if spawned > 0 || collisions > 0 {
+        let mut stats = state.stats.lock().unwrap();
+        stats.vehicles_spawned += spawned;
+        stats.collisions += collisions;
+    }
+    {
+        let mut flushed = state.flushed.lock().unwrap();
+        if flushed.elapsed() < FLUSH_INTERVAL {
+            return;
+        }
+        *flushed = Instant::now();
+    }
+    if let Err(e) = save(app, &state.current()) {
+        log::warn!("failed to save lifetime stats: {e}");
+    }
+}
+
+/// Writes the totals on the way out.
+pub fn flush(app: &AppHandle) {
+    let Some(state) = app.try_state::<StatsState>() else {
+        return;
+    };
+    match save(app, &state.current()) {
+        Ok(()) => log::info!("lifetime stats saved"),
+        Err(e) => log::error!("failed to save lifetime stats on exit: {e}"),
+    }
+}
+
+#[tauri::command]
+pub fn get_stats(state: State<StatsState>) -> Stats {
+    state.current()
+}
+
+#[tauri::command]
+pub fn reset_stats(app: AppHandle) -> Result<(), AppError> {
+    let state = app.state::<StatsState>();
+    *state.stats.lock().unwrap() = Stats::default();
+    *state.counted.lock().unwrap() = Instant::now();
+    save(&app, &Stats::default()).map_err(AppError::Io)
+}
@@ -0,0 +1,190 @@
+//! Opt-in anonymous usage events, batched and posted from a background
+//! thread. Nothing is queued or sent unless the user turned it on and an
+//! endpoint is configured.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+
+#[cfg(desktop)]
+use crate::tray;
+use crate::{
+    error::AppError,
+    settings::{self, Settings, SettingsState},
+};
+
+/// How long events wait to be batched before they're posted.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(300);
+/// A batch this large is posted without waiting for the interval.
+const MAX_BATCH: usize = 50;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const PROMPT: &str = "Share anonymous usage statistics? Only app starts, which kind of preset \
+     is used and crashes are sent, with the app version and OS. You can change this from the tray \
+     menu at any time.";
+
+/// Events carry no identifiers, paths or user-entered text.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    AppStart,
+    /// Only whether it was the built-in preset, never a user's preset name.
+    PresetUsed {
+        builtin: bool,
+    },
+    Crash,
+}
+
+#[derive(Serialize)]
+struct Batch<'a> {
+    app_version: &'static str,
+    os: &'static str,
+    events: &'a [Event],
+}
+
+/// The running sender thread. Dropping it disconnects the channel, which
+/// ends the thread and throws away whatever it had batched.
+struct Worker {
+    endpoint: String,
+    tx: Sender<Event>,
+    live: Arc<AtomicBool>,
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.live.store(false, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+pub struct TelemetryState(Mutex<Option<Worker>>);
+
+/// Starts or stops the sender to match the settings.
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    let endpoint = settings
+        .telemetry_endpoint
+        .as_deref()
+        .filter(|e| !e.trim().is_empty());
+    let state = app.state::<TelemetryState>();
+    let mut worker = state.0.lock().unwrap();
+    let Some(endpoint) = endpoint.filter(|_| settings.telemetry_enabled) else {
+        if worker.take().is_some() {
+            log::info!("telemetry stopped");
+        }
+        return;
+    };
+    if worker.as_ref().is_some_and(|w| w.endpoint == endpoint) {
+        return;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let live = Arc::new(AtomicBool::new(true));
+    let thread_live = live.clone();
+    let thread_endpoint = endpoint.to_string();
+    thread::spawn(move || run(&thread_endpoint, rx, &thread_live));
+    log::info!("telemetry enabled, posting to {endpoint}");
+    *worker = Some(Worker {
+        endpoint: endpoint.to_string(),
+        tx,
+        live,
+    });
+}
+
+/// Queues an event if telemetry is on. Safe to call from the panic hook: it
+/// never blocks on a lock.
+pub fn record(app: &AppHandle, event: Event) {
+    let Some(state) = app.try_state::<TelemetryState>() else {
+        return;
+    };
+    let Ok(worker) = state.0.try_lock() else {
+        return;
+    };
+    if let Some(worker) = worker.as_ref() {
+        let _ = worker.tx.send(event);
+    }
+}
+
+fn run(endpoint: &str, rx: mpsc::Receiver<Event>, live: &AtomicBool) {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(REQUEST_TIMEOUT))
+        .build()
+        .into();
+    let mut batch = Vec::new();
+    let mut deadline = Instant::now() + FLUSH_INTERVAL;
+    loop {
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(event) => {
+                batch.push(event);
+                if batch.len() < MAX_BATCH {
+                    continue;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        deadline = Instant::now() + FLUSH_INTERVAL;
+        // Events still in the channel when telemetry was turned off must not
+        // go out either.
+        if batch.is_empty() || !live.load(Ordering::Relaxed) {
+            continue;
+        }
+        let body = Batch {
+            app_version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            events: &batch,
+        };
+        // Failed batches are dropped rather than retried.
+        match agent.post(endpoint).send_json(&body) {
+            Ok(_) => log::debug!("sent {} telemetry events", batch.len()),
+            Err(e) => log::debug!("failed to send telemetry: {e}"),
+        }
+        batch.clear();
+    }
+}
+
+/// Saves the user's choice and starts or stops the sender right away.
+pub fn set_enabled(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(app, |s| {
+        s.telemetry_enabled = enabled;
+        s.telemetry_prompted = true;
+    })?;
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    load_settings(app, &settings);
+    #[cfg(desktop)]
+    tray::sync(app);
+    Ok(())
+}
+
+/// Asks once whether to share usage statistics. Closing the dialog counts as
+/// declining.
+pub fn prompt(app: &AppHandle) {
+    let app = app.clone();
+    app.dialog()
+        .message(PROMPT)
+        .title("Usage Statistics")
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Share".into(),
+            "Don't Share".into(),
+        ))
+        .show(move |accepted| {
+            if let Err(e) = set_enabled(&app, accepted) {
+                log::error!("failed to save telemetry choice: {e}");
+            }
+        });
+}
+
+#[tauri::command]
+pub fn set_telemetry(app: AppHandle, enabled: bool) -> Result<(), AppError> {
+    set_enabled(&app, enabled).map_err(AppError::Io)
+}
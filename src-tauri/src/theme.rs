@@ -0,0 +1,184 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+    error::AppError,
+    settings::{self, Settings, SettingsState},
+};
+
+pub const DEFAULT_THEME: &str = "default";
+
+/// Colors the canvas paints the crossing with, as CSS hex strings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub road: String,
+    pub lane_markings: String,
+    /// Vehicles cycle through these.
+    pub vehicles: Vec<String>,
+    pub background_tint: String,
+}
+
+/// The theme the canvas is currently painted with.
+pub struct ThemeState(Mutex<Theme>);
+
+impl Default for ThemeState {
+    fn default() -> Self {
+        Self(Mutex::new(default_theme()))
+    }
+}
+
+fn theme(name: &str, road: &str, lane_markings: &str, vehicles: &[&str], tint: &str) -> Theme {
+    Theme {
+        name: name.into(),
+        road: road.into(),
+        lane_markings: lane_markings.into(),
+        vehicles: vehicles.iter().map(|&c| c.into()).collect(),
+        background_tint: tint.into(),
+    }
+}
+
+fn default_theme() -> Theme {
+    theme(
+        DEFAULT_THEME,
+        "#3a3a3c",
+        "#f2f2f2",
+        &["#e63946", "#f1c453", "#457b9d", "#2a9d8f", "#f4f4f4"],
+        "#222222",
+    )
+}
+
+pub fn builtins() -> Vec<Theme> {
+    vec![
+        default_theme(),
+        theme(
+            "light",
+            "#b8b8bc",
+            "#ffffff",
+            &["#d62828", "#f77f00", "#277da1", "#43aa8b", "#4d4d4d"],
+            "#e8e8e8",
+        ),
+        theme(
+            "dark",
+            "#1c1c1e",
+            "#8e8e93",
+            &["#ff453a", "#ffd60a", "#0a84ff", "#30d158", "#bf5af2"],
+            "#000000",
+        ),
+    ]
+}
+
+/// Whether `name` is a built-in theme or the saved custom one.
+pub fn exists(settings: &Settings, name: &str) -> bool {
+    builtins()
+        .iter()
+        .chain(settings.custom_theme.as_ref())
+        .any(|t| t.name == name)
+}
+
+/// The theme `settings` names: a built-in, or the saved custom theme.
+fn resolve(settings: &Settings) -> Theme {
+    let custom = settings.custom_theme.iter().cloned();
+    if let Some(theme) = builtins()
+        .into_iter()
+        .chain(custom)
+        .find(|t| t.name == settings.theme)
+    {
+        return theme;
+    }
+    log::warn!("unknown theme {:?}, using the default", settings.theme);
+    default_theme()
+}
+
+/// Accepts `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa`.
+fn is_color(s: &str) -> bool {
+    s.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+/// Checks every color up front so a bad theme is never half applied.
+fn validate(theme: &Theme) -> Result<(), String> {
+    if theme.name.trim().is_empty() {
+        return Err("theme name is empty".into());
+    }
+    if theme.vehicles.is_empty() {
+        return Err(format!("theme {:?} has no vehicle colors", theme.name));
+    }
+    let fields = [
+        ("road", &theme.road),
+        ("lane_markings", &theme.lane_markings),
+        ("background_tint", &theme.background_tint),
+    ];
+    let vehicles = theme.vehicles.iter().map(|c| ("vehicles", c));
+    for (field, color) in fields.into_iter().chain(vehicles) {
+        if !is_color(color) {
+            return Err(format!(
+                "theme {:?} has an invalid {field} color {color:?}; expected #rgb, #rgba, \
+                 #rrggbb or #rrggbbaa",
+                theme.name
+            ));
+        }
+    }
+    if builtins()
+        .iter()
+        .any(|b| b.name == theme.name && b != theme)
+    {
+        return Err(format!("{:?} is a built-in theme name", theme.name));
+    }
+    Ok(())
+}
+
+/// Makes the theme the settings name live, repainting the canvas if it changed.
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    let theme = resolve(settings);
+    let state = app.state::<ThemeState>();
+    let mut current = state.0.lock().unwrap();
+    if *current == theme {
+        return;
+    }
+    *current = theme.clone();
+    if let Err(e) = app.emit("theme-changed", theme) {
+        log::error!("failed to emit theme-changed: {e}");
+    }
+}
+
+/// Validates, saves and applies `theme`. A theme that isn't built in replaces
+/// the saved custom theme.
+pub fn set(app: &AppHandle, theme: Theme) -> Result<(), AppError> {
+    validate(&theme).map_err(AppError::InvalidParam)?;
+    let builtin = builtins().contains(&theme);
+    settings::update(app, |s| {
+        s.theme = theme.name.clone();
+        if !builtin {
+            s.custom_theme = Some(theme);
+        }
+    })
+    .map_err(AppError::Io)?;
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    load_settings(app, &settings);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_theme(state: State<ThemeState>) -> Theme {
+    state.0.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_theme(app: AppHandle, theme: Theme) -> Result<(), AppError> {
+    set(&app, theme)
+}
+
+/// Built-in theme names followed by the custom theme's, if there is one.
+#[tauri::command]
+pub fn list_themes(state: State<SettingsState>) -> Vec<String> {
+    let settings = state.0.lock().unwrap();
+    builtins()
+        .into_iter()
+        .chain(settings.custom_theme.clone())
+        .map(|t| t.name)
+        .collect()
+}
@@ -0,0 +1,82 @@
+//! Falls back to a small, decorated window where the desktop can't draw
+//! transparent windows, instead of covering the screen in opaque black.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(desktop)]
+use tauri::WebviewWindow;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{
+    notify,
+    settings::{self, SettingsState},
+};
+#[cfg(desktop)]
+use crate::{overlay::AppState, platform, settings::Settings};
+
+/// Logical size of the fallback window.
+#[cfg(desktop)]
+const FALLBACK_SIZE: (f64, f64) = (480.0, 240.0);
+
+/// Whether the main window is in the opaque fallback.
+#[derive(Default)]
+pub struct Transparency(AtomicBool);
+
+/// Switches "main" to the fallback if transparency isn't available, unless
+/// `force_transparent` is set. Runs before the window is first shown.
+#[cfg(desktop)]
+pub fn check(app: &AppHandle, window: &WebviewWindow, settings: &Settings) {
+    if settings.force_transparent || platform::transparency_available(window) {
+        return;
+    }
+    log::warn!("transparent windows aren't supported here, using a small window");
+    app.state::<Transparency>().0.store(true, Ordering::Relaxed);
+    // Clickable and in the taskbar, so it can always be found and moved;
+    // neither is saved.
+    let state = app.state::<AppState>();
+    state.click_through.store(false, Ordering::Relaxed);
+    state.show_in_taskbar.store(true, Ordering::Relaxed);
+    let (width, height) = FALLBACK_SIZE;
+    let result = window
+        .set_decorations(true)
+        .and_then(|()| window.set_size(tauri::LogicalSize::new(width, height)))
+        .and_then(|()| window.center());
+    if let Err(e) = result {
+        log::error!("failed to switch to the fallback window: {e}");
+    }
+}
+
+/// Tells the UI if the fallback is in use and, the first time, explains it
+/// in a notification. Needs the settings to be managed.
+pub fn announce(app: &AppHandle) {
+    if !app.state::<Transparency>().0.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Err(e) = app.emit("transparency-unavailable", ()) {
+        log::error!("failed to emit transparency-unavailable: {e}");
+    }
+    if app
+        .state::<SettingsState>()
+        .0
+        .lock()
+        .unwrap()
+        .transparency_warned
+    {
+        return;
+    }
+    let body = "Your desktop isn't compositing windows, so the overlay runs in a small \
+                window. Set force_transparent if you know transparency works.";
+    if let Err(e) = notify::send(app, "transparency", "Transparency unavailable", body) {
+        log::error!("failed to send notification: {e}");
+    }
+    if let Err(e) = settings::update(app, |s| s.transparency_warned = true) {
+        log::error!("failed to save settings: {e}");
+    }
+}
+
+/// Whether the overlay is in the small opaque fallback, which the UI
+/// shouldn't stretch across the screen.
+#[tauri::command]
+pub fn is_transparency_unavailable(state: State<Transparency>) -> bool {
+    state.0.load(Ordering::Relaxed)
+}
@@ -0,0 +1,930 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use tauri::{
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, Submenu},
+    tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
+    App, AppHandle, CloseRequestApi, Emitter, Manager, Monitor, WebviewWindow, Wry,
+};
+use tauri_plugin_autostart::ManagerExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::{
+    clipboard,
+    error::{self, AppError},
+    foreground, i18n, notify, opacity, overlay,
+    position::{self, Anchor},
+    presets, road,
+    settings::{self, Settings, SettingsState, TrayAction, TrayDoubleClickAction},
+    settings_window,
+    sim::{self, SimParams, SimState},
+    snapshot,
+    stats::StatsState,
+    telemetry, theme, updater, viewport, AppState,
+};
+
+const ANCHOR_ID_PREFIX: &str = "anchor:";
+const APPLY_PRESET_ID_PREFIX: &str = "apply_preset:";
+const MONITOR_ID_PREFIX: &str = "monitor:";
+const OPACITY_ID_PREFIX: &str = "opacity:";
+const OPACITY_PRESETS: [u32; 4] = [25, 50, 75, 100];
+const PRESET_ID_PREFIX: &str = "preset:";
+const ROAD_ID_PREFIX: &str = "road:";
+const SPEED_ID_PREFIX: &str = "speed:";
+const THEME_ID_PREFIX: &str = "theme:";
+const WAYPOINT_ID_PREFIX: &str = "waypoint:";
+const RESET_PROMPT: &str =
+    "Reset all settings to their defaults? The current settings are backed up to settings.json.bak.";
+const SPEED_PRESETS: [(&str, f64); 3] = [("slow", 0.5), ("normal", 1.0), ("fast", 2.0)];
+/// How long a left click waits to see whether it starts a double-click.
+const DOUBLE_CLICK_WINDOW: Duration = Duration::from_millis(300);
+/// How long a double-click keeps the window above everything else.
+const RAISE_DURATION: Duration = Duration::from_secs(2);
+
+/// Tells single clicks apart from the clicks that make up a double-click.
+#[derive(Default)]
+struct Clicks {
+    /// Bumped by every click, so a delayed single click can tell it was
+    /// followed by another.
+    generation: AtomicU64,
+    last_double: Mutex<Option<Instant>>,
+}
+
+/// What the menu was built from, and the items whose state changes
+/// afterwards. Items left out of `tray_items` are `None`.
+#[derive(Clone, Default)]
+struct Items {
+    actions: Vec<TrayAction>,
+    click_through: Option<CheckMenuItem<Wry>>,
+    always_on_top: Option<CheckMenuItem<Wry>>,
+    paused: Option<CheckMenuItem<Wry>>,
+    notifications: Option<CheckMenuItem<Wry>>,
+    network_driven: Option<CheckMenuItem<Wry>>,
+    telemetry: Option<CheckMenuItem<Wry>>,
+    autostart: Option<CheckMenuItem<Wry>>,
+    close_to_tray: Option<CheckMenuItem<Wry>>,
+    monitors: Option<Submenu<Wry>>,
+    presets: Option<Submenu<Wry>>,
+    waypoints: Option<Submenu<Wry>>,
+}
+
+pub struct TrayMenu {
+    /// Replaced when the menu is rebuilt in another language.
+    items: Mutex<Items>,
+    monitor_labels: Mutex<Vec<String>>,
+    icon: TrayIcon<Wry>,
+    clicks: Clicks,
+}
+
+impl TrayMenu {
+    fn items(&self) -> Items {
+        self.items.lock().unwrap().clone()
+    }
+}
+
+fn monitor_label(language: &str, index: usize, monitor: &Monitor) -> String {
+    let size = monitor.size();
+    match monitor.name() {
+        Some(name) => format!("{name} ({}x{})", size.width, size.height),
+        None => format!(
+            "{} {} ({}x{})",
+            i18n::text(language, "display"),
+            index + 1,
+            size.width,
+            size.height
+        ),
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Builds the menu from `tray_items` in the current language, with
+/// checkmarks matching the live state.
+fn build_menu<M: Manager<Wry>>(app: &M) -> tauri::Result<(Menu<Wry>, Items)> {
+    let language = i18n::current(app.app_handle());
+    let t = |key| i18n::text(language, key);
+    let item = |id: &'static str| MenuItem::with_id(app, id, t(id), true, None::<&str>);
+    let check = |id: &'static str, checked: bool| {
+        CheckMenuItem::with_id(app, id, t(id), true, checked, None::<&str>)
+    };
+    let state = app.state::<AppState>();
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    let actions = settings.valid_tray_items();
+
+    let menu = Menu::new(app)?;
+    let mut items = Items {
+        actions: actions.clone(),
+        ..Items::default()
+    };
+    for action in actions {
+        match action {
+            TrayAction::Show => menu.append(&item("show")?)?,
+            TrayAction::Hide => menu.append(&item("hide")?)?,
+            TrayAction::ClickThrough => {
+                let click_through =
+                    check("click_through", state.click_through.load(Ordering::Relaxed))?;
+                menu.append(&click_through)?;
+                items.click_through = Some(click_through);
+            }
+            TrayAction::AlwaysOnTop => {
+                let always_on_top =
+                    check("always_on_top", state.always_on_top.load(Ordering::Relaxed))?;
+                menu.append(&always_on_top)?;
+                items.always_on_top = Some(always_on_top);
+            }
+            TrayAction::ResetPosition => menu.append(&item("reset_pos")?)?,
+            TrayAction::Anchors => {
+                let anchors = Submenu::with_id(app, "anchors", t("anchors"), true)?;
+                for (i, anchor) in Anchor::ALL.iter().enumerate() {
+                    let id = format!("{ANCHOR_ID_PREFIX}{i}");
+                    let item = MenuItem::with_id(app, id, t(anchor.key()), true, None::<&str>)?;
+                    anchors.append(&item)?;
+                }
+                menu.append(&anchors)?;
+            }
+            TrayAction::Monitors => {
+                let monitors = Submenu::with_id(app, "monitors", t("monitors"), true)?;
+                menu.append(&monitors)?;
+                items.monitors = Some(monitors);
+            }
+            TrayAction::Opacity => {
+                let opacity_presets = Submenu::with_id(app, "opacity", t("opacity"), true)?;
+                for percent in OPACITY_PRESETS {
+                    let id = format!("{OPACITY_ID_PREFIX}{percent}");
+                    let item =
+                        MenuItem::with_id(app, id, format!("{percent}%"), true, None::<&str>)?;
+                    opacity_presets.append(&item)?;
+                }
+                menu.append(&opacity_presets)?;
+            }
+            TrayAction::Speed => {
+                let speed_presets = Submenu::with_id(app, "speed", t("speed"), true)?;
+                for (key, _) in SPEED_PRESETS {
+                    let id = format!("{SPEED_ID_PREFIX}{key}");
+                    let item = MenuItem::with_id(app, id, t(key), true, None::<&str>)?;
+                    speed_presets.append(&item)?;
+                }
+                menu.append(&speed_presets)?;
+            }
+            TrayAction::Roads => {
+                let roads = Submenu::with_id(app, "roads", t("roads"), true)?;
+                for network in road::builtins() {
+                    let id = format!("{ROAD_ID_PREFIX}{}", network.name);
+                    let label = capitalize(&network.name);
+                    let item = MenuItem::with_id(app, id, label, true, None::<&str>)?;
+                    roads.append(&item)?;
+                }
+                menu.append(&roads)?;
+            }
+            TrayAction::Waypoints => {
+                let waypoints = Submenu::with_id(app, "waypoints", t("waypoints"), true)?;
+                menu.append(&waypoints)?;
+                items.waypoints = Some(waypoints);
+            }
+            TrayAction::Themes => {
+                let themes = Submenu::with_id(app, "themes", t("themes"), true)?;
+                for theme in theme::builtins() {
+                    let id = format!("{THEME_ID_PREFIX}{}", theme.name);
+                    let label = capitalize(&theme.name);
+                    let item = MenuItem::with_id(app, id, label, true, None::<&str>)?;
+                    themes.append(&item)?;
+                }
+                menu.append(&themes)?;
+            }
+            TrayAction::Presets => {
+                let presets = Submenu::with_id(app, "presets", t("presets"), true)?;
+                menu.append(&presets)?;
+                items.presets = Some(presets);
+            }
+            TrayAction::NextPreset => menu.append(&item("next_preset")?)?,
+            TrayAction::ApplyPreset(name) => {
+                let id = format!("{APPLY_PRESET_ID_PREFIX}{name}");
+                menu.append(&MenuItem::with_id(app, id, name, true, None::<&str>)?)?;
+            }
+            TrayAction::Pause => {
+                let paused = check(
+                    "paused",
+                    app.state::<SimState>().paused.load(Ordering::Relaxed),
+                )?;
+                menu.append(&paused)?;
+                items.paused = Some(paused);
+            }
+            TrayAction::ExportSnapshot => menu.append(&item("export")?)?,
+            TrayAction::CopySettings => menu.append(&item("copy_settings")?)?,
+            TrayAction::PasteSettings => menu.append(&item("paste_settings")?)?,
+            TrayAction::Notifications => {
+                let notifications = check("notifications", settings.notifications_enabled)?;
+                menu.append(&notifications)?;
+                items.notifications = Some(notifications);
+            }
+            TrayAction::NetworkDriven => {
+                let network_driven = check("network_driven", settings.network_driven)?;
+                menu.append(&network_driven)?;
+                items.network_driven = Some(network_driven);
+            }
+            TrayAction::Telemetry => {
+                let telemetry = check("telemetry", settings.telemetry_enabled)?;
+                menu.append(&telemetry)?;
+                items.telemetry = Some(telemetry);
+            }
+            TrayAction::CheckUpdates => menu.append(&item("check_updates")?)?,
+            TrayAction::Autostart => {
+                let enabled = app.autolaunch().is_enabled().unwrap_or_else(|e| {
+                    log::warn!("failed to query autostart state: {e}");
+                    false
+                });
+                let autostart = check("autostart", enabled)?;
+                menu.append(&autostart)?;
+                items.autostart = Some(autostart);
+            }
+            TrayAction::CloseToTray => {
+                let close_to_tray = check("close_to_tray", settings.close_to_tray)?;
+                menu.append(&close_to_tray)?;
+                items.close_to_tray = Some(close_to_tray);
+            }
+            TrayAction::Settings => menu.append(&item("settings")?)?,
+            TrayAction::OpenConfig => menu.append(&item("open_config")?)?,
+            TrayAction::ResetSettings => menu.append(&item("reset_settings")?)?,
+        }
+    }
+    menu.append(&item("quit")?)?;
+    Ok((menu, items))
+}
+
+pub fn create(app: &App) -> tauri::Result<()> {
+    let (menu, items) = build_menu(app)?;
+    let click_toggles = app
+        .state::<SettingsState>()
+        .0
+        .lock()
+        .unwrap()
+        .tray_click_toggles;
+    let icon = TrayIconBuilder::new()
+        .menu(&menu)
+        .show_menu_on_left_click(!click_toggles)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(on_tray_icon_event)
+        .build(app)?;
+
+    app.manage(TrayMenu {
+        items: Mutex::new(items),
+        monitor_labels: Mutex::default(),
+        icon,
+        clicks: Clicks::default(),
+    });
+    refresh_monitors(app.handle());
+    refresh_presets(app.handle());
+    refresh_waypoints(app.handle());
+
+    Ok(())
+}
+
+/// The tray icon, once it's been created.
+pub fn icon(app: &AppHandle) -> Option<TrayIcon<Wry>> {
+    Some(app.try_state::<TrayMenu>()?.icon.clone())
+}
+
+/// Replaces the menu with one built from the current settings and language.
+pub fn rebuild(app: &AppHandle) {
+    let Some(tray) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let (menu, items) = match build_menu(app) {
+        Ok(built) => built,
+        Err(e) => return error::report(app, "failed to rebuild the tray menu", e),
+    };
+    if let Err(e) = tray.icon.set_menu(Some(menu)) {
+        return error::report(app, "failed to rebuild the tray menu", e);
+    }
+    *tray.items.lock().unwrap() = items;
+    tray.monitor_labels.lock().unwrap().clear();
+    refresh_monitors(app);
+    refresh_presets(app);
+    refresh_waypoints(app);
+}
+
+/// Rebuilds the menu if `settings` lists different items than it shows.
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    let Some(tray) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    if tray.items().actions != settings.valid_tray_items() {
+        rebuild(app);
+    }
+}
+
+/// Shows the simulation status and lifetime totals in the tray tooltip.
+/// Platforms without tray tooltips (e.g. Linux) silently ignore this.
+pub fn set_status(app: &AppHandle, vehicles: usize, tick_rate: f64) {
+    if let Some(tray) = app.try_state::<TrayMenu>() {
+        let mut tooltip = format!("{vehicles} vehicles · {tick_rate:.0} ticks/s");
+        if let Some(stats) = app.try_state::<StatsState>() {
+            let stats = stats.current();
+            tooltip += &format!(
+                "\n{} spawned · {} crashes all time",
+                stats.vehicles_spawned, stats.collisions
+            );
+        }
+        if let Err(e) = tray.icon.set_tooltip(Some(tooltip)) {
+            error::report(app, "failed to update the tray tooltip", e);
+        }
+    }
+}
+
+/// Updates checkmarks to match the live state.
+pub fn sync(app: &AppHandle) {
+    let Some(tray) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let state = app.state::<AppState>();
+    let settings = app.state::<SettingsState>().0.lock().unwrap().clone();
+    let items = tray.items();
+    let checks = [
+        (
+            items.click_through,
+            state.click_through.load(Ordering::Relaxed),
+        ),
+        (
+            items.always_on_top,
+            state.always_on_top.load(Ordering::Relaxed),
+        ),
+        (
+            items.paused,
+            app.state::<SimState>().paused.load(Ordering::Relaxed),
+        ),
+        (items.notifications, settings.notifications_enabled),
+        (items.network_driven, settings.network_driven),
+        (items.telemetry, settings.telemetry_enabled),
+        (items.close_to_tray, settings.close_to_tray),
+    ];
+    let results = checks
+        .into_iter()
+        .filter_map(|(item, checked)| Some(item?.set_checked(checked)))
+        .chain([tray
+            .icon
+            .set_show_menu_on_left_click(!settings.tray_click_toggles)]);
+    for e in results.filter_map(Result::err) {
+        error::report(app, "failed to update the tray menu", e);
+    }
+}
+
+/// Rebuilds the "Presets" submenu from the current settings.
+pub fn refresh_presets(app: &AppHandle) {
+    let Some(tray) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let presets = presets::all(&app.state::<SettingsState>().0.lock().unwrap());
+    let items = presets
+        .into_iter()
+        .enumerate()
+        .map(|(i, preset)| (format!("{PRESET_ID_PREFIX}{i}"), preset.name));
+    if let Some(submenu) = tray.items().presets {
+        replace_items(app, &submenu, items);
+    }
+}
+
+/// Rebuilds the "Waypoints" submenu, disabling it while there are none.
+pub fn refresh_waypoints(app: &AppHandle) {
+    let Some(tray) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let Some(submenu) = tray.items().waypoints else {
+        return;
+    };
+    let waypoints = app
+        .state::<SettingsState>()
+        .0
+        .lock()
+        .unwrap()
+        .waypoints
+        .clone();
+    if let Err(e) = submenu.set_enabled(!waypoints.is_empty()) {
+        error::report(app, "failed to update the tray menu", e);
+    }
+    let items = waypoints
+        .into_iter()
+        .enumerate()
+        .map(|(i, waypoint)| (format!("{WAYPOINT_ID_PREFIX}{i}"), waypoint.name));
+    replace_items(app, &submenu, items);
+}
+
+fn replace_items(
+    app: &AppHandle,
+    submenu: &Submenu<Wry>,
+    items: impl IntoIterator<Item = (String, String)>,
+) {
+    let result = (|| -> tauri::Result<()> {
+        for item in submenu.items()? {
+            submenu.remove(&item)?;
+        }
+        for (id, label) in items {
+            submenu.append(&MenuItem::with_id(app, id, label, true, None::<&str>)?)?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        error::report(app, "failed to rebuild a tray submenu", e);
+    }
+}
+
+/// Rebuilds the "Move to Monitor" submenu if the connected displays changed.
+pub fn refresh_monitors(app: &AppHandle) {
+    let Some(tray) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let Ok(monitors) = app.available_monitors() else {
+        return;
+    };
+    let language = i18n::current(app);
+    let labels: Vec<String> = monitors
+        .iter()
+        .enumerate()
+        .map(|(i, m)| monitor_label(language, i, m))
+        .collect();
+
+    let mut current = tray.monitor_labels.lock().unwrap();
+    if *current == labels {
+        return;
+    }
+
+    let items = labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| (format!("{MONITOR_ID_PREFIX}{i}"), label.clone()));
+    if let Some(submenu) = tray.items().monitors {
+        replace_items(app, &submenu, items);
+    }
+    *current = labels;
+}
+
+/// Toggles the window on a left click when enabled, and runs the configured
+/// action on a double-click. Linux trays don't report clicks, so there the
+/// menu is the only way in.
+fn on_tray_icon_event(icon: &TrayIcon, event: TrayIconEvent) {
+    let app = icon.app_handle();
+    match event {
+        TrayIconEvent::Click {
+            button: MouseButton::Left,
+            button_state: MouseButtonState::Up,
+            ..
+        } => on_left_click(app),
+        TrayIconEvent::DoubleClick {
+            button: MouseButton::Left,
+            ..
+        } => on_double_click(app),
+        _ => {}
+    }
+}
+
+fn on_left_click(app: &AppHandle) {
+    let (click_toggles, double_click) = {
+        let settings = app.state::<SettingsState>();
+        let settings = settings.0.lock().unwrap();
+        (
+            settings.tray_click_toggles,
+            settings.tray_double_click_action,
+        )
+    };
+    if !click_toggles {
+        return;
+    }
+    // Windows sends both clicks of a double-click as well as the double-click
+    // itself, so hold the toggle back until it's clear no second click follows.
+    if !cfg!(target_os = "windows") || double_click == TrayDoubleClickAction::None {
+        overlay::toggle_visibility(app);
+        return;
+    }
+    let clicks = &app.state::<TrayMenu>().clicks;
+    let after_double = clicks
+        .last_double
+        .lock()
+        .unwrap()
+        .is_some_and(|at| at.elapsed() < DOUBLE_CLICK_WINDOW);
+    if after_double {
+        return;
+    }
+    let generation = clicks.generation.fetch_add(1, Ordering::Relaxed) + 1;
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(DOUBLE_CLICK_WINDOW);
+        let clicks = &app.state::<TrayMenu>().clicks;
+        if clicks.generation.load(Ordering::Relaxed) != generation {
+            return;
+        }
+        let handle = app.clone();
+        if let Err(e) = app.run_on_main_thread(move || overlay::toggle_visibility(&handle)) {
+            error::report(&app, "failed to toggle window visibility", e);
+        }
+    });
+}
+
+fn on_double_click(app: &AppHandle) {
+    let clicks = &app.state::<TrayMenu>().clicks;
+    clicks.generation.fetch_add(1, Ordering::Relaxed);
+    *clicks.last_double.lock().unwrap() = Some(Instant::now());
+
+    let action = app
+        .state::<SettingsState>()
+        .0
+        .lock()
+        .unwrap()
+        .tray_double_click_action;
+    match action {
+        TrayDoubleClickAction::ResetPosition => bring_back(app),
+        TrayDoubleClickAction::ToggleClickThrough => toggle_click_through(app),
+        TrayDoubleClickAction::None => {}
+    }
+}
+
+/// Centers the window on its monitor.
+fn reset_position(window: &WebviewWindow) {
+    match window.current_monitor() {
+        Ok(Some(monitor)) => {
+            if let Err(e) = position::center_on_monitor(window, &monitor) {
+                error::report(window.app_handle(), "failed to reset position", e);
+            }
+        }
+        Ok(None) => log::warn!("window is not on any monitor"),
+        Err(e) => error::report(window.app_handle(), "failed to query current monitor", e),
+    }
+}
+
+/// Centers the window, shows it and briefly raises it above everything, for
+/// when it's been lost off screen or behind other windows.
+fn bring_back(app: &AppHandle) {
+    let Some(window) = overlay::main_window_or_recreate(app) else {
+        return;
+    };
+    reset_position(&window);
+    foreground::set_manually_hidden(app, false);
+    if let Err(e) = overlay::show(&window) {
+        error::report(app, "failed to show window", e);
+    }
+    if let Err(e) = window.set_focus() {
+        log::warn!("failed to focus window: {e}");
+    }
+    if let Err(e) = window.set_always_on_top(true) {
+        error::report(app, "failed to raise window", e);
+    }
+    // Back to the user's setting; the raise isn't saved.
+    thread::spawn(move || {
+        thread::sleep(RAISE_DURATION);
+        if let Err(e) = overlay::restore_level(&window) {
+            error::report(window.app_handle(), "failed to restore always-on-top", e);
+        }
+    });
+}
+
+fn toggle_click_through(app: &AppHandle) {
+    let Some(window) = main_window(app) else {
+        return;
+    };
+    match overlay::toggle_click_through(&window) {
+        Ok(enabled) => {
+            let tray = app.state::<TrayMenu>();
+            if let Some(item) = tray.items().click_through {
+                if let Err(e) = item.set_checked(enabled) {
+                    log::warn!("failed to update tray checkmark: {e}");
+                }
+            }
+            if let Err(e) = window.emit("toggle-click-through", enabled) {
+                error::report(app, "failed to emit toggle-click-through", e);
+            }
+        }
+        Err(e) => error::report(app, "failed to toggle click-through", e),
+    }
+}
+
+/// Handles the main window's close button: hides it to the tray, explaining
+/// that once, or quits when `close_to_tray` is off. Other windows just close.
+pub fn on_close_requested(app: &AppHandle, label: &str, api: &CloseRequestApi) {
+    if label != "main" {
+        return;
+    }
+    let (close_to_tray, notified) = {
+        let settings = app.state::<SettingsState>();
+        let settings = settings.0.lock().unwrap();
+        (settings.close_to_tray, settings.close_to_tray_notified)
+    };
+    if !close_to_tray {
+        app.exit(0);
+        return;
+    }
+    api.prevent_close();
+    foreground::set_manually_hidden(app, true);
+    if let Some(window) = app.get_webview_window(label) {
+        if let Err(e) = window.hide() {
+            error::report(app, "failed to hide window", e);
+        }
+    }
+    if notified {
+        return;
+    }
+    let body = "Shibuya Traffic is still running. Use the tray icon to show the window or quit.";
+    if let Err(e) = notify::send(app, "close-to-tray", "Still running in the tray", body) {
+        log::error!("failed to send notification: {e}");
+    }
+    if let Err(e) = settings::update(app, |s| s.close_to_tray_notified = true) {
+        log::error!("failed to save settings: {e}");
+    }
+}
+
+/// Looks up the overlay window, reporting when it's gone.
+fn main_window(app: &AppHandle) -> Option<WebviewWindow> {
+    let window = app.get_webview_window("main");
+    if window.is_none() {
+        error::report(app, "tray action failed", AppError::window_missing("main"));
+    }
+    window
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    let tray = app.state::<TrayMenu>();
+    log::debug!("tray menu: {}", event.id.as_ref());
+    match event.id.as_ref() {
+        "show" => {
+            foreground::set_manually_hidden(app, false);
+            if let Some(window) = overlay::main_window_or_recreate(app) {
+                if let Err(e) = overlay::show(&window) {
+                    error::report(app, "failed to show window", e);
+                }
+                // Without a Dock icon this is the only way to bring the app forward.
+                if let Err(e) = window.set_focus() {
+                    log::warn!("failed to focus window: {e}");
+                }
+            }
+        }
+        "hide" => {
+            foreground::set_manually_hidden(app, true);
+            if let Some(window) = main_window(app) {
+                if let Err(e) = window.hide() {
+                    error::report(app, "failed to hide window", e);
+                }
+            }
+        }
+        "click_through" => toggle_click_through(app),
+        "always_on_top" => {
+            if let Some(window) = main_window(app) {
+                let enabled = !app
+                    .state::<AppState>()
+                    .always_on_top
+                    .load(Ordering::Relaxed);
+                if let Err(e) = overlay::set_always_on_top(&window, enabled) {
+                    error::report(app, "failed to set always on top", e);
+                }
+            }
+            sync(app);
+        }
+        "next_preset" => {
+            if let Err(e) = presets::cycle(app, 1) {
+                error::report(app, "failed to switch preset", e);
+            }
+        }
+        "paused" => {
+            let paused = !app.state::<SimState>().paused.load(Ordering::Relaxed);
+            sim::set_paused_state(app, paused);
+        }
+        "export" => match snapshot::export(app, None) {
+            Ok(path) => {
+                let body = path.display().to_string();
+                if let Err(e) = notify::send(app, "snapshot", "Snapshot exported", &body) {
+                    error::report(app, "failed to send notification", AppError::Platform(e));
+                }
+            }
+            Err(e) => error::report(app, "failed to export snapshot", e),
+        },
+        "copy_settings" => {
+            if let Err(e) = clipboard::export(app) {
+                error::report(app, "failed to copy settings", e);
+            }
+        }
+        "paste_settings" => {
+            if let Err(e) = clipboard::import(app) {
+                log::warn!("failed to paste settings: {e}");
+                app.dialog()
+                    .message(e.to_string())
+                    .title("Paste Settings")
+                    .kind(MessageDialogKind::Error)
+                    .show(|_| {});
+            }
+        }
+        "notifications" => {
+            let result =
+                settings::update(app, |s| s.notifications_enabled = !s.notifications_enabled);
+            if let Err(e) = result {
+                error::report(app, "failed to save settings", AppError::Io(e));
+            }
+            sync(app);
+        }
+        "close_to_tray" => {
+            if let Err(e) = settings::update(app, |s| s.close_to_tray = !s.close_to_tray) {
+                error::report(app, "failed to save settings", AppError::Io(e));
+            }
+            sync(app);
+        }
+        "network_driven" => {
+            if let Err(e) = settings::update(app, |s| s.network_driven = !s.network_driven) {
+                error::report(app, "failed to save settings", AppError::Io(e));
+            }
+            sync(app);
+        }
+        "check_updates" => updater::check_now(app),
+        "telemetry" => {
+            let enabled = app
+                .state::<SettingsState>()
+                .0
+                .lock()
+                .unwrap()
+                .telemetry_enabled;
+            if let Err(e) = telemetry::set_enabled(app, !enabled) {
+                error::report(app, "failed to save settings", AppError::Io(e));
+            }
+        }
+        "reset_pos" => {
+            if let Some(window) = main_window(app) {
+                reset_position(&window);
+            }
+        }
+        "autostart" => {
+            let Some(autostart) = tray.items().autostart else {
+                return;
+            };
+            let autolaunch = app.autolaunch();
+            let result = autolaunch.is_enabled().and_then(|enabled| {
+                if enabled {
+                    autolaunch.disable()
+                } else {
+                    autolaunch.enable()
+                }
+                .map(|_| !enabled)
+            });
+            match result {
+                Ok(enabled) => {
+                    if let Err(e) = autostart.set_checked(enabled) {
+                        log::warn!("failed to update tray checkmark: {e}");
+                    }
+                }
+                Err(e) => {
+                    error::report(
+                        app,
+                        "failed to toggle autostart",
+                        AppError::Platform(e.to_string()),
+                    );
+                    // The menu toggles the checkmark itself on click, so put it back.
+                    let checked = autostart.is_checked().unwrap_or(false);
+                    if let Err(e) = autostart.set_checked(!checked) {
+                        log::warn!("failed to update tray checkmark: {e}");
+                    }
+                }
+            }
+        }
+        "settings" => {
+            if let Err(e) = settings_window::open(app) {
+                error::report(app, "failed to open settings", e);
+            }
+        }
+        "open_config" => {
+            let result = settings::config_dir(app).and_then(|dir| {
+                app.opener()
+                    .open_path(dir.to_string_lossy(), None::<&str>)
+                    .map_err(|e| e.to_string())
+            });
+            if let Err(e) = result {
+                error::report(app, "failed to open config folder", AppError::Io(e));
+            }
+        }
+        "reset_settings" => {
+            let app = app.clone();
+            app.dialog()
+                .message(RESET_PROMPT)
+                .title("Reset to Defaults")
+                .kind(MessageDialogKind::Warning)
+                .buttons(MessageDialogButtons::OkCancelCustom(
+                    "Reset".into(),
+                    "Cancel".into(),
+                ))
+                .show(move |confirmed| {
+                    if confirmed {
+                        if let Err(e) = settings::reset(&app) {
+                            error::report(&app, "failed to reset settings", AppError::Io(e));
+                        }
+                    }
+                });
+        }
+        "quit" => {
+            app.exit(0);
+        }
+        id => {
+            if let Some(percent) = id.strip_prefix(OPACITY_ID_PREFIX) {
+                let Ok(percent) = percent.parse::<f64>() else {
+                    log::warn!("invalid opacity menu id: {id}");
+                    return;
+                };
+                if let Some(window) = main_window(app) {
+                    if let Err(e) = opacity::set(&window, percent / 100.0) {
+                        error::report(app, "failed to set opacity", e);
+                    }
+                }
+            } else if let Some(index) = id.strip_prefix(PRESET_ID_PREFIX) {
+                let preset = index.parse().ok().and_then(|i| presets::get(app, i));
+                match preset {
+                    Some(preset) => {
+                        if let Err(e) = presets::apply(app, &preset) {
+                            let context = format!("failed to apply preset {:?}", preset.name);
+                            error::report(app, &context, e);
+                        }
+                    }
+                    None => log::warn!("unknown preset menu id: {id}"),
+                }
+            } else if let Some(name) = id.strip_prefix(APPLY_PRESET_ID_PREFIX) {
+                let Some(preset) = presets::by_name(app, name) else {
+                    log::warn!("preset {name:?} no longer exists");
+                    return;
+                };
+                if let Err(e) = presets::apply(app, &preset) {
+                    error::report(app, &format!("failed to apply preset {name:?}"), e);
+                }
+            } else if let Some(name) = id.strip_prefix(THEME_ID_PREFIX) {
+                let Some(theme) = theme::builtins().into_iter().find(|t| t.name == name) else {
+                    log::warn!("unknown theme menu id: {id}");
+                    return;
+                };
+                if let Err(e) = theme::set(app, theme) {
+                    error::report(app, "failed to set theme", e);
+                }
+            } else if let Some(index) = id.strip_prefix(WAYPOINT_ID_PREFIX) {
+                let waypoint = index.parse().ok().and_then(|i: usize| {
+                    let settings = app.state::<SettingsState>();
+                    let settings = settings.0.lock().unwrap();
+                    settings.waypoints.get(i).map(|w| w.name.clone())
+                });
+                let Some(name) = waypoint else {
+                    log::warn!("waypoint {index} no longer exists");
+                    return;
+                };
+                if let Err(e) = viewport::go_to(app, &name) {
+                    error::report(app, &format!("failed to go to waypoint {name:?}"), e);
+                }
+            } else if let Some(name) = id.strip_prefix(ROAD_ID_PREFIX) {
+                let Some(network) = road::builtins().into_iter().find(|n| n.name == name) else {
+                    log::warn!("unknown road network menu id: {id}");
+                    return;
+                };
+                if let Err(e) = road::set(app, network) {
+                    error::report(app, "failed to set road network", e);
+                }
+            } else if let Some(key) = id.strip_prefix(SPEED_ID_PREFIX) {
+                if let Some((_, speed)) = SPEED_PRESETS.iter().find(|(k, _)| *k == key) {
+                    let params = SimParams {
+                        speed_multiplier: *speed,
+                        ..*app.state::<SimState>().params.lock().unwrap()
+                    };
+                    if let Err(e) = sim::set_params(app, params) {
+                        error::report(app, "failed to set speed", e);
+                    }
+                }
+            } else if let Some(index) = id.strip_prefix(ANCHOR_ID_PREFIX) {
+                let Some(anchor) = index.parse().ok().and_then(|i: usize| Anchor::ALL.get(i))
+                else {
+                    log::warn!("unknown anchor menu id: {id}");
+                    return;
+                };
+                let margin = app.state::<SettingsState>().0.lock().unwrap().anchor_margin;
+                if let Some(window) = main_window(app) {
+                    if let Err(e) = position::move_to_anchor(&window, *anchor, margin) {
+                        error::report(app, "failed to move window", e);
+                    }
+                }
+            } else if let Some(index) = id.strip_prefix(MONITOR_ID_PREFIX) {
+                let monitor = index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| app.available_monitors().ok()?.into_iter().nth(i));
+                let Some(monitor) = monitor else {
+                    log::warn!("monitor {index} is no longer connected");
+                    return;
+                };
+                if let Some(window) = main_window(app) {
+                    if let Err(e) = position::center_on_monitor(&window, &monitor) {
+                        error::report(app, "failed to move window", e);
+                    }
+                }
+            } else {
+                log::warn!("unhandled tray menu id: {id}");
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Badges the tray icon green, amber or red by how close the road is to the
+//! vehicle cap.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tauri::{image::Image, AppHandle, Manager};
+
+use crate::{settings::Settings, tray};
+
+/// How long a new load has to last before the badge changes, so a count
+/// hovering at a threshold doesn't make the icon flicker.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+/// Fractions of the cap where the badge turns amber and red.
+const BUSY_AT: f64 = 0.5;
+const FULL_AT: f64 = 0.85;
+/// The badge's radius, as a fraction of the icon's size.
+const BADGE_RADIUS: f64 = 0.22;
+const BADGE_OUTLINE: [u8; 3] = [0x20, 0x20, 0x20];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Load {
+    Light,
+    Busy,
+    Full,
+}
+
+impl Load {
+    fn of(vehicles: usize, cap: usize) -> Self {
+        let ratio = if cap == 0 {
+            1.0
+        } else {
+            vehicles as f64 / cap as f64
+        };
+        if ratio < BUSY_AT {
+            Load::Light
+        } else if ratio < FULL_AT {
+            Load::Busy
+        } else {
+            Load::Full
+        }
+    }
+
+    fn color(self) -> [u8; 3] {
+        match self {
+            Load::Light => [0x2e, 0xcc, 0x71],
+            Load::Busy => [0xf3, 0x9c, 0x12],
+            Load::Full => [0xe7, 0x4c, 0x3c],
+        }
+    }
+}
+
+#[derive(Default)]
+struct Badge {
+    enabled: bool,
+    /// Icons drawn so far, by load.
+    icons: [Option<Image<'static>>; 3],
+    shown: Option<Load>,
+    /// A load that hasn't lasted [`DEBOUNCE`] yet, and when it started.
+    pending: Option<(Load, Instant)>,
+    /// Set once changing the icon has failed, which it keeps doing on
+    /// platforms that can't.
+    unsupported: bool,
+}
+
+#[derive(Default)]
+pub struct TrayLoad(Mutex<Badge>);
+
+/// Draws a dot of `color`, outlined so it stands out on any panel, in the
+/// bottom-right corner of a `width` by `height` RGBA image.
+fn badge(rgba: &[u8], width: u32, height: u32, color: [u8; 3]) -> Vec<u8> {
+    let mut out = rgba.to_vec();
+    let radius = width.min(height) as f64 * BADGE_RADIUS;
+    let (cx, cy) = (width as f64 - radius, height as f64 - radius);
+    for y in 0..height {
+        for x in 0..width {
+            let distance = (x as f64 + 0.5 - cx).hypot(y as f64 + 0.5 - cy);
+            if distance > radius {
+                continue;
+            }
+            let i = (y * width + x) as usize * 4;
+            let fill = if distance > radius * 0.75 {
+                BADGE_OUTLINE
+            } else {
+                color
+            };
+            out[i..i + 3].copy_from_slice(&fill);
+            out[i + 3] = 0xff;
+        }
+    }
+    out
+}
+
+/// The app icon with the badge for `load`, drawn the first time it's needed.
+fn icon(app: &AppHandle, badge: &mut Badge, load: Load) -> Option<Image<'static>> {
+    let slot = &mut badge.icons[load as usize];
+    if slot.is_none() {
+        let base = app.default_window_icon()?;
+        let rgba = self::badge(base.rgba(), base.width(), base.height(), load.color());
+        *slot = Some(Image::new_owned(rgba, base.width(), base.height()));
+    }
+    slot.clone()
+}
+
+/// Puts `image` in the tray, or the plain icon for `None`.
+fn show(app: &AppHandle, image: Option<Image<'static>>) -> tauri::Result<()> {
+    let Some(tray) = tray::icon(app) else {
+        return Ok(());
+    };
+    // macOS draws template icons in one color, which would hide the badge.
+    let template = image.is_none();
+    let image = image.or_else(|| app.default_window_icon().map(|i| i.clone().to_owned()));
+    tray.set_icon_as_template(template)?;
+    tray.set_icon(image)
+}
+
+/// Shows the load of `vehicles` out of `cap` on the tray icon, once it's
+/// lasted long enough. Called every tick.
+pub fn update(app: &AppHandle, vehicles: usize, cap: usize) {
+    let Some(state) = app.try_state::<TrayLoad>() else {
+        return;
+    };
+    let load = Load::of(vehicles, cap);
+    let mut badge = state.0.lock().unwrap();
+    if !badge.enabled || badge.unsupported || badge.shown == Some(load) {
+        badge.pending = None;
+        return;
+    }
+    // The first badge goes up straight away.
+    if badge.shown.is_some() {
+        match badge.pending {
+            Some((pending, since)) if pending == load => {
+                if since.elapsed() < DEBOUNCE {
+                    return;
+                }
+            }
+            _ => {
+                badge.pending = Some((load, Instant::now()));
+                return;
+            }
+        }
+    }
+    badge.pending = None;
+    let Some(image) = icon(app, &mut badge, load) else {
+        return;
+    };
+    badge.shown = Some(load);
+    // Setting the icon waits on the main thread, which may be waiting on
+    // this lock.
+    drop(badge);
+    if let Err(e) = show(app, Some(image)) {
+        log::warn!("can't change the tray icon here, keeping the plain one: {e}");
+        state.0.lock().unwrap().unsupported = true;
+    }
+}
+
+/// Turns the badge on or off, putting the plain icon back when it goes off.
+pub fn load_settings(app: &AppHandle, settings: &Settings) {
+    let Some(state) = app.try_state::<TrayLoad>() else {
+        return;
+    };
+    let mut badge = state.0.lock().unwrap();
+    badge.enabled = settings.dynamic_tray_icon;
+    badge.pending = None;
+    let badged = badge.shown.take().is_some();
+    let unsupported = badge.unsupported;
+    drop(badge);
+    if badged && !settings.dynamic_tray_icon && !unsupported {
+        if let Err(e) = show(app, None) {
+            log::warn!("failed to restore the tray icon: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_follows_the_cap() {
+        assert_eq!(Load::of(10, 300), Load::Light);
+        assert_eq!(Load::of(150, 300), Load::Busy);
+        assert_eq!(Load::of(290, 300), Load::Full);
+        assert_eq!(Load::of(0, 0), Load::Full);
+    }
+
+    #[test]
+    fn badge_covers_only_the_corner() {
+        let rgba = vec![0; 16 * 16 * 4];
+        let out = badge(&rgba, 16, 16, [0xff, 0, 0]);
+        let pixel = |x: usize, y: usize| &out[(y * 16 + x) * 4..(y * 16 + x) * 4 + 4];
+        assert_eq!(pixel(0, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(13, 13), [0xff, 0, 0, 0xff]);
+    }
+}
@@ -0,0 +1,129 @@
+//! Checks for new releases and installs them.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_updater::{Update, Updater, UpdaterExt};
+
+use crate::{
+    error::{self, AppError},
+    notify,
+    settings::SettingsState,
+};
+
+/// Sent with `update-available` so the UI can prompt.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub notes: Option<String>,
+}
+
+/// The update found by the last check, ready for `install_update`.
+#[derive(Default)]
+pub struct PendingUpdate(Mutex<Option<Update>>);
+
+const NO_KEY: &str = "this build has no update signing key, so updates can't be verified";
+
+/// Whether the config has the public key downloaded updates are checked
+/// against. Without one every update fails verification.
+fn has_pubkey(app: &AppHandle) -> bool {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|key| key.as_str())
+        .is_some_and(|key| !key.trim().is_empty())
+}
+
+/// Whether updates can be checked for and installed.
+pub fn available(app: &AppHandle) -> bool {
+    has_pubkey(app) && app.updater().is_ok()
+}
+
+fn updater(app: &AppHandle) -> Result<Updater, String> {
+    if !has_pubkey(app) {
+        return Err(NO_KEY.into());
+    }
+    app.updater().map_err(|e| e.to_string())
+}
+
+/// Checks in the background at startup when `auto_check_updates` is on.
+pub fn start(app: AppHandle) {
+    let enabled = app
+        .state::<SettingsState>()
+        .0
+        .lock()
+        .unwrap()
+        .auto_check_updates;
+    if !enabled {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        // Usually just offline, so don't bother the user on every launch.
+        if let Err(e) = check(&app).await {
+            log::info!("skipped update check: {e}");
+        }
+    });
+}
+
+/// Checks now and always reports back, for the tray's "Check for Updates".
+pub fn check_now(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let body = match check(&app).await {
+            Ok(Some(info)) => format!("Version {} is available", info.version),
+            Ok(None) => "You're on the latest version".to_string(),
+            Err(e) => {
+                error::report(&app, "update check failed", AppError::Io(e));
+                "Couldn't check for updates".to_string()
+            }
+        };
+        if let Err(e) = notify::send(&app, "update", "Shibuya Traffic", &body) {
+            log::error!("failed to send notification: {e}");
+        }
+    });
+}
+
+/// Looks for a newer release and emits `update-available` if there is one.
+async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let update = updater(app)?.check().await.map_err(|e| e.to_string())?;
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        notes: update.body.clone(),
+    });
+    match &info {
+        Some(info) => {
+            log::info!("update {} available", info.version);
+            if let Err(e) = app.emit("update-available", info) {
+                log::error!("failed to emit update-available: {e}");
+            }
+        }
+        None => log::info!("no update available"),
+    }
+    *app.state::<PendingUpdate>().0.lock().unwrap() = update;
+    Ok(info)
+}
+
+/// Downloads and installs the update found by the last check, checking
+/// first if there wasn't one, then relaunches.
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<(), AppError> {
+    let pending = app.state::<PendingUpdate>().0.lock().unwrap().take();
+    let update = match pending {
+        Some(update) => update,
+        None => updater(&app)
+            .map_err(AppError::Platform)?
+            .check()
+            .await
+            .map_err(|e| AppError::Io(e.to_string()))?
+            .ok_or_else(|| AppError::InvalidParam("no update available".into()))?,
+    };
+    log::info!("installing update {}", update.version);
+    update
+        .download_and_install(|_, _| {}, || log::info!("update downloaded"))
+        .await
+        .map_err(|e| AppError::Io(e.to_string()))?;
+    app.restart()
+}
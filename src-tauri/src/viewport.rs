@@ -0,0 +1,215 @@
+//! Which part of the road network the canvas shows, and named spots to jump
+//! back to.
+
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+#[cfg(desktop)]
+use crate::tray;
+use crate::{
+    error::AppError,
+    road::{Bounds, RoadState},
+    settings::{self, SettingsState},
+    sim::{SimState, SCENE_H, SCENE_W},
+};
+
+pub const ZOOM_RANGE: RangeInclusive<f64> = 0.25..=4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Viewport {
+    /// World position of the canvas's top-left corner.
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Viewport {
+    /// The part of the world the canvas shows. It stretches to fill the
+    /// window, so that's the same at any window size.
+    pub fn visible(&self) -> Bounds {
+        Bounds {
+            min_x: self.x,
+            min_y: self.y,
+            max_x: self.x + SCENE_W / self.zoom,
+            max_y: self.y + SCENE_H / self.zoom,
+        }
+    }
+
+    /// Clamps the zoom and keeps the center over `bounds`, so some of the
+    /// roads are always in view.
+    pub fn clamped(self, bounds: Bounds) -> Self {
+        let zoom = self.zoom.clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end());
+        let (w, h) = (SCENE_W / zoom, SCENE_H / zoom);
+        let cx = (self.x + w / 2.0).clamp(bounds.min_x, bounds.max_x);
+        let cy = (self.y + h / 2.0).clamp(bounds.min_y, bounds.max_y);
+        Self {
+            x: cx - w / 2.0,
+            y: cy - h / 2.0,
+            zoom,
+        }
+    }
+}
+
+/// A bookmarked viewport.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Waypoint {
+    pub name: String,
+    #[serde(flatten)]
+    pub viewport: Viewport,
+}
+
+/// Clamps `viewport` to the current roads, switches to it and tells the
+/// canvas.
+fn apply(app: &AppHandle, viewport: Viewport) -> Viewport {
+    let bounds = app.state::<RoadState>().0.lock().unwrap().bounds();
+    let viewport = viewport.clamped(bounds);
+    *app.state::<SimState>().viewport.lock().unwrap() = viewport;
+    if let Err(e) = app.emit("viewport-changed", viewport) {
+        log::error!("failed to emit viewport-changed: {e}");
+    }
+    viewport
+}
+
+/// Re-clamps the viewport after the roads change under it.
+pub fn refit(app: &AppHandle) {
+    let viewport = *app.state::<SimState>().viewport.lock().unwrap();
+    apply(app, viewport);
+}
+
+pub fn set(app: &AppHandle, viewport: Viewport) -> Result<Viewport, AppError> {
+    if ![viewport.x, viewport.y, viewport.zoom]
+        .iter()
+        .all(|v| v.is_finite())
+    {
+        return Err(AppError::InvalidParam(format!(
+            "viewport must be finite, got {viewport:?}"
+        )));
+    }
+    Ok(apply(app, viewport))
+}
+
+/// Jumps to the saved waypoint called `name`.
+pub fn go_to(app: &AppHandle, name: &str) -> Result<Viewport, AppError> {
+    let waypoint = app
+        .state::<SettingsState>()
+        .0
+        .lock()
+        .unwrap()
+        .waypoints
+        .iter()
+        .find(|w| w.name == name)
+        .cloned()
+        .ok_or_else(|| AppError::InvalidParam(format!("no waypoint named {name:?}")))?;
+    set(app, waypoint.viewport)
+}
+
+fn update_waypoints(app: &AppHandle, f: impl FnOnce(&mut Vec<Waypoint>)) -> Result<(), AppError> {
+    settings::update(app, |s| f(&mut s.waypoints)).map_err(AppError::Io)?;
+    #[cfg(desktop)]
+    tray::refresh_waypoints(app);
+    Ok(())
+}
+
+/// Pans so world position `x`, `y` is at the canvas's top-left corner, at
+/// `zoom`. Returns the viewport after clamping.
+#[tauri::command]
+pub fn set_viewport(app: AppHandle, x: f64, y: f64, zoom: f64) -> Result<Viewport, AppError> {
+    set(&app, Viewport { x, y, zoom })
+}
+
+#[tauri::command]
+pub fn get_viewport(state: State<SimState>) -> Viewport {
+    *state.viewport.lock().unwrap()
+}
+
+#[tauri::command]
+pub fn get_waypoints(state: State<SettingsState>) -> Vec<Waypoint> {
+    state.0.lock().unwrap().waypoints.clone()
+}
+
+/// Bookmarks the current viewport as `name`, replacing a waypoint of the
+/// same name.
+#[tauri::command]
+pub fn save_waypoint(app: AppHandle, name: String) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::InvalidParam("waypoint name is empty".into()));
+    }
+    let viewport = *app.state::<SimState>().viewport.lock().unwrap();
+    update_waypoints(&app, |waypoints| {
+        match waypoints.iter_mut().find(|w| w.name == name) {
+            Some(waypoint) => waypoint.viewport = viewport,
+            None => waypoints.push(Waypoint { name, viewport }),
+        }
+    })
+}
+
+#[tauri::command]
+pub fn remove_waypoint(app: AppHandle, name: String) -> Result<(), AppError> {
+    update_waypoints(&app, |waypoints| waypoints.retain(|w| w.name != name))
+}
+
+#[tauri::command]
+pub fn go_to_waypoint(app: AppHandle, name: String) -> Result<Viewport, AppError> {
+    go_to(&app, &name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: Bounds = Bounds {
+        min_x: 0.0,
+        min_y: 0.0,
+        max_x: 800.0,
+        max_y: 300.0,
+    };
+
+    #[test]
+    fn zoom_is_clamped() {
+        let viewport = Viewport {
+            zoom: 100.0,
+            ..Viewport::default()
+        };
+        assert_eq!(viewport.clamped(BOUNDS).zoom, *ZOOM_RANGE.end());
+        let viewport = Viewport {
+            zoom: 0.0,
+            ..Viewport::default()
+        };
+        assert_eq!(viewport.clamped(BOUNDS).zoom, *ZOOM_RANGE.start());
+    }
+
+    #[test]
+    fn the_roads_stay_in_view() {
+        let viewport = Viewport {
+            x: 10_000.0,
+            y: -10_000.0,
+            zoom: 2.0,
+        }
+        .clamped(BOUNDS);
+        // Centered on the bounds' top-right corner.
+        assert_eq!(viewport.x + SCENE_W / 4.0, BOUNDS.max_x);
+        assert_eq!(viewport.y + SCENE_H / 4.0, BOUNDS.min_y);
+    }
+
+    #[test]
+    fn viewports_inside_the_bounds_are_kept() {
+        let viewport = Viewport {
+            x: 100.0,
+            y: 50.0,
+            zoom: 2.0,
+        };
+        assert_eq!(viewport.clamped(BOUNDS), viewport);
+    }
+}
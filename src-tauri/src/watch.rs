@@ -0,0 +1,125 @@
+use std::{
+    fs,
+    path::Path,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use notify_debouncer_mini::{
+    new_debouncer,
+    notify::{RecommendedWatcher, RecursiveMode},
+    DebounceEventResult, Debouncer,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    error::{self, AppError},
+    settings::{self, Settings, SettingsState},
+};
+
+/// How long the file must be quiet before it's reloaded.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// Changes this soon after our own save are assumed to be that save.
+const SELF_WRITE_GRACE: Duration = Duration::from_secs(1);
+/// An editor may still be writing when the change arrives, so a file that
+/// doesn't parse is retried a few times before giving up.
+const PARSE_RETRIES: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// When `Settings::save` last wrote the file.
+#[derive(Default)]
+pub struct LastSave(Mutex<Option<Instant>>);
+
+/// Keeps the watcher alive for the life of the app.
+struct SettingsWatcher {
+    _debouncer: Mutex<Debouncer<RecommendedWatcher>>,
+}
+
+pub fn record_save(app: &AppHandle) {
+    if let Some(last) = app.try_state::<LastSave>() {
+        *last.0.lock().unwrap() = Some(Instant::now());
+    }
+}
+
+fn saved_recently(app: &AppHandle) -> bool {
+    app.state::<LastSave>()
+        .0
+        .lock()
+        .unwrap()
+        .is_some_and(|at| at.elapsed() < SELF_WRITE_GRACE)
+}
+
+/// Watches the settings file and applies edits made outside the app.
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    let path = settings::settings_path(app)?;
+    let dir = settings::config_dir(app)?;
+    let file_name = path.file_name().map(ToOwned::to_owned);
+
+    let handle = app.clone();
+    // The folder is watched rather than the file, since editors often
+    // replace the file instead of writing to it.
+    let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| match result {
+        Ok(events) => {
+            if events
+                .iter()
+                .any(|e| e.path.file_name() == file_name.as_deref())
+            {
+                reload(&handle, &path);
+            }
+        }
+        Err(e) => log::warn!("settings watcher error: {e}"),
+    })
+    .map_err(|e| e.to_string())?;
+    debouncer
+        .watcher()
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+
+    app.manage(SettingsWatcher {
+        _debouncer: Mutex::new(debouncer),
+    });
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Settings, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    Settings::parse(&contents)
+}
+
+fn reload(app: &AppHandle, path: &Path) {
+    if saved_recently(app) {
+        return;
+    }
+    let mut attempt = 0;
+    let new = loop {
+        match read(path) {
+            Ok(new) => {
+                settings::allow_saving(app);
+                break new;
+            }
+            Err(e) if attempt < PARSE_RETRIES => {
+                log::debug!("settings not readable yet ({e}), retrying");
+                attempt += 1;
+                thread::sleep(RETRY_DELAY);
+            }
+            Err(e) => {
+                error::report(
+                    app,
+                    "ignoring unreadable settings edit",
+                    AppError::InvalidParam(e),
+                );
+                return;
+            }
+        }
+    };
+
+    if *app.state::<SettingsState>().0.lock().unwrap() == new {
+        return;
+    }
+    log::info!("settings changed on disk, reloading");
+    settings::replace(app, new.clone());
+    if let Err(e) = app.emit("settings-reloaded", new) {
+        log::error!("failed to emit settings-reloaded: {e}");
+    }
+}
@@ -1,11 +1,9 @@
 import type { Container } from "pixi.js";
 
-export type EntityType = "vehicle" | "pedestrian" | "cyclist";
+export type EntityType = "pedestrian";
 export type Direction = "n" | "s" | "e" | "w";
 export type PedDirection = "n" | "ne" | "e" | "se" | "s" | "sw" | "w" | "nw";
 
-export type SpeedState = "cruising" | "decelerating" | "stopped" | "accelerating";
-
 export interface Waypoint {
   x: number;
   y: number;
@@ -24,15 +22,9 @@ export interface Entity {
   direction: Direction | PedDirection;
   sprite: Container;
   state: "moving" | "waiting" | "despawning";
-  /** Fine-grained speed state for vehicles */
-  speedState: SpeedState;
   variant: string;
   animFrame: number;
   animTimer: number;
-  // For vehicles: lane assignment
-  lane?: number;
-  // Length of the entity along its direction of travel (sprite height for N/S, width for E/W)
-  length?: number;
   // For pedestrians: target position
   targetX?: number;
   targetY?: number;
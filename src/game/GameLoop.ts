@@ -3,8 +3,8 @@ import type { GameState } from "./GameState";
 import type { SceneLayers } from "../scene/SceneBuilder";
 import type { Spritesheet } from "pixi.js";
 import { updateTrafficLight, placeTrafficLightSprites } from "../systems/TrafficLight";
-import { updateSpawner } from "../systems/Spawner";
-import { updateMovement } from "../systems/Movement";
+import { updatePedestrians } from "../systems/Pedestrians";
+import { updateVehicles } from "../systems/Vehicles";
 import { updateLighting } from "../systems/Lighting";
 import { updateQFrontScreen } from "../systems/QFrontScreen";
 
@@ -20,18 +20,21 @@ export function createGameLoop(
   ticker.add(() => {
     const dt = ticker.deltaMS / 1000; // seconds
 
-    // Advance time of day (1 real second = 1 game minute)
-    state.timeOfDay += dt / 60;
-    if (state.timeOfDay >= 24) state.timeOfDay -= 24;
+    // Advance time of day (1 real second = 1 game minute), unless the
+    // backend is setting it
+    if (state.tint === null) {
+      state.timeOfDay += dt / 60;
+      if (state.timeOfDay >= 24) state.timeOfDay -= 24;
+    }
 
     // Update traffic light state machine
     updateTrafficLight(state, dt, layers.trafficLight, sheet);
 
-    // Spawn entities
-    updateSpawner(state, dt, layers, sheet);
+    // Draw the backend's newest simulation frame
+    updateVehicles(state, layers, sheet);
 
-    // Move entities
-    updateMovement(state, dt, layers, sheet);
+    // Spawn and walk pedestrians
+    updatePedestrians(state, dt, layers, sheet);
 
     // Update lighting
     updateLighting(state, layers);
@@ -1,4 +1,6 @@
-import type { Entity } from "../entities/Entity";
+import type { Sprite } from "pixi.js";
+import type { Direction, Entity } from "../entities/Entity";
+import type { PackedVehicle } from "../systems/Vehicles";
 
 export enum TrafficPhase {
   NS_GREEN = "NS_GREEN",
@@ -12,12 +14,27 @@ export enum TrafficPhase {
   ALL_RED_3 = "ALL_RED_3",
 }
 
+export interface VehicleFrame {
+  id: number;
+  vehicles: PackedVehicle[];
+}
+
+export interface DrawnVehicle {
+  sprite: Sprite;
+  variant: string;
+  direction: Direction | null;
+}
+
 export interface GameState {
   trafficPhase: TrafficPhase;
   phaseElapsed: number; // seconds elapsed in current phase
-  entities: Map<number, Entity>;
+  entities: Map<number, Entity>; // pedestrians; vehicles come from the backend
   nextEntityId: number;
+  vehicles: Map<number, DrawnVehicle>; // by backend vehicle id
+  pendingFrame: VehicleFrame | null; // newest `sim-tick` not drawn yet
+  drawnFrameId: number | null; // drawn but not acknowledged yet
   timeOfDay: number; // 0-24 hours (fractional)
+  tint: string | null; // resolved by the backend, which then owns timeOfDay
 }
 
 export function createGameState(): GameState {
@@ -26,6 +43,10 @@ export function createGameState(): GameState {
     phaseElapsed: 0,
     entities: new Map(),
     nextEntityId: 1,
+    vehicles: new Map(),
+    pendingFrame: null,
+    drawnFrameId: null,
     timeOfDay: 12, // start at noon
+    tint: null,
   };
 }
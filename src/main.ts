@@ -1,7 +1,8 @@
-import { Application, Assets, Graphics, Spritesheet, TextureSource } from "pixi.js";
+import { Application, Assets, Graphics, Spritesheet, TextureSource, UPDATE_PRIORITY } from "pixi.js";
 import { buildScene } from "./scene/SceneBuilder";
 import { createGameState } from "./game/GameState";
 import { createGameLoop } from "./game/GameLoop";
+import { decodeVehicles } from "./systems/Vehicles";
 import { SCENE_W, SCENE_H } from "./scene/Road";
 
 // Force nearest-neighbor scaling for pixel art
@@ -96,74 +97,150 @@ async function init() {
   // Window dragging & full-width resize (Tauri)
   try {
     const { getCurrentWindow } = await import("@tauri-apps/api/window");
+    const { invoke } = await import("@tauri-apps/api/core");
     const win = getCurrentWindow();
+    const savedPosition = await invoke<{ x: number; y: number } | null>(
+      "saved_window_position"
+    );
 
-    // Resize window to full screen width, keeping 2:1 aspect ratio
-    try {
-      const { PhysicalSize, PhysicalPosition } = await import("@tauri-apps/api/dpi");
-      const { primaryMonitor, currentMonitor } = await import("@tauri-apps/api/window");
-
-      // Try multiple approaches to get screen size
-      const monitor = await primaryMonitor() ?? await currentMonitor();
-      debugLog(`monitor: ${JSON.stringify(monitor ? { w: monitor.size.width, h: monitor.size.height, scale: monitor.scaleFactor } : null)}`);
-      debugLog(`window.screen: ${window.screen.width}x${window.screen.height}`);
-
-      let physW: number;
-      let physH: number;
-      if (monitor) {
-        physW = monitor.size.width;
-        physH = monitor.size.height;
-      } else {
-        // Fallback: use window.screen with device pixel ratio
-        const dpr = window.devicePixelRatio || 2;
-        physW = window.screen.width * dpr;
-        physH = window.screen.height * dpr;
+    // Spawned overlays are sized to their monitor by the backend and stay put
+    const isMain = win.label === "main";
+
+    if (isMain) {
+      // Without transparency the backend keeps a small opaque window instead
+      const opaque = await invoke<boolean>("is_transparency_unavailable");
+      if (!opaque) {
+        // Resize window to full screen width, keeping 2:1 aspect ratio
+        try {
+          const { PhysicalSize, PhysicalPosition } = await import("@tauri-apps/api/dpi");
+          const { primaryMonitor, currentMonitor } = await import("@tauri-apps/api/window");
+
+          // Try multiple approaches to get screen size
+          const monitor = await primaryMonitor() ?? await currentMonitor();
+          debugLog(`monitor: ${JSON.stringify(monitor ? { w: monitor.size.width, h: monitor.size.height, scale: monitor.scaleFactor } : null)}`);
+          debugLog(`window.screen: ${window.screen.width}x${window.screen.height}`);
+
+          let physW: number;
+          let physH: number;
+          if (monitor) {
+            physW = monitor.size.width;
+            physH = monitor.size.height;
+          } else {
+            // Fallback: use window.screen with device pixel ratio
+            const dpr = window.devicePixelRatio || 2;
+            physW = window.screen.width * dpr;
+            physH = window.screen.height * dpr;
+          }
+
+          const targetH = Math.round(physW * (SCENE_H / SCENE_W));
+          debugLog(`setting physical size: ${physW}x${targetH}`);
+
+          await win.setSize(new PhysicalSize(physW, targetH));
+          if (savedPosition) {
+            debugLog(`restored position: ${savedPosition.x},${savedPosition.y}`);
+          } else {
+            await win.setPosition(new PhysicalPosition(0, physH - targetH));
+            debugLog(`done: ${physW}x${targetH}, y=${physH - targetH}`);
+          }
+        } catch (e) {
+          debugLog(`resize error: ${e}`);
+        }
       }
 
-      const targetH = Math.round(physW * (SCENE_H / SCENE_W));
-      debugLog(`setting physical size: ${physW}x${targetH}`);
+      container.addEventListener("mousedown", async (e) => {
+        if (e.button === 0) {
+          await win.startDragging();
+        }
+      });
 
-      await win.setSize(new PhysicalSize(physW, targetH));
-      await win.setPosition(new PhysicalPosition(0, physH - targetH));
-      debugLog(`done: ${physW}x${targetH}, y=${physH - targetH}`);
-    } catch (e) {
-      debugLog(`resize error: ${e}`);
+      // Snap to nearby screen edges and persist the position once dragging
+      // settles. A snap moves the window again, which saves the snapped spot.
+      let moveTimer: ReturnType<typeof setTimeout> | undefined;
+      await win.onMoved(({ payload }) => {
+        clearTimeout(moveTimer);
+        moveTimer = setTimeout(async () => {
+          try {
+            await invoke("snap_if_near");
+            await invoke("save_window_position", { x: payload.x, y: payload.y });
+          } catch (e) {
+            debugLog(`save position error: ${e}`);
+          }
+        }, 250);
+      });
     }
 
-    container.addEventListener("mousedown", async (e) => {
-      if (e.button === 0) {
-        await win.startDragging();
-      }
+    const { listen } = await import("@tauri-apps/api/event");
+    // The backend owns click-through; the UI only mirrors it
+    let clickThrough = await invoke<boolean>("is_click_through");
+    debugLog(`click-through: ${clickThrough}`);
+
+    await listen<boolean>("toggle-click-through", ({ payload }) => {
+      clickThrough = payload;
+      debugLog(`click-through: ${clickThrough}`);
     });
 
-    const { listen } = await import("@tauri-apps/api/event");
-    let clickThrough = false;
+    // Repaint the backdrop when the theme changes
+    type Theme = { background_tint: string };
+    const paintTheme = (theme: Theme) => {
+      testRect.clear();
+      testRect.rect(0, 0, SCENE_W, SCENE_H);
+      testRect.fill({ color: theme.background_tint, alpha: 0.9 });
+    };
+    paintTheme(await invoke<Theme>("get_theme"));
+    await listen<Theme>("theme-changed", ({ payload }) => paintTheme(payload));
+
+    // Pan and zoom the scene to the backend's viewport
+    type Viewport = { x: number; y: number; zoom: number };
+    const applyViewport = ({ x, y, zoom }: Viewport) => {
+      layers.root.scale.set(zoom);
+      layers.root.position.set(-x * zoom, -y * zoom);
+    };
+    applyViewport(await invoke<Viewport>("get_viewport"));
+    await listen<Viewport>("viewport-changed", ({ payload }) => applyViewport(payload));
 
-    await listen("toggle-click-through", async () => {
-      clickThrough = !clickThrough;
-      await win.setIgnoreCursorEvents(clickThrough);
+    // Tint the scene for the backend's time of day
+    type TimeOfDay = { hour: number; tint: string };
+    const applyTimeOfDay = ({ hour, tint }: TimeOfDay) => {
+      state.timeOfDay = hour;
+      state.tint = tint;
+    };
+    await listen<TimeOfDay>("time-of-day-changed", ({ payload }) => applyTimeOfDay(payload));
+
+    // Failures the backend couldn't hand back through a rejected command
+    type AppError = { context: string; kind: string; message: string };
+    await listen<AppError>("app-error", ({ payload }) => {
+      debugLog(`${payload.context}: ${payload.message} (${payload.kind})`);
     });
 
-    await listen("reset-position", async () => {
-      await win.setPosition(
-        new (await import("@tauri-apps/api/dpi")).PhysicalPosition(100, 100)
-      );
+    // Vehicles come from the backend's simulation; only the newest frame is
+    // kept for the next draw
+    await listen<{ id: number; vehicles: string }>("sim-tick", ({ payload }) => {
+      state.pendingFrame = { id: payload.id, vehicles: decodeVehicles(payload.vehicles) };
     });
 
-    await listen("toggle-autostart", async () => {
-      try {
-        const { enable, disable, isEnabled } = await import(
-          "@tauri-apps/plugin-autostart"
-        );
-        if (await isEnabled()) {
-          await disable();
-        } else {
-          await enable();
-        }
-      } catch (e) {
-        debugLog(`autostart error: ${e}`);
-      }
+    // Acknowledge a frame once it's on screen, so the backend only sends as
+    // many as we keep up with. Runs after the ticker's render.
+    app.ticker.add(() => {
+      if (state.drawnFrameId === null) return;
+      invoke("ack_frame", { frameId: state.drawnFrameId }).catch(() => {});
+      state.drawnFrameId = null;
+    }, undefined, UPDATE_PRIORITY.UTILITY);
+
+    // Answer the backend's heartbeat so it can tell if the webview hangs
+    if (isMain) {
+      await listen<number>("ping", ({ payload }) => {
+        invoke("pong", { timestamp: payload }).catch(() => {});
+      });
+    }
+
+    // The backend holds the simulation back until we're listening, then
+    // replays its state
+    await listen<{ viewport: Viewport; time_of_day: TimeOfDay }>("sim-init", ({ payload }) => {
+      applyViewport(payload.viewport);
+      applyTimeOfDay(payload.time_of_day);
+      debugLog("simulation started");
     });
+    await invoke("frontend_ready");
 
     debugLog("Tauri APIs wired");
   } catch {
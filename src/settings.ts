@@ -0,0 +1,129 @@
+// Settings window: one field per setting, generated from whatever
+// `get_settings` returns so new settings show up without changes here.
+import { invoke } from "@tauri-apps/api/core";
+
+type Settings = Record<string, unknown>;
+type Capabilities = Record<string, boolean>;
+
+// Settings that only do anything with a subsystem this build may lack, by
+// the `capabilities` field saying whether it has it.
+const requires: Record<string, string> = {
+  audio_reactive: "audio_reactive",
+  audio_sensitivity: "audio_reactive",
+  network_driven: "network_driven",
+  notifications_enabled: "notifications",
+  toggle_shortcut: "global_shortcuts",
+  next_preset_shortcut: "global_shortcuts",
+  previous_preset_shortcut: "global_shortcuts",
+  nudge_modifier: "global_shortcuts",
+};
+
+const form = document.getElementById("settings") as HTMLFormElement;
+const status = document.getElementById("status") as HTMLSpanElement;
+const reload = document.getElementById("reload") as HTMLButtonElement;
+
+let loaded: Settings = {};
+let capabilities: Capabilities = {};
+
+function setStatus(message: string, error = false) {
+  status.textContent = message;
+  status.classList.toggle("error", error);
+}
+
+// An input suited to the value's type; objects, arrays and nulls are
+// edited as JSON.
+function field(value: unknown): HTMLInputElement | HTMLTextAreaElement {
+  if (typeof value === "boolean") {
+    const input = document.createElement("input");
+    input.type = "checkbox";
+    input.checked = value;
+    return input;
+  }
+  if (typeof value === "number") {
+    const input = document.createElement("input");
+    input.type = "number";
+    input.step = "any";
+    input.value = String(value);
+    return input;
+  }
+  if (typeof value === "string") {
+    const input = document.createElement("input");
+    input.value = value;
+    return input;
+  }
+  const textarea = document.createElement("textarea");
+  textarea.value = JSON.stringify(value, null, 2);
+  textarea.rows = Math.min(textarea.value.split("\n").length, 12);
+  return textarea;
+}
+
+function render(settings: Settings) {
+  form.replaceChildren();
+  for (const [key, value] of Object.entries(settings)) {
+    // Bookkeeping rather than something to edit.
+    if (key === "version") continue;
+    // Hidden rather than offered when they'd do nothing; saving keeps them.
+    const needs = requires[key];
+    if (needs && capabilities[needs] === false) continue;
+    const label = document.createElement("label");
+    label.textContent = key;
+    label.htmlFor = key;
+    const input = field(value);
+    input.id = key;
+    input.name = key;
+    form.append(label, input);
+  }
+}
+
+function collect(): Settings {
+  const settings: Settings = { ...loaded };
+  for (const key of Object.keys(loaded)) {
+    const input = form.elements.namedItem(key) as HTMLInputElement | HTMLTextAreaElement | null;
+    if (!input) continue;
+    const original = loaded[key];
+    if (typeof original === "boolean") {
+      settings[key] = (input as HTMLInputElement).checked;
+    } else if (typeof original === "number") {
+      const value = Number(input.value);
+      if (input.value.trim() === "" || !Number.isFinite(value)) {
+        throw new Error(`${key} must be a number`);
+      }
+      settings[key] = value;
+    } else if (typeof original === "string") {
+      settings[key] = input.value;
+    } else {
+      try {
+        settings[key] = JSON.parse(input.value);
+      } catch {
+        throw new Error(`${key} isn't valid JSON`);
+      }
+    }
+  }
+  return settings;
+}
+
+async function load() {
+  try {
+    capabilities = await invoke<Capabilities>("capabilities");
+    loaded = await invoke<Settings>("get_settings");
+    render(loaded);
+    setStatus("");
+  } catch (e) {
+    setStatus(`Couldn't load settings: ${e}`, true);
+  }
+}
+
+form.addEventListener("submit", async (event) => {
+  event.preventDefault();
+  try {
+    const settings = collect();
+    await invoke("set_settings", { new: settings });
+    loaded = settings;
+    setStatus("Saved");
+  } catch (e) {
+    setStatus(e instanceof Error ? e.message : String(e), true);
+  }
+});
+reload.addEventListener("click", load);
+
+load();
@@ -16,7 +16,7 @@ function getTimePeriod(hour: number): TimePeriod {
 }
 
 export function updateLighting(state: GameState, layers: SceneLayers) {
-  const period = getTimePeriod(state.timeOfDay);
+  const period = state.tint ?? getTimePeriod(state.timeOfDay);
 
   if (period === lastPeriod) return;
   lastPeriod = period;
@@ -28,6 +28,15 @@ export function updateLighting(state: GameState, layers: SceneLayers) {
 
   filter.reset();
 
+  if (state.tint !== null) {
+    // Multiply by the backend's "#rrggbb" tint
+    const rgb = parseInt(state.tint.slice(1), 16);
+    filter.matrix[0] = ((rgb >> 16) & 0xff) / 255;
+    filter.matrix[6] = ((rgb >> 8) & 0xff) / 255;
+    filter.matrix[12] = (rgb & 0xff) / 255;
+    return;
+  }
+
   switch (period) {
     case "sunrise":
       // Warm orange tint
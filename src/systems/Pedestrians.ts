@@ -1,31 +1,15 @@
 import { Sprite, Spritesheet } from "pixi.js";
 import { GameState, TrafficPhase } from "../game/GameState";
 import type { SceneLayers } from "../scene/SceneBuilder";
-import type { Entity, Direction, Waypoint } from "../entities/Entity";
-import {
-  SCENE_W, SCENE_H, nsLaneX, ewLaneY,
-  NS_ROAD_LEFT, NS_ROAD_RIGHT, EW_ROAD_TOP, EW_ROAD_BOTTOM,
-} from "../scene/Road";
-import { computeDirection } from "./Movement";
+import type { Entity, PedDirection, Waypoint } from "../entities/Entity";
+import { SCENE_W, SCENE_H } from "../scene/Road";
+import { canPedestriansGo, isPedestrianFlashing } from "./TrafficLight";
 
 // Spawn timers
-let vehicleTimer = 0;
-let cyclistTimer = 0;
 let pedTimer = 0;
 let crosserBurstSpawned = false;
 let lastPhaseForBurst: TrafficPhase | null = null;
 
-// Vehicle type weights
-const VEHICLE_TYPES = [
-  { type: "sedan", weight: 50 },
-  { type: "taxi", weight: 20 },
-  { type: "bus", weight: 10 },
-  { type: "kei_truck", weight: 15 },
-  { type: "police", weight: 5 },
-];
-
-const SEDAN_COLORS = ["red", "blue", "white", "black"];
-
 function weightedRandom<T extends { weight: number }>(items: T[]): T {
   const total = items.reduce((s, i) => s + i.weight, 0);
   let r = Math.random() * total;
@@ -36,126 +20,138 @@ function weightedRandom<T extends { weight: number }>(items: T[]): T {
   return items[items.length - 1];
 }
 
-function spawnVehicle(state: GameState, layers: SceneLayers, sheet: Spritesheet) {
-  const dir: Direction = (["n", "s", "e", "w"] as const)[Math.floor(Math.random() * 4)];
-  const vType = weightedRandom(VEHICLE_TYPES).type;
-
-  // Pick lane (0-1 for one direction, 2-3 for opposite)
-  let lane: number;
-  if (dir === "s") lane = Math.random() < 0.5 ? 0 : 1;
-  else if (dir === "n") lane = Math.random() < 0.5 ? 2 : 3;
-  else if (dir === "e") lane = Math.random() < 0.5 ? 0 : 1;
-  else lane = Math.random() < 0.5 ? 2 : 3;
-
-  // Starting position (off screen)
-  let x: number, y: number;
-  if (dir === "s") { x = nsLaneX(lane); y = -40; }
-  else if (dir === "n") { x = nsLaneX(lane); y = SCENE_H + 40; }
-  else if (dir === "e") { x = -40; y = ewLaneY(lane); }
-  else { x = SCENE_W + 40; y = ewLaneY(lane); }
-
-  // Check for too-close existing vehicles
-  for (const [, e] of state.entities) {
-    if (e.type !== "vehicle") continue;
-    if (e.direction !== dir) continue;
-    const dist = Math.abs(e.x - x) + Math.abs(e.y - y);
-    if (dist < 40) return; // too close, skip spawn
-  }
+function isOffScreen(entity: Entity): boolean {
+  const margin = 60;
+  return entity.x < -margin || entity.x > SCENE_W + margin ||
+         entity.y < -margin || entity.y > SCENE_H + margin;
+}
 
-  let frameName: string;
-  if (vType === "sedan") {
-    const color = SEDAN_COLORS[Math.floor(Math.random() * SEDAN_COLORS.length)];
-    frameName = `sedan_${color}_${dir}`;
-  } else {
-    frameName = `${vType}_${dir}`;
+// ---- Direction helper ----
+
+/** Map a dx/dy vector to one of 8 PedDirection values using atan2. */
+function computeDirection(dx: number, dy: number): PedDirection {
+  const angle = Math.atan2(dy, dx); // radians, 0 = east, π/2 = south
+  // Divide into 8 sectors of π/4
+  const sector = Math.round(angle / (Math.PI / 4));
+  switch (((sector % 8) + 8) % 8) {
+    case 0: return "e";
+    case 1: return "se";
+    case 2: return "s";
+    case 3: return "sw";
+    case 4: return "w";
+    case 5: return "nw";
+    case 6: return "n";
+    case 7: return "ne";
+    default: return "s";
   }
+}
 
-  const texture = sheet.textures[frameName];
-  if (!texture) return;
-
-  const sprite = new Sprite(texture);
-  sprite.anchor.set(0.5, 0.5);
-  sprite.x = x;
-  sprite.y = y;
-  sprite.zIndex = y;
-  layers.vehicle.addChild(sprite);
-
-  // Cruise speed in px/s with slight per-vehicle variance
-  const baseCruise = vType === "bus" ? 35 : vType === "kei_truck" ? 40 : 50;
-  const cruiseSpeed = baseCruise + (Math.random() - 0.5) * 4;
-
-  // Length along direction of travel: height for N/S, width for E/W
-  const length = (dir === "n" || dir === "s") ? texture.height : texture.width;
+// ---- Pedestrian (waypoint-based path following) ----
 
-  const entity: Entity = {
-    id: state.nextEntityId++,
-    type: "vehicle",
-    x, y,
-    currentSpeed: cruiseSpeed,
-    cruiseSpeed,
-    direction: dir,
-    sprite,
-    state: "moving",
-    speedState: "cruising",
-    variant: vType,
-    animFrame: 0,
-    animTimer: 0,
-    lane,
-    length,
-  };
-
-  state.entities.set(entity.id, entity);
-}
+function updatePedestrian(entity: Entity, state: GameState, dt: number, sheet: Spritesheet) {
+  const path = entity.path;
+  if (!path || entity.pathIndex === undefined) {
+    entity.state = "despawning";
+    return;
+  }
 
-function spawnCyclist(state: GameState, layers: SceneLayers, sheet: Spritesheet) {
-  const dir: Direction = (["n", "s", "e", "w"] as const)[Math.floor(Math.random() * 4)];
-  const variant = Math.random() < 0.7 ? "commuter" : "delivery";
+  // Reached end of path
+  if (entity.pathIndex >= path.length) {
+    entity.state = "despawning";
+    return;
+  }
 
-  // Cyclists use curb lane (lane 0 or 3)
-  let lane: number;
-  if (dir === "s") lane = 0;
-  else if (dir === "n") lane = 3;
-  else if (dir === "e") lane = 0;
-  else lane = 3;
+  const target = path[entity.pathIndex];
+  const wasWaiting = entity.state === "waiting";
+
+  entity.state = "moving";
+
+  const dx = target.x - entity.x;
+  const dy = target.y - entity.y;
+  const dist = Math.sqrt(dx * dx + dy * dy);
+
+  // Arrived at waypoint
+  if (dist < 3) {
+    // If this is a wait waypoint, hold here until signal allows crossing.
+    // Don't start crossing during SCRAMBLE_FLASH if already waiting.
+    if (target.waitForSignal) {
+      if (!canPedestriansGo(state) || (isPedestrianFlashing(state) && wasWaiting)) {
+        entity.state = "waiting";
+        entity.sprite.x = entity.x;
+        entity.sprite.y = entity.y;
+        entity.sprite.zIndex = entity.y;
+        return;
+      }
+    }
 
-  let x: number, y: number;
-  if (dir === "s") { x = nsLaneX(lane); y = -24; }
-  else if (dir === "n") { x = nsLaneX(lane); y = SCENE_H + 24; }
-  else if (dir === "e") { x = -24; y = ewLaneY(lane); }
-  else { x = SCENE_W + 24; y = ewLaneY(lane); }
+    entity.pathIndex++;
+    // Check if path is complete
+    if (entity.pathIndex >= path.length) {
+      entity.state = "despawning";
+      return;
+    }
+    // Recompute direction for next segment
+    const next = path[entity.pathIndex];
+    const ndx = next.x - entity.x;
+    const ndy = next.y - entity.y;
+    const newDir = computeDirection(ndx, ndy);
+    if (newDir !== entity.direction) {
+      entity.direction = newDir;
+      // Update sprite texture for new direction
+      const frameName = `ped_${entity.variant}_${newDir}_${entity.animFrame}`;
+      const tex = sheet.textures[frameName];
+      if (tex) {
+        (entity.sprite as Sprite).texture = tex;
+      }
+    }
+    return;
+  }
 
-  const frameName = `cyclist_${variant}_${dir}_0`;
-  const texture = sheet.textures[frameName];
-  if (!texture) return;
+  let speed = entity.cruiseSpeed;
+  if (isPedestrianFlashing(state)) {
+    speed *= 1.3;
+  }
 
-  const sprite = new Sprite(texture);
-  sprite.anchor.set(0.5, 0.5);
-  sprite.x = x;
-  sprite.y = y;
-  sprite.zIndex = y;
-  layers.vehicle.addChild(sprite);
+  const nx = dx / dist;
+  const ny = dy / dist;
+  entity.x += nx * speed * dt;
+  entity.y += ny * speed * dt;
+
+  // Animation
+  entity.animTimer += dt;
+  if (entity.animTimer > 0.15) {
+    entity.animTimer = 0;
+    const prevFrame = entity.animFrame;
+    entity.animFrame = (entity.animFrame + 1) % 4;
+    // Update texture on frame change
+    if (entity.animFrame !== prevFrame) {
+      const frameName = `ped_${entity.variant}_${entity.direction}_${entity.animFrame}`;
+      const tex = sheet.textures[frameName];
+      if (tex) {
+        (entity.sprite as Sprite).texture = tex;
+      }
+    }
+  }
 
-  const length = (dir === "n" || dir === "s") ? texture.height : texture.width;
-  const cruiseSpeed = 30 + (Math.random() - 0.5) * 4;
+  // Update direction based on movement
+  const newDir = computeDirection(dx, dy);
+  if (newDir !== entity.direction) {
+    entity.direction = newDir;
+    const frameName = `ped_${entity.variant}_${newDir}_${entity.animFrame}`;
+    const tex = sheet.textures[frameName];
+    if (tex) {
+      (entity.sprite as Sprite).texture = tex;
+    }
+  }
 
-  const entity: Entity = {
-    id: state.nextEntityId++,
-    type: "cyclist",
-    x, y,
-    currentSpeed: cruiseSpeed,
-    cruiseSpeed,
-    direction: dir,
-    sprite,
-    state: "moving",
-    speedState: "cruising",
-    variant,
-    animFrame: 0,
-    animTimer: 0,
-    length,
-    lane,
-  };
+  entity.sprite.x = entity.x;
+  entity.sprite.y = entity.y;
+  entity.sprite.zIndex = entity.y;
 
-  state.entities.set(entity.id, entity);
+  // Off-screen safety net
+  if (isOffScreen(entity)) {
+    entity.state = "despawning";
+  }
 }
 
 // ---- Pedestrian path system ----
@@ -432,11 +428,7 @@ function generatePedestrianPath(): Waypoint[] {
 /** Spawn a single pedestrian with a waypoint path */
 function spawnPedestrian(state: GameState, layers: SceneLayers, sheet: Spritesheet, pathOverride?: Waypoint[]) {
   // Cap total pedestrians
-  let pedCount = 0;
-  for (const [, e] of state.entities) {
-    if (e.type === "pedestrian") pedCount++;
-  }
-  if (pedCount >= MAX_PEDESTRIANS) return;
+  if (state.entities.size >= MAX_PEDESTRIANS) return;
 
   const path = pathOverride ?? generatePedestrianPath();
   if (path.length < 2) return;
@@ -473,7 +465,6 @@ function spawnPedestrian(state: GameState, layers: SceneLayers, sheet: Spriteshe
     direction: dir,
     sprite,
     state: "moving",
-    speedState: "cruising",
     variant,
     animFrame: 0,
     animTimer: 0,
@@ -505,26 +496,14 @@ function spawnCrosserBurst(state: GameState, layers: SceneLayers, sheet: Sprites
   }
 }
 
-export function updateSpawner(
+// ---- Public entry point ----
+
+export function updatePedestrians(
   state: GameState,
   dt: number,
   layers: SceneLayers,
   sheet: Spritesheet
 ) {
-  // Vehicle spawning
-  vehicleTimer += dt;
-  if (vehicleTimer >= 2 + Math.random()) {
-    vehicleTimer = 0;
-    spawnVehicle(state, layers, sheet);
-  }
-
-  // Cyclist spawning
-  cyclistTimer += dt;
-  if (cyclistTimer >= 8 + Math.random() * 2) {
-    cyclistTimer = 0;
-    spawnCyclist(state, layers, sheet);
-  }
-
   // Continuous pedestrian spawning
   pedTimer += dt;
   if (pedTimer >= 1.2 + Math.random() * 0.5) {
@@ -541,4 +520,22 @@ export function updateSpawner(
     crosserBurstSpawned = true;
     spawnCrosserBurst(state, layers, sheet);
   }
+
+  const toRemove: number[] = [];
+
+  for (const [id, entity] of state.entities) {
+    updatePedestrian(entity, state, dt, sheet);
+    if (entity.state === "despawning") {
+      toRemove.push(id);
+    }
+  }
+
+  for (const id of toRemove) {
+    const entity = state.entities.get(id);
+    if (entity) {
+      entity.sprite.removeFromParent();
+      entity.sprite.destroy();
+      state.entities.delete(id);
+    }
+  }
 }
@@ -1,6 +1,5 @@
 import { Container, Sprite, Spritesheet } from "pixi.js";
 import { GameState, TrafficPhase } from "../game/GameState";
-import type { Direction } from "../entities/Entity";
 import {
   NS_ROAD_LEFT, NS_ROAD_RIGHT, EW_ROAD_TOP, EW_ROAD_BOTTOM,
   ZEBRA_WIDTH, ZEBRA_WIDTH_N, TILE
@@ -32,22 +31,6 @@ const PHASE_ORDER: TrafficPhase[] = [
 ];
 
 // Query methods
-export function canVehicleGo(state: GameState, dir: Direction): boolean {
-  const p = state.trafficPhase;
-  if (dir === "n" || dir === "s") {
-    return p === TrafficPhase.NS_GREEN;
-  }
-  return p === TrafficPhase.EW_GREEN;
-}
-
-export function shouldVehicleSlow(state: GameState, dir: Direction): boolean {
-  const p = state.trafficPhase;
-  if (dir === "n" || dir === "s") {
-    return p === TrafficPhase.NS_YELLOW;
-  }
-  return p === TrafficPhase.EW_YELLOW;
-}
-
 export function canPedestriansGo(state: GameState): boolean {
   return state.trafficPhase === TrafficPhase.SCRAMBLE;
 }
@@ -0,0 +1,101 @@
+import { Sprite, Spritesheet } from "pixi.js";
+import type { GameState, VehicleFrame } from "../game/GameState";
+import type { SceneLayers } from "../scene/SceneBuilder";
+import type { Direction } from "../entities/Entity";
+
+// Layout of a vehicle in a packed `sim-tick` frame (see frame.rs)
+const VEHICLE_BYTES = 22;
+const DIRECTIONS: Direction[] = ["n", "s", "e", "w"];
+
+// Vehicle type weights
+const VEHICLE_TYPES = [
+  { type: "sedan", weight: 50 },
+  { type: "taxi", weight: 20 },
+  { type: "bus", weight: 10 },
+  { type: "kei_truck", weight: 15 },
+  { type: "police", weight: 5 },
+];
+const TOTAL_WEIGHT = VEHICLE_TYPES.reduce((s, t) => s + t.weight, 0);
+
+const SEDAN_COLORS = ["red", "blue", "white", "black"];
+
+export interface PackedVehicle {
+  id: number;
+  x: number;
+  y: number;
+  direction: Direction;
+}
+
+/** Unpacks the base64 `vehicles` buffer of a `sim-tick` frame. */
+export function decodeVehicles(base64: string): PackedVehicle[] {
+  const raw = atob(base64);
+  const bytes = new Uint8Array(raw.length);
+  for (let i = 0; i < raw.length; i++) bytes[i] = raw.charCodeAt(i);
+  const view = new DataView(bytes.buffer);
+
+  const vehicles: PackedVehicle[] = [];
+  for (let at = 0; at + VEHICLE_BYTES <= bytes.length; at += VEHICLE_BYTES) {
+    vehicles.push({
+      id: view.getUint32(at, true),
+      x: view.getFloat32(at + 4, true),
+      y: view.getFloat32(at + 8, true),
+      // vx and vy at +12 and +16 aren't needed to draw
+      direction: DIRECTIONS[view.getUint8(at + 20)] ?? "e",
+    });
+  }
+  return vehicles;
+}
+
+/** The sprite family for a vehicle, picked from its id so it never changes. */
+function variantOf(id: number): string {
+  // Knuth's multiplicative hash spreads consecutive ids across the weights
+  const hash = Math.imul(id, 2654435761) >>> 0;
+  let r = hash % TOTAL_WEIGHT;
+  for (const { type, weight } of VEHICLE_TYPES) {
+    r -= weight;
+    if (r < 0) {
+      return type === "sedan" ? `sedan_${SEDAN_COLORS[(hash >>> 8) % SEDAN_COLORS.length]}` : type;
+    }
+  }
+  return VEHICLE_TYPES[0].type;
+}
+
+/** Moves the vehicle sprites to `frame`, adding and removing them to match. */
+function drawFrame(state: GameState, frame: VehicleFrame, layers: SceneLayers, sheet: Spritesheet) {
+  const seen = new Set<number>();
+  for (const vehicle of frame.vehicles) {
+    seen.add(vehicle.id);
+    let drawn = state.vehicles.get(vehicle.id);
+    if (!drawn) {
+      const sprite = new Sprite();
+      sprite.anchor.set(0.5, 0.5);
+      layers.vehicle.addChild(sprite);
+      drawn = { sprite, variant: variantOf(vehicle.id), direction: null };
+      state.vehicles.set(vehicle.id, drawn);
+    }
+    if (drawn.direction !== vehicle.direction) {
+      drawn.direction = vehicle.direction;
+      const texture = sheet.textures[`${drawn.variant}_${vehicle.direction}`];
+      if (texture) drawn.sprite.texture = texture;
+    }
+    drawn.sprite.x = vehicle.x;
+    drawn.sprite.y = vehicle.y;
+    drawn.sprite.zIndex = vehicle.y;
+  }
+
+  for (const [id, drawn] of state.vehicles) {
+    if (seen.has(id)) continue;
+    drawn.sprite.removeFromParent();
+    drawn.sprite.destroy();
+    state.vehicles.delete(id);
+  }
+}
+
+/** Draws the newest frame from the backend, if one came in since the last tick. */
+export function updateVehicles(state: GameState, layers: SceneLayers, sheet: Spritesheet) {
+  const frame = state.pendingFrame;
+  if (!frame) return;
+  state.pendingFrame = null;
+  drawFrame(state, frame, layers, sheet);
+  state.drawnFrameId = frame.id;
+}
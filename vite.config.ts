@@ -4,6 +4,12 @@ const host = process.env.TAURI_DEV_HOST;
 
 export default defineConfig(async () => ({
   clearScreen: false,
+  build: {
+    rollupOptions: {
+      // The overlay and the settings window are separate pages.
+      input: ["index.html", "settings.html"],
+    },
+  },
   server: {
     port: 5173,
     strictPort: true,